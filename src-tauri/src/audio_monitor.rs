@@ -0,0 +1,187 @@
+//! Reacting to PulseAudio/PipeWire events
+//!
+//! A background task follows `pactl subscribe` and, whenever a sink
+//! changes, compares its active port with the last one seen. A changed
+//! port (e.g. headphones plugged into the jack) is emitted to the frontend
+//! as `port-changed`. Sink and server changes also drop the cached volume,
+//! so changes made by other apps or hardware keys are read back at once
+//! instead of after the cache expires. If pactl exits, for instance because
+//! the sound server restarted, the subscription is re-established after a
+//! short delay; if pactl cannot be started at all the task gives up.
+
+use crate::commands::{list_sinks_internal, tool_command};
+use crate::pactl::SinkInfo;
+use crate::state::{AppState, CacheDomain};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// Delay before resubscribing after `pactl subscribe` exits
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(2);
+
+/// Payload of the `port-changed` event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PortChange {
+    pub sink: String,
+    pub port: Option<String>,
+}
+
+/// Whether a `pactl subscribe` line reports a change to a sink
+///
+/// Lines look like `Event 'change' on sink #56`; sink inputs are excluded.
+pub fn is_sink_change(line: &str) -> bool {
+    line.trim()
+        .strip_prefix("Event 'change' on sink #")
+        .is_some_and(|index| index.parse::<u32>().is_ok())
+}
+
+/// Whether a `pactl subscribe` line can mean the default sink's volume or
+/// mute changed: a sink change, or a server change such as a new default
+pub fn is_volume_change(line: &str) -> bool {
+    is_sink_change(line) || line.trim().starts_with("Event 'change' on server")
+}
+
+/// Record the active ports of `sinks`, returning the ones that changed
+///
+/// Sinks seen for the first time are recorded without being reported.
+pub fn changed_ports(
+    known: &mut HashMap<String, Option<String>>,
+    sinks: &[SinkInfo],
+) -> Vec<PortChange> {
+    let mut changes = Vec::new();
+
+    for sink in sinks {
+        let previous = known.insert(sink.name.clone(), sink.active_port.clone());
+        if previous.is_some_and(|port| port != sink.active_port) {
+            changes.push(PortChange {
+                sink: sink.name.clone(),
+                port: sink.active_port.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+/// Start following sound server events
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut known = HashMap::new();
+
+        loop {
+            if let Ok(sinks) = list_sinks_internal().await {
+                changed_ports(&mut known, &sinks);
+            }
+            if !follow_events(&app, &mut known).await {
+                break;
+            }
+            tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+        }
+    });
+}
+
+/// Run one `pactl subscribe` session until it exits
+///
+/// Returns `false` if pactl could not be started.
+async fn follow_events(app: &AppHandle, known: &mut HashMap<String, Option<String>>) -> bool {
+    let child = tool_command("pactl")
+        .arg("subscribe")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            warn!("Cannot subscribe to audio events: {}", e);
+            return false;
+        }
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return false;
+    };
+    info!("Following audio events");
+
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if is_volume_change(&line) {
+            app.state::<AppState>().invalidate(CacheDomain::Volume);
+        }
+        if !is_sink_change(&line) {
+            continue;
+        }
+
+        let sinks = match list_sinks_internal().await {
+            Ok(sinks) => sinks,
+            Err(e) => {
+                debug!("Failed to list sinks after change event: {}", e);
+                continue;
+            }
+        };
+        for change in changed_ports(known, &sinks) {
+            info!("Sink {} switched to port {:?}", change.sink, change.port);
+            app.state::<AppState>().invalidate_all();
+            let _ = app.emit("port-changed", change);
+        }
+    }
+
+    debug!("pactl subscribe exited");
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sink(name: &str, port: Option<&str>) -> SinkInfo {
+        SinkInfo {
+            index: 0,
+            name: name.to_string(),
+            description: String::new(),
+            volume: 50,
+            muted: false,
+            base_volume: 100,
+            channels: Vec::new(),
+            ports: Vec::new(),
+            active_port: port.map(str::to_string),
+            sample_spec: None,
+        }
+    }
+
+    #[test]
+    fn test_is_sink_change() {
+        assert!(is_sink_change("Event 'change' on sink #56"));
+        assert!(!is_sink_change("Event 'change' on sink-input #112"));
+        assert!(!is_sink_change("Event 'new' on sink #57"));
+        assert!(!is_sink_change("Event 'change' on card #3"));
+    }
+
+    #[test]
+    fn test_is_volume_change() {
+        assert!(is_volume_change("Event 'change' on sink #56"));
+        assert!(is_volume_change("Event 'change' on server #-1"));
+        assert!(!is_volume_change("Event 'change' on sink-input #112"));
+        assert!(!is_volume_change("Event 'change' on source #3"));
+    }
+
+    #[test]
+    fn test_changed_ports() {
+        let mut known = HashMap::new();
+        assert!(changed_ports(&mut known, &[sink("a", Some("speaker"))]).is_empty());
+        assert!(changed_ports(&mut known, &[sink("a", Some("speaker"))]).is_empty());
+
+        let changes = changed_ports(&mut known, &[sink("a", Some("headphones")), sink("b", None)]);
+        assert_eq!(
+            changes,
+            vec![PortChange {
+                sink: "a".to_string(),
+                port: Some("headphones".to_string()),
+            }]
+        );
+    }
+}
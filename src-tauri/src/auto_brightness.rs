@@ -0,0 +1,194 @@
+//! Brightness following the ambient light sensor
+//!
+//! Laptops with an ambient light sensor expose it through the IIO
+//! subsystem. A background task samples it periodically and maps lux to a
+//! brightness percentage via the configured curve. Manual brightness
+//! changes pause the task for a configurable period.
+
+use crate::commands::{set_brightness_internal, BRIGHTNESS_FLOOR};
+use crate::config::{AutoBrightnessConfig, ConfigStore};
+use crate::error::{CCResult, ControlCentreError};
+use crate::state::AppState;
+use log::{debug, info, warn};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Root of the IIO device tree
+const IIO_DEVICES: &str = "/sys/bus/iio/devices";
+
+/// Incremented on every start/stop; a running task exits once it no longer
+/// matches the generation it was started with
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Time of the last manual brightness change
+static LAST_MANUAL_CHANGE: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// An ambient light sensor found under `/sys/bus/iio/devices`
+#[derive(Debug, Clone)]
+pub struct LightSensor {
+    raw: PathBuf,
+    scale: f64,
+    offset: f64,
+}
+
+impl LightSensor {
+    /// Find the first IIO device exposing illuminance
+    pub fn find() -> Option<Self> {
+        let entries = std::fs::read_dir(IIO_DEVICES).ok()?;
+
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|dir| {
+                dir.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("iio:device"))
+            })
+            .find_map(|dir| Self::from_device(&dir))
+    }
+
+    fn from_device(dir: &Path) -> Option<Self> {
+        let raw = ["in_illuminance_raw", "in_illuminance_input"]
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())?;
+
+        let read_f64 = |name: &str| {
+            std::fs::read_to_string(dir.join(name))
+                .ok()
+                .and_then(|s| s.trim().parse::<f64>().ok())
+        };
+
+        Some(Self {
+            raw,
+            scale: read_f64("in_illuminance_scale").unwrap_or(1.0),
+            offset: read_f64("in_illuminance_offset").unwrap_or(0.0),
+        })
+    }
+
+    /// Read the current illuminance in lux
+    pub fn read_lux(&self) -> CCResult<f64> {
+        let raw = std::fs::read_to_string(&self.raw)?;
+        let raw = raw
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| ControlCentreError::ParseError(e.to_string()))?;
+        Ok((raw + self.offset) * self.scale)
+    }
+}
+
+/// Map illuminance to a brightness percentage using a `[lux, percent]` curve
+///
+/// Values between points are interpolated linearly; values outside the
+/// curve use the nearest end point. The result is kept between the
+/// brightness floor and 100, whatever the curve says.
+pub fn lux_to_percent(curve: &[(f64, u8)], lux: f64) -> Option<u8> {
+    let first = curve.first()?;
+    let last = curve.last()?;
+
+    let percent = if lux <= first.0 {
+        f64::from(first.1)
+    } else if lux >= last.0 {
+        f64::from(last.1)
+    } else {
+        curve.windows(2).find_map(|pair| {
+            let (lo, hi) = (pair[0], pair[1]);
+            if lux < lo.0 || lux > hi.0 {
+                return None;
+            }
+            let span = hi.0 - lo.0;
+            let t = if span > 0.0 { (lux - lo.0) / span } else { 0.0 };
+            Some(f64::from(lo.1) + t * (f64::from(hi.1) - f64::from(lo.1)))
+        })?
+    };
+    Some(percent.round().clamp(f64::from(BRIGHTNESS_FLOOR), 100.0) as u8)
+}
+
+/// Record a manual brightness change so auto brightness backs off
+pub fn note_manual_change() {
+    if let Ok(mut last) = LAST_MANUAL_CHANGE.lock() {
+        *last = Some(Instant::now());
+    }
+}
+
+fn manual_override_active(period: Duration) -> bool {
+    LAST_MANUAL_CHANGE
+        .lock()
+        .ok()
+        .and_then(|last| *last)
+        .is_some_and(|at| at.elapsed() < period)
+}
+
+/// Start the background task, replacing any running one
+pub fn start(app: AppHandle) -> CCResult<()> {
+    let sensor = LightSensor::find().ok_or_else(|| {
+        ControlCentreError::CommandNotFound("No ambient light sensor found".to_string())
+    })?;
+
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    info!("Auto brightness started using {}", sensor.raw.display());
+
+    tauri::async_runtime::spawn(async move {
+        let mut last_applied = None;
+
+        while GENERATION.load(Ordering::SeqCst) == generation {
+            let mut config: AutoBrightnessConfig = app.state::<ConfigStore>().get().auto_brightness;
+            config.curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let period = Duration::from_secs(config.manual_override_secs);
+
+            if !manual_override_active(period) {
+                match sensor.read_lux() {
+                    Ok(lux) => {
+                        let target = lux_to_percent(&config.curve, lux);
+                        if let Some(percent) = target.filter(|p| Some(*p) != last_applied) {
+                            debug!("Ambient light {:.1} lux, brightness {}%", lux, percent);
+                            match set_brightness_internal(percent).await {
+                                Ok(()) => {
+                                    app.state::<AppState>().set_cached_brightness(percent);
+                                    last_applied = Some(percent);
+                                }
+                                Err(e) => warn!("Auto brightness failed to apply: {}", e),
+                            }
+                        }
+                    }
+                    Err(e) => warn!("Failed to read ambient light sensor: {}", e),
+                }
+            }
+
+            tokio::time::sleep(Duration::from_millis(config.interval_ms.max(100))).await;
+        }
+
+        debug!("Auto brightness task {} stopped", generation);
+    });
+
+    Ok(())
+}
+
+/// Stop the background task if it is running
+pub fn stop() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    info!("Auto brightness stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lux_to_percent() {
+        let curve = vec![(0.0, 10), (100.0, 50), (1000.0, 100)];
+        assert_eq!(lux_to_percent(&curve, -5.0), Some(10));
+        assert_eq!(lux_to_percent(&curve, 0.0), Some(10));
+        assert_eq!(lux_to_percent(&curve, 50.0), Some(30));
+        assert_eq!(lux_to_percent(&curve, 100.0), Some(50));
+        assert_eq!(lux_to_percent(&curve, 5000.0), Some(100));
+        assert_eq!(lux_to_percent(&[], 50.0), None);
+
+        // End points are clamped like interpolated values
+        let extreme = vec![(0.0, 0), (100.0, 255)];
+        assert_eq!(lux_to_percent(&extreme, 0.0), Some(1));
+        assert_eq!(lux_to_percent(&extreme, 500.0), Some(100));
+    }
+}
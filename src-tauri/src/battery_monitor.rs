@@ -0,0 +1,232 @@
+//! Low battery warnings
+//!
+//! A background task reads the batteries under `/sys/class/power_supply`
+//! periodically. When the charge falls to one of the configured thresholds
+//! while discharging, a notification is sent through `notify-send` and
+//! `battery-low` is emitted to the frontend. Each threshold fires once per
+//! crossing and is re-armed when the charge rises `REARM_MARGIN` above it
+//! again or the charger is plugged in. The first reading only sets the
+//! baseline, so starting on a low battery does not warn straight away.
+//! Machines without a battery never start the task.
+
+use crate::commands::tool_command;
+use crate::config::ConfigStore;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Root of the power supply tree
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// Points the charge must rise above a threshold before it warns again, so
+/// a reading wavering around the threshold does not repeat the warning
+const REARM_MARGIN: u8 = 3;
+
+/// A battery reading, averaged over all system batteries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryReading {
+    pub percent: u8,
+    pub discharging: bool,
+}
+
+/// Payload of the `battery-low` event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryLow {
+    pub percent: u8,
+    /// Threshold that was crossed
+    pub threshold: u8,
+}
+
+/// Find the system batteries, skipping those of peripherals
+///
+/// Mice and headsets report `scope` `Device`; system batteries set no
+/// scope or `System`.
+pub fn find_batteries() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(POWER_SUPPLY) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|dir| read_attr(dir, "type").as_deref() == Some("Battery"))
+        .filter(|dir| read_attr(dir, "scope").as_deref() != Some("Device"))
+        .collect()
+}
+
+fn read_attr(dir: &Path, name: &str) -> Option<String> {
+    std::fs::read_to_string(dir.join(name))
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+/// Read the charge of `batteries`
+///
+/// The machine counts as discharging when any battery is.
+pub fn read_batteries(batteries: &[PathBuf]) -> Option<BatteryReading> {
+    let capacities: Vec<u32> = batteries
+        .iter()
+        .filter_map(|dir| read_attr(dir, "capacity")?.parse().ok())
+        .collect();
+    if capacities.is_empty() {
+        return None;
+    }
+
+    let percent = capacities.iter().sum::<u32>() / capacities.len() as u32;
+    let discharging = batteries
+        .iter()
+        .any(|dir| read_attr(dir, "status").as_deref() == Some("Discharging"));
+
+    Some(BatteryReading {
+        percent: percent.min(100) as u8,
+        discharging,
+    })
+}
+
+/// Tracks which thresholds have already warned during the current discharge
+#[derive(Debug, Default)]
+pub struct ThresholdTracker {
+    warned: BTreeSet<u8>,
+    /// Whether the first reading, which never warns, has been seen
+    primed: bool,
+}
+
+impl ThresholdTracker {
+    /// Record a reading, returning the threshold to warn about if one was
+    /// newly reached
+    ///
+    /// When several thresholds are reached at once only the lowest is
+    /// reported.
+    pub fn update(&mut self, thresholds: &[u8], reading: BatteryReading) -> Option<u8> {
+        if !reading.discharging {
+            self.warned.clear();
+            self.primed = true;
+            return None;
+        }
+        self.warned
+            .retain(|threshold| reading.percent < threshold.saturating_add(REARM_MARGIN));
+
+        let reached: Vec<u8> = thresholds
+            .iter()
+            .copied()
+            .filter(|threshold| reading.percent <= *threshold)
+            .filter(|threshold| !self.warned.contains(threshold))
+            .collect();
+        self.warned.extend(&reached);
+        if !std::mem::replace(&mut self.primed, true) {
+            return None;
+        }
+        reached.into_iter().min()
+    }
+}
+
+/// Start watching the battery, unless there is none or warnings are off
+pub fn start(app: AppHandle) {
+    if !app.state::<ConfigStore>().get().battery_warning.enabled {
+        return;
+    }
+    let batteries = find_batteries();
+    if batteries.is_empty() {
+        debug!("No battery found, low battery warnings disabled");
+        return;
+    }
+    info!("Watching {} battery(s) for low charge", batteries.len());
+
+    tauri::async_runtime::spawn(async move {
+        let mut tracker = ThresholdTracker::default();
+
+        loop {
+            let config = app.state::<ConfigStore>().get().battery_warning;
+
+            if let Some(reading) = read_batteries(&batteries) {
+                if let Some(threshold) = tracker.update(&config.thresholds, reading) {
+                    warn!("Battery low: {}%", reading.percent);
+                    notify_low_battery(reading.percent).await;
+                    let _ = app.emit(
+                        "battery-low",
+                        BatteryLow {
+                            percent: reading.percent,
+                            threshold,
+                        },
+                    );
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(config.interval_secs.max(5))).await;
+        }
+    });
+}
+
+async fn notify_low_battery(percent: u8) {
+    let body = format!("{}% remaining", percent);
+    let status = tool_command("notify-send")
+        .args([
+            "--urgency=critical",
+            "--app-name=Control Centre",
+            "Battery low",
+            &body,
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+    if let Err(e) = status {
+        warn!("Failed to send low battery notification: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(percent: u8, discharging: bool) -> BatteryReading {
+        BatteryReading {
+            percent,
+            discharging,
+        }
+    }
+
+    #[test]
+    fn test_threshold_tracker() {
+        let thresholds = [15, 5];
+        let mut tracker = ThresholdTracker::default();
+
+        assert_eq!(tracker.update(&thresholds, reading(20, true)), None);
+        assert_eq!(tracker.update(&thresholds, reading(15, true)), Some(15));
+        assert_eq!(tracker.update(&thresholds, reading(14, true)), None);
+        assert_eq!(tracker.update(&thresholds, reading(4, true)), Some(5));
+        assert_eq!(tracker.update(&thresholds, reading(3, true)), None);
+
+        // Charging re-arms every threshold
+        assert_eq!(tracker.update(&thresholds, reading(3, false)), None);
+        assert_eq!(tracker.update(&thresholds, reading(3, true)), Some(5));
+    }
+
+    #[test]
+    fn test_threshold_tracker_rearms_above_threshold() {
+        let thresholds = [15];
+        let mut tracker = ThresholdTracker::default();
+
+        assert_eq!(tracker.update(&thresholds, reading(16, true)), None);
+        assert_eq!(tracker.update(&thresholds, reading(10, true)), Some(15));
+        // Wavering just above the threshold does not re-arm it
+        assert_eq!(tracker.update(&thresholds, reading(16, true)), None);
+        assert_eq!(tracker.update(&thresholds, reading(15, true)), None);
+        assert_eq!(tracker.update(&thresholds, reading(18, true)), None);
+        assert_eq!(tracker.update(&thresholds, reading(15, true)), Some(15));
+    }
+
+    #[test]
+    fn test_threshold_tracker_skips_first_reading() {
+        let thresholds = [15, 5];
+        let mut tracker = ThresholdTracker::default();
+
+        assert_eq!(tracker.update(&thresholds, reading(10, true)), None);
+        assert_eq!(tracker.update(&thresholds, reading(9, true)), None);
+        assert_eq!(tracker.update(&thresholds, reading(5, true)), Some(5));
+    }
+}
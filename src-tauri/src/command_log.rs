@@ -0,0 +1,362 @@
+//! Record of the most recent backend command invocations
+//!
+//! Every external tool run through the command helpers is noted here with
+//! its exit status, a truncated copy of its output and how long it took, so
+//! `get_command_log` can show in the panel why a backend call failed.
+//! Arguments that look like secrets (e.g. the password given to `nmcli`)
+//! are redacted before they are stored.
+//!
+//! Durations are also accumulated per tool for `get_backend_timings`, and
+//! an invocation slower than the `slow_command_ms` threshold is reported
+//! with a `slow-command` event, unless it is slow by design (see
+//! `SLOW_BY_DESIGN`). The log lives in `AppState`, so invocations are only
+//! recorded once the app is attached.
+
+use crate::state::AppState;
+use log::warn;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Number of invocations kept
+const CAPACITY: usize = 100;
+
+/// Longest output kept per invocation, in bytes
+const MAX_OUTPUT: usize = 512;
+
+/// Placeholder for redacted arguments
+const REDACTED: &str = "<redacted>";
+
+/// Argument names whose value is a secret, matched on the last `.`, `-` or
+/// `_` separated part (`password`, `--password`, `wifi-sec.psk`)
+const SECRET_KEYS: [&str; 5] = ["password", "passwd", "psk", "secret", "token"];
+
+/// Durations kept per tool for the averages
+const TIMING_HISTORY: usize = 20;
+
+/// Slow command threshold without a configured `slow_command_ms`
+const DEFAULT_SLOW_THRESHOLD_MS: u64 = 2000;
+
+/// Invocations that are slow by design, as a tool and a subcommand found
+/// among its arguments (empty for any); they are timed but never reported
+const SLOW_BY_DESIGN: [(&str, &[&str]); 7] = [
+    // DDC/CI round trips over I2C take up to a few seconds
+    ("ddcutil", &["getvcp"]),
+    ("ddcutil", &["setvcp"]),
+    ("ddcutil", &["detect"]),
+    // Pairing and association wait on the device or access point
+    ("bluetoothctl", &["connect"]),
+    ("nmcli", &["connection", "up"]),
+    ("nmcli", &["wifi", "connect"]),
+    // Waits for the user at the polkit password prompt
+    ("pkexec", &[]),
+];
+
+/// Duration from which an invocation counts as slow; 0 turns it off
+static SLOW_THRESHOLD_MS: AtomicU64 = AtomicU64::new(DEFAULT_SLOW_THRESHOLD_MS);
+
+/// App whose `AppState` keeps the log and that `slow-command` is emitted
+/// to, once the GUI is up
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// Recent invocations and per-tool timings, kept in `AppState`
+#[derive(Debug, Default)]
+pub struct CommandLog {
+    recent: Mutex<VecDeque<CommandLogEntry>>,
+    timings: Mutex<BTreeMap<String, TimingHistory>>,
+}
+
+/// One backend command invocation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CommandLogEntry {
+    pub command: String,
+    pub args: Vec<String>,
+    /// `None` when the command could not be started or was killed by a signal
+    pub exit_code: Option<i32>,
+    /// stdout on success, stderr (or the spawn error) on failure
+    pub output: String,
+    pub duration_ms: u64,
+}
+
+/// Latency of one tool, as returned by `get_backend_timings`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BackendTiming {
+    pub command: String,
+    /// Invocations since startup
+    pub runs: u64,
+    pub last_ms: u64,
+    /// Average of the last `TIMING_HISTORY` invocations
+    pub average_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Payload of `slow-command`
+#[derive(Debug, Clone, Serialize)]
+struct SlowCommand {
+    command: String,
+    duration_ms: u64,
+    threshold_ms: u64,
+}
+
+/// Recent durations of one tool
+#[derive(Debug, Default)]
+struct TimingHistory {
+    runs: u64,
+    durations: VecDeque<u64>,
+    max_ms: u64,
+}
+
+impl TimingHistory {
+    fn push(&mut self, duration_ms: u64) {
+        if self.durations.len() == TIMING_HISTORY {
+            self.durations.pop_front();
+        }
+        self.durations.push_back(duration_ms);
+        self.runs += 1;
+        self.max_ms = self.max_ms.max(duration_ms);
+    }
+
+    fn summary(&self, command: &str) -> Option<BackendTiming> {
+        let last_ms = *self.durations.back()?;
+        let total: u64 = self.durations.iter().sum();
+        Some(BackendTiming {
+            command: command.to_string(),
+            runs: self.runs,
+            last_ms,
+            average_ms: total / self.durations.len() as u64,
+            max_ms: self.max_ms,
+        })
+    }
+}
+
+/// Record invocations in `app`'s state and emit `slow-command` to it from now on
+pub fn attach(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// Set the slow command threshold from the `slow_command_ms` config key
+pub fn set_slow_threshold(threshold_ms: Option<u64>) {
+    SLOW_THRESHOLD_MS.store(
+        threshold_ms.unwrap_or(DEFAULT_SLOW_THRESHOLD_MS),
+        Ordering::Relaxed,
+    );
+}
+
+/// Note a finished invocation in the attached app's log
+pub fn record(
+    command: &str,
+    args: &[&str],
+    exit_code: Option<i32>,
+    output: &str,
+    duration: Duration,
+) {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    let duration_ms = duration.as_millis() as u64;
+    if let Some(state) = app.try_state::<AppState>() {
+        state
+            .command_log()
+            .push(command, args, exit_code, output, duration_ms);
+    }
+
+    let threshold_ms = SLOW_THRESHOLD_MS.load(Ordering::Relaxed);
+    if threshold_ms > 0 && duration_ms >= threshold_ms && !is_slow_by_design(command, args) {
+        warn!("{} took {}ms", command, duration_ms);
+        let slow = SlowCommand {
+            command: command.to_string(),
+            duration_ms,
+            threshold_ms,
+        };
+        let _ = app.emit("slow-command", slow);
+    }
+}
+
+fn is_slow_by_design(command: &str, args: &[&str]) -> bool {
+    SLOW_BY_DESIGN.iter().any(|(tool, subcommand)| {
+        *tool == command
+            && (subcommand.is_empty()
+                || args
+                    .windows(subcommand.len())
+                    .any(|words| words == *subcommand))
+    })
+}
+
+impl CommandLog {
+    fn push(
+        &self,
+        command: &str,
+        args: &[&str],
+        exit_code: Option<i32>,
+        output: &str,
+        duration_ms: u64,
+    ) {
+        if let Ok(mut timings) = self.timings.lock() {
+            timings
+                .entry(command.to_string())
+                .or_default()
+                .push(duration_ms);
+        }
+
+        let entry = CommandLogEntry {
+            command: command.to_string(),
+            args: scrub_args(args),
+            exit_code,
+            output: truncate_output(output.trim()),
+            duration_ms,
+        };
+        if let Ok(mut recent) = self.recent.lock() {
+            if recent.len() == CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(entry);
+        }
+    }
+
+    /// The most recent invocations, oldest first
+    pub fn recent(&self) -> Vec<CommandLogEntry> {
+        self.recent
+            .lock()
+            .map(|recent| recent.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Latency per tool, sorted by tool name
+    pub fn timings(&self) -> Vec<BackendTiming> {
+        self.timings
+            .lock()
+            .map(|timings| {
+                timings
+                    .iter()
+                    .filter_map(|(command, history)| history.summary(command))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Copy the arguments, redacting the value after a secret key and the
+/// value of `key=value` arguments with a secret key
+fn scrub_args(args: &[&str]) -> Vec<String> {
+    let mut scrubbed = Vec::with_capacity(args.len());
+    let mut redact_next = false;
+
+    for arg in args {
+        if redact_next {
+            scrubbed.push(REDACTED.to_string());
+            redact_next = false;
+        } else if let Some((key, _)) = arg.split_once('=').filter(|(key, _)| is_secret_key(key)) {
+            scrubbed.push(format!("{}={}", key, REDACTED));
+        } else {
+            redact_next = is_secret_key(arg);
+            scrubbed.push(arg.to_string());
+        }
+    }
+    scrubbed
+}
+
+fn is_secret_key(arg: &str) -> bool {
+    let key = arg.trim_start_matches('-').to_ascii_lowercase();
+    key.rsplit(['.', '-', '_'])
+        .next()
+        .is_some_and(|last| SECRET_KEYS.contains(&last))
+}
+
+fn truncate_output(output: &str) -> String {
+    if output.len() <= MAX_OUTPUT {
+        return output.to_string();
+    }
+
+    let mut end = MAX_OUTPUT;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &output[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub_args() {
+        let args = [
+            "device", "wifi", "connect", "Home", "password", "hunter2", "ifname", "wlan0",
+        ];
+        let scrubbed = scrub_args(&args);
+        assert_eq!(scrubbed[5], REDACTED);
+        assert_eq!(scrubbed[6], "ifname");
+
+        let scrubbed = scrub_args(&["connection", "modify", "Home", "wifi-sec.psk", "hunter2"]);
+        assert_eq!(scrubbed[4], REDACTED);
+
+        let scrubbed = scrub_args(&["--password=hunter2", "--display", "1"]);
+        assert_eq!(scrubbed, vec!["--password=<redacted>", "--display", "1"]);
+
+        // Only whole words count
+        assert_eq!(scrub_args(&["set-ntp", "true"]), vec!["set-ntp", "true"]);
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        assert_eq!(truncate_output("short"), "short");
+
+        let long = "é".repeat(MAX_OUTPUT);
+        let truncated = truncate_output(&long);
+        assert!(truncated.len() <= MAX_OUTPUT + '…'.len_utf8());
+        assert!(truncated.ends_with('…'));
+    }
+
+    #[test]
+    fn test_is_slow_by_design() {
+        let getvcp = ["--display", "1", "--brief", "getvcp", "10"];
+        assert!(is_slow_by_design("ddcutil", &getvcp));
+        assert!(is_slow_by_design("nmcli", &["connection", "up", "Home"]));
+        assert!(is_slow_by_design(
+            "pkexec",
+            &["brightnessctl", "set", "50%"]
+        ));
+        assert!(!is_slow_by_design("nmcli", &["connection", "show"]));
+        assert!(!is_slow_by_design("bluetoothctl", &["devices"]));
+        assert!(!is_slow_by_design("pactl", &["connect"]));
+    }
+
+    #[test]
+    fn test_command_log() {
+        let log = CommandLog::default();
+        for run in 0..=CAPACITY as u64 {
+            log.push("pactl", &["info"], Some(0), " ok\n", run);
+        }
+        let recent = log.recent();
+        assert_eq!(recent.len(), CAPACITY);
+        assert_eq!(recent[0].duration_ms, 1);
+        assert_eq!(recent[0].output, "ok");
+        assert_eq!(log.timings()[0].runs, CAPACITY as u64 + 1);
+    }
+
+    #[test]
+    fn test_timing_history() {
+        let mut history = TimingHistory::default();
+        assert!(history.summary("bluetoothctl").is_none());
+
+        history.push(900);
+        history.push(100);
+        let timing = history.summary("bluetoothctl").unwrap();
+        assert_eq!(timing.runs, 2);
+        assert_eq!(timing.last_ms, 100);
+        assert_eq!(timing.average_ms, 500);
+        assert_eq!(timing.max_ms, 900);
+
+        // The average only covers the recent history
+        for _ in 0..TIMING_HISTORY {
+            history.push(10);
+        }
+        let timing = history.summary("bluetoothctl").unwrap();
+        assert_eq!(timing.runs, 2 + TIMING_HISTORY as u64);
+        assert_eq!(timing.average_ms, 10);
+        assert_eq!(timing.max_ms, 900);
+    }
+}
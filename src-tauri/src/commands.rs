@@ -13,13 +13,27 @@
 //! - Uses tokio::process for async execution
 //! - All outputs are sanitized for IPC safety
 
+use crate::auto_brightness;
+use crate::battery_monitor;
+use crate::command_log::{self, BackendTiming, CommandLogEntry};
+use crate::config::{
+    Card, Config, ConfigStore, DisplayArrangement, NightLightSchedule, SinkLimit, ValueMode,
+};
 use crate::error::{CCResult, ControlCentreError};
-use crate::state::AppState;
+use crate::niri::{self, NiriOutput, NiriWindow, OutputMode};
+use crate::pactl::{self, Client, SampleSpec, SinkInfo, SinkInput};
+use crate::state::{AppState, CacheDomain, PowerTokenCheck};
 use log::{debug, error, info, warn};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
+use std::path::PathBuf;
 use std::process::Stdio;
-use tauri::State;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock, RwLock};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::AsyncReadExt;
 use tokio::process::Command;
 
 // ============================================================================
@@ -33,6 +47,13 @@ pub struct VolumeState {
     pub muted: bool,
 }
 
+/// Microphone (default source) state response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MicState {
+    pub volume: u8,
+    pub muted: bool,
+}
+
 /// Brightness state response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrightnessState {
@@ -48,41 +69,443 @@ pub struct NetworkState {
     pub wifi_ssid: Option<String>,
     pub bluetooth_enabled: bool,
     pub bluetooth_connected: bool,
+    /// Battery of the connected Bluetooth device, if it reports one
+    pub bluetooth_battery: Option<u8>,
 }
 
 /// Display state response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DisplayState {
     pub night_light_enabled: bool,
+    /// Configured Night Light start time (`HH:MM`), if scheduled
+    pub night_light_from: Option<String>,
+    /// Configured Night Light end time (`HH:MM`), if scheduled
+    pub night_light_to: Option<String>,
+    /// Several night light daemons are running and fighting each other
+    pub conflict: bool,
+    /// Outputs with their own Night Light, mapped to its temperature (Kelvin)
+    pub night_light_outputs: BTreeMap<String, u32>,
+    /// Temperature Night Light applies (Kelvin), `None` while it is off
+    #[serde(default)]
+    pub night_light_temperature: Option<u32>,
+}
+
+/// A display mode offered by an output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisplayMode {
+    /// Mode in the `<width>x<height>@<refresh>` form accepted by `set_display_mode`
+    pub mode: String,
+    pub width: u32,
+    pub height: u32,
+    pub refresh_hz: f64,
+    pub preferred: bool,
+}
+
+impl From<&OutputMode> for DisplayMode {
+    fn from(mode: &OutputMode) -> Self {
+        Self {
+            mode: mode.to_mode_string(),
+            width: mode.width,
+            height: mode.height,
+            refresh_hz: mode.refresh_hz(),
+            preferred: mode.is_preferred,
+        }
+    }
+}
+
+/// A connected output with its available modes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayOutput {
+    pub name: String,
+    pub make: String,
+    pub model: String,
+    pub enabled: bool,
+    pub current: Option<DisplayMode>,
+    pub modes: Vec<DisplayMode>,
+}
+
+impl From<&NiriOutput> for DisplayOutput {
+    fn from(output: &NiriOutput) -> Self {
+        Self {
+            name: output.name.clone(),
+            make: output.make.clone(),
+            model: output.model.clone(),
+            enabled: output.is_enabled(),
+            current: output.current().map(DisplayMode::from),
+            modes: output.modes.iter().map(DisplayMode::from).collect(),
+        }
+    }
+}
+
+/// A single entry from the notification daemon's history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    pub app: String,
+    pub summary: String,
+    pub body: String,
+    pub time: Option<String>,
+}
+
+/// Active notification daemon mode and the modes that can be switched to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationMode {
+    pub mode: String,
+    pub available: Vec<String>,
+}
+
+/// A domain's state together with whether its backend is usable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainState<T> {
+    pub available: bool,
+    pub value: Option<T>,
+    pub error: Option<String>,
+    /// The backend works but has no such device (e.g. no microphone), so
+    /// the control should be hidden rather than shown as broken
+    #[serde(default)]
+    pub missing_device: bool,
+    /// The sound server only offers its dummy output, so the controls have
+    /// no effect; `error` says why and `restart_audio_service` may help
+    #[serde(default)]
+    pub audio_unavailable: bool,
+}
+
+impl<T> DomainState<T> {
+    /// State of a domain whose card is turned off; it is not queried
+    pub fn disabled() -> Self {
+        Self {
+            available: false,
+            value: None,
+            error: None,
+            missing_device: false,
+            audio_unavailable: false,
+        }
+    }
+}
+
+impl<T> From<CCResult<T>> for DomainState<T> {
+    fn from(result: CCResult<T>) -> Self {
+        match result {
+            Ok(value) => Self {
+                available: true,
+                value: Some(value),
+                error: None,
+                missing_device: false,
+                audio_unavailable: false,
+            },
+            Err(e) => Self {
+                available: false,
+                value: None,
+                missing_device: matches!(e, ControlCentreError::DeviceNotFound(_)),
+                audio_unavailable: matches!(e, ControlCentreError::AudioUnavailable(_)),
+                error: Some(e.to_string()),
+            },
+        }
+    }
 }
 
 /// Complete system state response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AllStates {
-    pub volume: VolumeState,
-    pub brightness: BrightnessState,
-    pub network: NetworkState,
-    pub display: DisplayState,
+    pub volume: DomainState<VolumeState>,
+    pub mic: DomainState<MicState>,
+    pub brightness: DomainState<BrightnessState>,
+    pub network: DomainState<NetworkState>,
+    pub display: DomainState<DisplayState>,
+}
+
+/// Subset of `AllStates` whose backends answer quickly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FastStates {
+    pub volume: DomainState<VolumeState>,
+    pub brightness: DomainState<BrightnessState>,
+}
+
+impl AllStates {
+    /// Errors of all unavailable domains, prefixed with the domain name
+    pub fn errors(&self) -> Vec<String> {
+        [
+            ("volume", &self.volume.error),
+            ("mic", &self.mic.error),
+            ("brightness", &self.brightness.error),
+            ("network", &self.network.error),
+            ("display", &self.display.error),
+        ]
+        .into_iter()
+        .filter_map(|(domain, error)| error.as_ref().map(|e| format!("{}: {}", domain, e)))
+        .collect()
+    }
 }
 
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
+/// Lowest volume a relative adjustment can reach
+const VOLUME_FLOOR: u8 = 0;
+
+/// Lowest brightness ever applied, so the screen never goes fully dark
+pub(crate) const BRIGHTNESS_FLOOR: u8 = 1;
+
+/// Length of the smooth mute/unmute volume fade
+const VOLUME_FADE_DURATION: std::time::Duration = std::time::Duration::from_millis(150);
+
+/// Number of volume steps in a fade
+const VOLUME_FADE_STEPS: u32 = 6;
+
+/// Incremented by every volume/mute command; a running fade stops once it
+/// no longer matches the generation it was started with
+static VOLUME_FADE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Volume the current smooth mute started from, until the fade completes
+static VOLUME_FADE_ORIGIN: Mutex<Option<u8>> = Mutex::new(None);
+
+/// Incremented by every duck and unduck; a pending restore only runs if
+/// no newer one has arrived since it was scheduled
+static DUCK_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Brightness hysteresis window without a configured `brightness_hysteresis_ms`
+const DEFAULT_BRIGHTNESS_HYSTERESIS_MS: u64 = 1000;
+
+/// Step of `nudge_volume`/`nudge_brightness` without a configured `nudge_step`
+const DEFAULT_NUDGE_STEP: u8 = 2;
+
+/// Window in which nudges are coalesced into a single write
+const NUDGE_COALESCE_WINDOW: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Coalesces bursts of nudges so only the latest target is written
+///
+/// Each nudge records its target; the first nudge of a burst schedules a
+/// write after `NUDGE_COALESCE_WINDOW` that applies whatever target is
+/// pending by then.
+struct NudgeQueue {
+    /// Target not written yet, the base for the next nudge
+    pending: Mutex<Option<u8>>,
+    /// Whether a write is already scheduled
+    scheduled: AtomicBool,
+}
+
+impl NudgeQueue {
+    const fn new() -> Self {
+        Self {
+            pending: Mutex::new(None),
+            scheduled: AtomicBool::new(false),
+        }
+    }
+    
+    fn pending(&self) -> Option<u8> {
+        *self.pending.lock().ok()?
+    }
+    
+    /// Record a target, returning `true` if the caller must schedule the write
+    fn push(&self, value: u8) -> bool {
+        if let Ok(mut pending) = self.pending.lock() {
+            *pending = Some(value);
+        }
+        !self.scheduled.swap(true, Ordering::SeqCst)
+    }
+    
+    /// Take the target to write once the coalescing window is over
+    fn take(&self) -> Option<u8> {
+        self.scheduled.store(false, Ordering::SeqCst);
+        self.pending.lock().ok()?.take()
+    }
+}
+
+static VOLUME_NUDGES: NudgeQueue = NudgeQueue::new();
+
+static BRIGHTNESS_NUDGES: NudgeQueue = NudgeQueue::new();
+
+/// Time a privileged brightness write waits for a newer value, so dragging
+/// the slider runs one escalated brightnessctl instead of one per step
+const PRIVILEGED_BRIGHTNESS_SETTLE: std::time::Duration = std::time::Duration::from_millis(400);
+
+/// Latest brightness waiting for a privileged write
+static PRIVILEGED_BRIGHTNESS: NudgeQueue = NudgeQueue::new();
+
+/// Held around privileged brightness writes, one password prompt at a time
+static PRIVILEGED_BRIGHTNESS_WRITE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Time given to NetworkManager/BlueZ to apply a radio switch before re-reading it
+const RADIO_SETTLE_DELAY: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// Time each paired device gets to reconnect after Bluetooth is enabled
+const BLUETOOTH_RECONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Time the WiFi connection gets to come back after airplane mode
+const WIFI_RECONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// sysfs node of the mic-mute key LED found on many laptops
+const MIC_MUTE_LED: &str = "/sys/class/leds/platform::micmute";
+
+/// Tool paths from the config's `binaries` table, keyed by command name
+static BINARY_PATHS: RwLock<BTreeMap<String, PathBuf>> = RwLock::new(BTreeMap::new());
+
+/// Install the configured tool paths, warning about unusable entries
+///
+/// Must run before `AppState::new()` so backend detection sees them.
+pub(crate) fn set_binary_paths(binaries: &BTreeMap<String, PathBuf>) {
+    for (name, path) in binaries {
+        if !path.is_absolute() {
+            warn!("Configured path for {} is not absolute: {}", name, path.display());
+        } else if !path.is_file() {
+            warn!("Configured path for {} does not exist: {}", name, path.display());
+        } else {
+            debug!("Using {} for {}", path.display(), name);
+        }
+    }
+    
+    if let Ok(mut paths) = BINARY_PATHS.write() {
+        *paths = binaries.clone();
+    }
+}
+
+/// `PATH` given to every spawned tool, computed on first use
+static EFFECTIVE_PATH: OnceLock<OsString> = OnceLock::new();
+
+/// Directories appended to `PATH` when missing from it
+///
+/// A GUI process started from a compositor keybinding can inherit a minimal
+/// `PATH` that lacks the usual system or Nix profile directories.
+const FALLBACK_PATH_DIRS: [&str; 4] =
+    ["/run/current-system/sw/bin", "/usr/local/bin", "/usr/bin", "/bin"];
+
+/// The inherited `PATH` followed by any missing fallback directories
+fn effective_path() -> &'static OsString {
+    EFFECTIVE_PATH.get_or_init(|| {
+        let inherited = std::env::var_os("PATH");
+        let path = augment_path(inherited.as_deref(), std::env::var_os("HOME").as_deref());
+        debug!("Effective PATH for tools: {}", path.to_string_lossy());
+        path
+    })
+}
+
+fn augment_path(current: Option<&OsStr>, home: Option<&OsStr>) -> OsString {
+    let mut dirs: Vec<PathBuf> = current
+        .map(|p| std::env::split_paths(p).collect())
+        .unwrap_or_default();
+    
+    let nix_profile = home.map(|home| PathBuf::from(home).join(".nix-profile/bin"));
+    let fallbacks = nix_profile.into_iter().chain(FALLBACK_PATH_DIRS.iter().map(PathBuf::from));
+    for dir in fallbacks {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    
+    std::env::join_paths(dirs)
+        .unwrap_or_else(|_| current.map(OsStr::to_os_string).unwrap_or_default())
+}
+
+/// Program to execute for `cmd`: its configured path, or the bare name
+fn resolve_binary(cmd: &str) -> PathBuf {
+    BINARY_PATHS
+        .read()
+        .ok()
+        .and_then(|paths| paths.get(cmd).cloned())
+        .unwrap_or_else(|| PathBuf::from(cmd))
+}
+
+/// Prepare a tool invocation with its configured path and the effective `PATH`
+///
+/// Tools run in the C locale (with UTF-8 kept for names such as SSIDs) so
+/// the English words the parsers look for are not translated.
+pub(crate) fn tool_command(cmd: &str) -> Command {
+    let mut command = Command::new(resolve_binary(cmd));
+    command
+        .env("PATH", effective_path())
+        .env("LC_ALL", "C.UTF-8")
+        .env_remove("LANGUAGE");
+    command
+}
+
+/// Attempts a pactl call gets while the sound server refuses connections
+const PACTL_CONNECT_ATTEMPTS: u32 = 5;
+
+/// Pause between pactl attempts; PipeWire takes a second or two to restart
+const PACTL_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// How long pactl calls get a single attempt after the retries ran out
+const PACTL_REFUSAL_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Remembers when the sound server last refused every retry
+///
+/// While the cooldown runs, pactl calls are not retried, so a machine
+/// without a sound server does not spend seconds on every query. Any
+/// answer from the server, such as the audio monitor resubscribing, ends
+/// it early.
+struct RefusalCooldown {
+    since: Mutex<Option<std::time::Instant>>,
+}
+
+impl RefusalCooldown {
+    const fn new() -> Self {
+        Self {
+            since: Mutex::new(None),
+        }
+    }
+    
+    fn active(&self, period: std::time::Duration) -> bool {
+        self.since
+            .lock()
+            .ok()
+            .and_then(|since| *since)
+            .is_some_and(|at| at.elapsed() < period)
+    }
+    
+    /// Note the outcome of a call; `retried` is whether it had every attempt
+    fn record(&self, refused: bool, retried: bool) {
+        if let Ok(mut since) = self.since.lock() {
+            match (refused, retried) {
+                (false, _) => *since = None,
+                (true, true) => *since = Some(std::time::Instant::now()),
+                (true, false) => {}
+            }
+        }
+    }
+}
+
+static PACTL_REFUSALS: RefusalCooldown = RefusalCooldown::new();
+
 /// Execute a command and return stdout as string
+///
+/// pactl calls are retried while the sound server is restarting, except
+/// during `PACTL_REFUSAL_COOLDOWN` after the server refused every retry.
 async fn run_command(cmd: &str, args: &[&str]) -> CCResult<String> {
+    if cmd == "pactl" {
+        let retried = !PACTL_REFUSALS.active(PACTL_REFUSAL_COOLDOWN);
+        let attempts = if retried { PACTL_CONNECT_ATTEMPTS } else { 1 };
+        let result = retry_on_connection_refused(attempts, PACTL_RETRY_DELAY, || {
+            run_command_once(cmd, args)
+        })
+        .await;
+        PACTL_REFUSALS.record(is_connection_refused(&result), retried);
+        return result;
+    }
+    run_command_once(cmd, args).await
+}
+
+fn is_connection_refused<T>(result: &CCResult<T>) -> bool {
+    matches!(
+        result,
+        Err(ControlCentreError::CommandFailed(stderr)) if stderr.contains("Connection refused")
+    )
+}
+
+async fn run_command_once(cmd: &str, args: &[&str]) -> CCResult<String> {
     debug!("Running command: {} {:?}", cmd, args);
     
-    let output = Command::new(cmd)
+    let started = std::time::Instant::now();
+    let result = tool_command(cmd)
         .args(args)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .output()
-        .await
-        .map_err(|e| {
-            error!("Failed to execute {}: {}", cmd, e);
-            ControlCentreError::from(e)
-        })?;
+        .await;
+    record_command(cmd, args, started, &result);
+    let output = result.map_err(|e| {
+        error!("Failed to execute {}: {}", cmd, e);
+        ControlCentreError::from(e)
+    })?;
     
     if output.status.success() {
         let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -96,31 +519,175 @@ async fn run_command(cmd: &str, args: &[&str]) -> CCResult<String> {
 }
 
 /// Execute a command without capturing output
+///
+/// Only stderr is kept, for the error message; pactl calls are retried
+/// like in `run_command`.
 async fn run_command_no_output(cmd: &str, args: &[&str]) -> CCResult<()> {
+    if cmd == "pactl" {
+        return retry_on_connection_refused(PACTL_CONNECT_ATTEMPTS, PACTL_RETRY_DELAY, || {
+            run_command_no_output_once(cmd, args)
+        })
+        .await;
+    }
+    run_command_no_output_once(cmd, args).await
+}
+
+async fn run_command_no_output_once(cmd: &str, args: &[&str]) -> CCResult<()> {
     debug!("Running command (no output): {} {:?}", cmd, args);
     
-    let status = Command::new(cmd)
+    let started = std::time::Instant::now();
+    let result = tool_command(cmd)
         .args(args)
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
-        .status()
-        .await
-        .map_err(|e| {
-            error!("Failed to execute {}: {}", cmd, e);
-            ControlCentreError::from(e)
-        })?;
+        .output()
+        .await;
+    record_command(cmd, args, started, &result);
+    let output = result.map_err(|e| {
+        error!("Failed to execute {}: {}", cmd, e);
+        ControlCentreError::from(e)
+    })?;
     
-    if status.success() {
+    if output.status.success() {
         Ok(())
     } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
         Err(ControlCentreError::CommandFailed(format!(
-            "Command {} exited with status {}",
+            "Command {} exited with status {}: {}",
             cmd,
-            status.code().unwrap_or(-1)
+            output.status.code().unwrap_or(-1),
+            stderr
         )))
     }
 }
 
+/// Add a finished invocation to the command log
+///
+/// Failures keep stderr, or stdout when the tool reports on stdout only.
+fn record_command(
+    cmd: &str,
+    args: &[&str],
+    started: std::time::Instant,
+    result: &std::io::Result<std::process::Output>,
+) {
+    match result {
+        Ok(output) => {
+            let shown = if output.status.success() || output.stderr.is_empty() {
+                &output.stdout
+            } else {
+                &output.stderr
+            };
+            command_log::record(
+                cmd,
+                args,
+                output.status.code(),
+                &String::from_utf8_lossy(shown),
+                started.elapsed(),
+            );
+        }
+        Err(e) => command_log::record(cmd, args, None, &e.to_string(), started.elapsed()),
+    }
+}
+
+/// Run `attempt` until it stops failing with "Connection refused"
+///
+/// pactl reports this while PipeWire/PulseAudio restarts. Other errors
+/// return immediately; once `attempts` are used up the last refusal is
+/// returned, meaning the server is really gone.
+async fn retry_on_connection_refused<T, F, Fut>(
+    attempts: u32,
+    delay: std::time::Duration,
+    mut attempt: F,
+) -> CCResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = CCResult<T>>,
+{
+    let mut tries = 1;
+    loop {
+        match attempt().await {
+            Err(ControlCentreError::CommandFailed(stderr))
+                if stderr.contains("Connection refused") && tries < attempts =>
+            {
+                debug!("Sound server refused connection (attempt {}), retrying", tries);
+                tries += 1;
+                tokio::time::sleep(delay).await;
+            }
+            Err(ControlCentreError::CommandFailed(stderr))
+                if stderr.contains("Connection refused") =>
+            {
+                if attempts > 1 {
+                    warn!("Sound server still refusing connections after {} attempts", attempts);
+                }
+                return Err(ControlCentreError::CommandFailed(stderr));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Raw `/proc/<pid>/cmdline` of the first running process named `process`
+async fn process_cmdline(process: &str) -> CCResult<Option<Vec<u8>>> {
+    let Ok(pids) = run_command("pgrep", &["-x", process]).await else {
+        return Ok(None);
+    };
+    let Some(pid) = pids.lines().next() else {
+        return Ok(None);
+    };
+    Ok(Some(tokio::fs::read(format!("/proc/{}/cmdline", pid.trim())).await?))
+}
+
+/// Split a NUL-separated `/proc/<pid>/cmdline` into its arguments
+fn split_cmdline(cmdline: &[u8]) -> Vec<String> {
+    cmdline
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect()
+}
+
+/// Value following `flag` in a split command line
+fn cmdline_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let position = args.iter().position(|arg| arg == flag)?;
+    args.get(position + 1).map(String::as_str)
+}
+
+/// Check whether an executable is configured or available on the effective `PATH`
+fn command_exists(cmd: &str) -> bool {
+    let binary = resolve_binary(cmd);
+    if binary.is_absolute() {
+        return binary.is_file();
+    }
+    
+    std::env::split_paths(effective_path()).any(|dir| dir.join(cmd).is_file())
+}
+
+/// Start a long-running helper without waiting for it
+///
+/// Used for tools that must stay alive to keep their effect (e.g. gamma
+/// ramps on Wayland are reset when the client disconnects).
+fn spawn_detached(cmd: &str, args: &[&str]) -> CCResult<()> {
+    debug!("Spawning detached: {} {:?}", cmd, args);
+    
+    std::process::Command::new(resolve_binary(cmd))
+        .args(args)
+        .env("PATH", effective_path())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|mut child| {
+            // Reap the child when it exits so it does not linger as a zombie
+            std::thread::spawn(move || {
+                let _ = child.wait();
+            });
+        })
+        .map_err(|e| {
+            error!("Failed to spawn {}: {}", cmd, e);
+            ControlCentreError::from(e)
+        })
+}
+
 /// Validate percentage value (0-100)
 fn validate_percentage(value: u8) -> CCResult<u8> {
     if value > 100 {
@@ -133,6 +700,30 @@ fn validate_percentage(value: u8) -> CCResult<u8> {
     }
 }
 
+/// Apply a signed step to a percentage, saturating into `floor..=100`
+fn apply_delta(current: u8, delta: i8, floor: u8) -> u8 {
+    current.saturating_add_signed(delta).clamp(floor, 100)
+}
+
+/// Bring a requested percentage into 0-100 according to `mode`
+///
+/// `Reject` fails on out-of-range input; `Clamp` saturates it so the
+/// caller gets back the value that will actually be applied.
+fn normalize_percentage(value: i32, mode: ValueMode) -> CCResult<u8> {
+    match mode {
+        ValueMode::Clamp => Ok(value.clamp(0, 100) as u8),
+        ValueMode::Reject => {
+            let value = u8::try_from(value).map_err(|_| {
+                ControlCentreError::InvalidArgument(format!(
+                    "Percentage must be 0-100, got {}",
+                    value
+                ))
+            })?;
+            validate_percentage(value)
+        }
+    }
+}
+
 // ============================================================================
 // Audio Commands (PulseAudio/PipeWire via pactl)
 // ============================================================================
@@ -161,351 +752,6451 @@ pub async fn get_volume(state: State<'_, AppState>) -> Result<u8, String> {
 }
 
 /// Set volume level (0-100)
+///
+/// Out-of-range values are rejected or clamped depending on `mode`, which
+/// defaults to the config's `value_mode`. Values above the default sink's
+/// `sink_limits` entry are capped. Returns the applied value.
 #[tauri::command]
-pub async fn set_volume(state: State<'_, AppState>, value: u8) -> Result<u8, String> {
-    let value = validate_percentage(value).map_err(|e| e.to_string())?;
-    
-    run_command_no_output("pactl", &["set-sink-volume", "@DEFAULT_SINK@", &format!("{}%", value)])
+pub async fn set_volume(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    value: i32,
+    mode: Option<ValueMode>,
+) -> Result<u8, String> {
+    let config = config.get();
+    let value = normalize_percentage(value, mode.unwrap_or(config.value_mode))
+        .map_err(|e| e.to_string())?;
+    let value = limit_default_sink_volume(&config.sink_limits, value)
         .await
         .map_err(|e| e.to_string())?;
     
+    interrupt_volume_fade(&state).await;
+    cancel_duck(&state);
+    set_volume_internal(value).await.map_err(|e| e.to_string())?;
+    
     state.set_cached_volume(value);
     info!("Volume set to {}%", value);
     
     Ok(value)
 }
 
-/// Toggle mute state
+/// Adjust volume by a signed delta, clamped to 0-100 and the sink's limit
+///
+/// Returns the applied value.
 #[tauri::command]
-pub async fn toggle_mute(state: State<'_, AppState>) -> Result<bool, String> {
-    run_command_no_output("pactl", &["set-sink-mute", "@DEFAULT_SINK@", "toggle"])
-        .await
-        .map_err(|e| e.to_string())?;
+pub async fn adjust_volume(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    delta: i8,
+) -> Result<u8, String> {
+    interrupt_volume_fade(&state).await;
+    cancel_duck(&state);
     
-    let muted = get_mute_status_internal().await.map_err(|e| e.to_string())?;
-    state.set_cached_muted(muted);
+    let current = match state.get_cached_volume() {
+        Some(cached) => cached,
+        None => get_volume_internal().await.map_err(|e| e.to_string())?,
+    };
+    let value = limit_default_sink_volume(
+        &config.get().sink_limits,
+        apply_delta(current, delta, VOLUME_FLOOR),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
     
-    info!("Mute toggled, now: {}", muted);
-    Ok(muted)
+    set_volume_internal(value).await.map_err(|e| e.to_string())?;
+    
+    state.set_cached_volume(value);
+    info!("Volume adjusted by {} to {}%", delta, value);
+    
+    Ok(value)
 }
 
-/// Get mute status
+/// Nudge the volume by the fine scroll step, returning the new value
+///
+/// Rapid nudges are coalesced so a scroll burst spawns only a few pactl
+/// calls; the returned value is applied within `NUDGE_COALESCE_WINDOW`.
 #[tauri::command]
-pub async fn get_mute_status(state: State<'_, AppState>) -> Result<bool, String> {
-    if let Some(cached) = state.get_cached_muted() {
-        return Ok(cached);
+pub async fn nudge_volume(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    up: bool,
+) -> Result<u8, String> {
+    interrupt_volume_fade(&state).await;
+    cancel_duck(&state);
+    
+    let current = match VOLUME_NUDGES.pending().or_else(|| state.get_cached_volume()) {
+        Some(current) => current,
+        None => get_volume_internal().await.map_err(|e| e.to_string())?,
+    };
+    let config = config.get();
+    let value = limit_default_sink_volume(
+        &config.sink_limits,
+        apply_delta(current, nudge_delta(&config, up), VOLUME_FLOOR),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    state.set_cached_volume(value);
+    
+    if VOLUME_NUDGES.push(value) {
+        tauri::async_runtime::spawn(async {
+            tokio::time::sleep(NUDGE_COALESCE_WINDOW).await;
+            if let Some(value) = VOLUME_NUDGES.take() {
+                if let Err(e) = set_volume_internal(value).await {
+                    warn!("Failed to apply volume nudge: {}", e);
+                }
+            }
+        });
     }
     
-    let muted = get_mute_status_internal().await.map_err(|e| e.to_string())?;
-    state.set_cached_muted(muted);
-    Ok(muted)
+    Ok(value)
 }
 
-async fn get_mute_status_internal() -> CCResult<bool> {
-    let output = run_command("pactl", &["get-sink-mute", "@DEFAULT_SINK@"]).await?;
-    Ok(output.to_lowercase().contains("yes"))
+/// Signed step of one nudge
+fn nudge_delta(config: &Config, up: bool) -> i8 {
+    let step = config.nudge_step.unwrap_or(DEFAULT_NUDGE_STEP).clamp(1, 100) as i8;
+    if up { step } else { -step }
 }
 
-// ============================================================================
-// Brightness Commands (brightnessctl)
-// ============================================================================
-
-/// Get current brightness percentage (0-100)
+/// Get the per-sink volume ceilings from the config
 #[tauri::command]
-pub async fn get_brightness(state: State<'_, AppState>) -> Result<u8, String> {
-    if let Some(cached) = state.get_cached_brightness() {
-        return Ok(cached);
+pub async fn get_sink_limits(config: State<'_, ConfigStore>) -> Result<Vec<SinkLimit>, String> {
+    Ok(config.get().sink_limits)
+}
+
+/// Cap `value` at the default sink's limit, if it has one
+///
+/// Skips the pactl lookup entirely when no limits are configured.
+async fn limit_default_sink_volume(limits: &[SinkLimit], value: u8) -> CCResult<u8> {
+    if limits.is_empty() {
+        return Ok(value);
     }
     
-    let output = run_command("brightnessctl", &["-m", "info"])
-        .await
-        .map_err(|e| e.to_string())?;
-    
-    let brightness = output
-        .split(',')
-        .nth(3)
-        .and_then(|s| s.trim_end_matches('%').parse::<u8>().ok())
-        .unwrap_or(50);
+    let sink = run_command("pactl", &["get-default-sink"]).await?;
+    Ok(cap_sink_volume(limits, &sink, value))
+}
+
+/// Cap `value` at the limit of the named `sink`, if it has one
+fn cap_sink_volume(limits: &[SinkLimit], sink: &str, value: u8) -> u8 {
+    match sink_ceiling(limits, sink) {
+        Some(max) if value > max => {
+            info!("Volume {}% capped at {}% by the limit for {}", value, max, sink);
+            max
+        }
+        _ => value,
+    }
+}
+
+/// Lower `sink` to its limit if it is currently above it
+async fn enforce_sink_limit(limits: &[SinkLimit], sink: &str) -> CCResult<()> {
+    let Some(max) = sink_ceiling(limits, sink) else {
+        return Ok(());
+    };
     
-    state.set_cached_brightness(brightness);
-    Ok(brightness)
+    let output = run_command("pactl", &["get-sink-volume", sink]).await?;
+    if let Some(volume) = parse_volume_percent(&output).filter(|volume| *volume > max) {
+        info!("Volume {}% capped at {}% by the limit for {}", volume, max, sink);
+        run_command_no_output("pactl", &["set-sink-volume", sink, &format!("{}%", max)]).await?;
+    }
+    Ok(())
+}
+
+/// Lower a linear volume factor to a percentage ceiling
+///
+/// pactl percentages are on PulseAudio's cubic scale, so the ceiling in
+/// linear terms is its cube. Returns the factor to write and whether it
+/// was lowered.
+fn cap_linear_volume(factor: f32, max_percent: u8) -> (f32, bool) {
+    let cap = (f32::from(max_percent) / 100.0).powi(3);
+    if factor > cap {
+        (cap, true)
+    } else {
+        (factor, false)
+    }
+}
+
+/// Volume ceiling configured for `sink`
+///
+/// The lowest one wins if the sink is listed more than once.
+fn sink_ceiling(limits: &[SinkLimit], sink: &str) -> Option<u8> {
+    limits
+        .iter()
+        .filter(|limit| limit.name == sink)
+        .map(|limit| limit.max.min(100))
+        .min()
+}
+
+/// Accepted range for `set_volume_db`
+const VOLUME_DB_RANGE: std::ops::RangeInclusive<f32> = -60.0..=6.0;
+
+/// Get the default sink volume in decibels
+///
+/// Returns `None` at 0%, which pactl reports as `-inf dB`.
+#[tauri::command]
+pub async fn get_volume_db() -> Result<Option<f32>, String> {
+    get_volume_db_internal().await.map_err(|e| e.to_string())
+}
+
+/// Result of `set_volume_db`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeDbSet {
+    /// Level read back from pactl; `None` at 0% (`-inf dB`)
+    pub db: Option<f32>,
+    /// Whether the sink's `sink_limits` entry lowered the request
+    pub capped: bool,
 }
 
-/// Set brightness percentage (0-100)
+/// Set the default sink volume in decibels (-60 to +6)
+///
+/// A level above the sink's `sink_limits` entry is lowered to the limit
+/// before it is written, so the sink never plays above it. Returns the
+/// resulting level as read back from pactl and whether it was capped.
 #[tauri::command]
-pub async fn set_brightness(state: State<'_, AppState>, value: u8) -> Result<u8, String> {
-    let value = validate_percentage(value).map_err(|e| e.to_string())?;
-    let safe_value = value.max(1);
+pub async fn set_volume_db(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    db: f32,
+) -> Result<VolumeDbSet, String> {
+    if !VOLUME_DB_RANGE.contains(&db) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Volume must be between {} and {} dB, got {}",
+            VOLUME_DB_RANGE.start(),
+            VOLUME_DB_RANGE.end(),
+            db
+        ))
+        .to_string());
+    }
+    
+    // A leading '-' would make pactl treat "<db>dB" as a relative change,
+    // so pass the equivalent linear factor instead
+    let mut factor = 10f32.powf(db / 20.0);
+    let mut capped = false;
+    let limits = config.get().sink_limits;
+    if !limits.is_empty() {
+        let sink = run_command("pactl", &["get-default-sink"])
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Some(max) = sink_ceiling(&limits, &sink) {
+            (factor, capped) = cap_linear_volume(factor, max);
+            if capped {
+                info!("Volume {} dB capped at {}% by the limit for {}", db, max, sink);
+            }
+        }
+    }
     
-    run_command_no_output("brightnessctl", &["set", &format!("{}%", safe_value)])
+    interrupt_volume_fade(&state).await;
+    cancel_duck(&state);
+    let factor = format!("{:.6}", factor);
+    run_command_no_output("pactl", &["set-sink-volume", "@DEFAULT_SINK@", &factor])
         .await
         .map_err(|e| e.to_string())?;
     
-    state.set_cached_brightness(safe_value);
-    info!("Brightness set to {}%", safe_value);
-    
-    Ok(safe_value)
-}
-
-/// Get maximum brightness value
-#[tauri::command]
-pub async fn get_max_brightness() -> Result<u32, String> {
-    let output = run_command("brightnessctl", &["max"])
+    let output = run_command("pactl", &["get-sink-volume", "@DEFAULT_SINK@"])
         .await
         .map_err(|e| e.to_string())?;
+    if let Some(percent) = parse_volume_percent(&output) {
+        state.set_cached_volume(percent);
+    }
     
-    output
-        .trim()
-        .parse::<u32>()
-        .map_err(|e| format!("Failed to parse max brightness: {}", e))
+    let applied = parse_volume_db(&output).map_err(|e| e.to_string())?;
+    info!("Volume set to {:?} dB", applied);
+    Ok(VolumeDbSet { db: applied, capped })
 }
 
-// ============================================================================
-// WiFi Commands (NetworkManager via nmcli)
-// ============================================================================
+async fn get_volume_db_internal() -> CCResult<Option<f32>> {
+    let output = run_command("pactl", &["get-sink-volume", "@DEFAULT_SINK@"]).await?;
+    parse_volume_db(&output)
+}
 
-/// Get WiFi enabled status
+/// First channel's percentage in `pactl get-sink-volume` output
+fn parse_volume_percent(output: &str) -> Option<u8> {
+    let re = Regex::new(r"(\d+)%").ok()?;
+    re.captures(output)?.get(1)?.as_str().parse().ok()
+}
+
+/// First channel's level from the `/ -12.04 dB` field of `pactl get-sink-volume`
+fn parse_volume_db(output: &str) -> CCResult<Option<f32>> {
+    let re = Regex::new(r"/\s*(-inf|-?[\d.]+)\s*dB")
+        .map_err(|e| ControlCentreError::ParseError(e.to_string()))?;
+    let value = re
+        .captures(output)
+        .and_then(|cap| cap.get(1))
+        .ok_or_else(|| ControlCentreError::ParseError(format!("No dB value in pactl output: {}", output)))?
+        .as_str();
+    
+    if value == "-inf" {
+        return Ok(None);
+    }
+    value
+        .parse::<f32>()
+        .map(Some)
+        .map_err(|e| ControlCentreError::ParseError(e.to_string()))
+}
+
+/// Toggle mute state
+///
+/// With `smooth_mute` enabled the volume fades out before muting and back
+/// in after unmuting.
 #[tauri::command]
-pub async fn get_wifi_status(state: State<'_, AppState>) -> Result<NetworkState, String> {
-    let wifi_output = run_command("nmcli", &["radio", "wifi"])
+pub async fn toggle_mute(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+) -> Result<bool, String> {
+    let muted = if config.get().smooth_mute {
+        toggle_mute_smooth_internal().await
+    } else {
+        interrupt_volume_fade(&state).await;
+        toggle_mute_internal().await
+    }
+    .map_err(|e| e.to_string())?;
+    
+    state.set_cached_muted(muted);
+    
+    info!("Mute toggled, now: {}", muted);
+    Ok(muted)
+}
+
+/// Mute or unmute the default sink, returning the resulting state
+///
+/// Unlike `toggle_mute` this is idempotent, so scripts can reach a known
+/// state without reading it first. Never fades.
+#[tauri::command]
+pub async fn set_mute(state: State<'_, AppState>, muted: bool) -> Result<bool, String> {
+    interrupt_volume_fade(&state).await;
+    
+    let value = if muted { "1" } else { "0" };
+    run_command_no_output("pactl", &["set-sink-mute", "@DEFAULT_SINK@", value])
         .await
         .map_err(|e| e.to_string())?;
     
-    let wifi_enabled = wifi_output.to_lowercase().trim() == "enabled";
-    state.set_cached_wifi(wifi_enabled);
+    let actual = get_mute_status_internal().await.map_err(|e| e.to_string())?;
+    state.set_cached_muted(actual);
     
-    let mut wifi_connected = false;
-    let mut wifi_ssid = None;
+    info!("Mute set to {}", actual);
+    Ok(actual)
+}
+
+async fn toggle_mute_internal() -> CCResult<bool> {
+    run_command_no_output("pactl", &["set-sink-mute", "@DEFAULT_SINK@", "toggle"]).await?;
+    get_mute_status_internal().await
+}
+
+/// Fade out and mute, or unmute and fade in
+///
+/// The sink keeps its original volume while muted, so other tools see the
+/// usual level. A fade interrupted by another toggle resumes from the
+/// volume the first fade started at rather than the partially faded one.
+async fn toggle_mute_smooth_internal() -> CCResult<bool> {
+    let generation = cancel_volume_fade();
+    let muted = get_mute_status_internal().await?;
     
-    if wifi_enabled {
-        if let Ok(conn_output) = run_command("nmcli", &["-t", "-f", "ACTIVE,SSID", "device", "wifi"]).await {
-            for line in conn_output.lines() {
-                if line.starts_with("yes:") {
-                    wifi_connected = true;
-                    wifi_ssid = line.strip_prefix("yes:").map(|s| s.to_string());
-                    break;
-                }
-            }
+    let origin = match fade_origin() {
+        Some(origin) => origin,
+        None => get_volume_internal().await?,
+    };
+    set_fade_origin(Some(origin));
+    
+    if muted {
+        set_volume_internal(0).await?;
+        run_command_no_output("pactl", &["set-sink-mute", "@DEFAULT_SINK@", "0"]).await?;
+        if fade_volume(0, origin, generation).await? {
+            set_fade_origin(None);
+        }
+        Ok(false)
+    } else {
+        if !fade_volume(origin, 0, generation).await? {
+            // Superseded mid-fade; the newer command owns the sink now
+            return Ok(false);
         }
+        run_command_no_output("pactl", &["set-sink-mute", "@DEFAULT_SINK@", "1"]).await?;
+        set_volume_internal(origin).await?;
+        set_fade_origin(None);
+        Ok(true)
     }
+}
+
+/// Step the volume from `from` to `to` over `VOLUME_FADE_DURATION`
+///
+/// Returns `false` if another audio command cancelled the fade.
+async fn fade_volume(from: u8, to: u8, generation: u64) -> CCResult<bool> {
+    let step_delay = VOLUME_FADE_DURATION / VOLUME_FADE_STEPS;
     
-    let bt_enabled = get_bluetooth_status_internal().await.unwrap_or(false);
-    state.set_cached_bluetooth(bt_enabled);
+    for step in 1..=VOLUME_FADE_STEPS {
+        if VOLUME_FADE_GENERATION.load(Ordering::SeqCst) != generation {
+            debug!("Volume fade {} cancelled", generation);
+            return Ok(false);
+        }
+        
+        let t = step as f64 / VOLUME_FADE_STEPS as f64;
+        let value = (from as f64 + (to as f64 - from as f64) * t).round() as u8;
+        set_volume_internal(value).await?;
+        tokio::time::sleep(step_delay).await;
+    }
     
-    Ok(NetworkState {
-        wifi_enabled,
-        wifi_connected,
-        wifi_ssid,
-        bluetooth_enabled: bt_enabled,
-        bluetooth_connected: false,
-    })
+    Ok(VOLUME_FADE_GENERATION.load(Ordering::SeqCst) == generation)
 }
 
-/// Enable or disable WiFi
-#[tauri::command]
-pub async fn set_wifi_enabled(state: State<'_, AppState>, enabled: bool) -> Result<bool, String> {
-    let arg = if enabled { "on" } else { "off" };
+/// Cancel any running volume fade, returning the new fade generation
+fn cancel_volume_fade() -> u64 {
+    VOLUME_FADE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+/// Cancel any running volume fade and put the sink back at the volume the
+/// fade started from
+///
+/// Used by explicit volume and mute commands, so an interrupted smooth
+/// mute neither leaves the sink partly faded nor lets a later unmute jump
+/// back to the pre-fade level.
+async fn interrupt_volume_fade(state: &AppState) {
+    cancel_volume_fade();
+    let Some(origin) = take_fade_origin() else {
+        return;
+    };
     
-    run_command_no_output("nmcli", &["radio", "wifi", arg])
+    match set_volume_internal(origin).await {
+        Ok(()) => {
+            state.set_cached_volume(origin);
+            debug!("Volume fade interrupted, back at {}%", origin);
+        }
+        Err(e) => warn!("Failed to restore the volume from before a fade: {}", e),
+    }
+}
+
+fn fade_origin() -> Option<u8> {
+    *VOLUME_FADE_ORIGIN.lock().ok()?
+}
+
+fn take_fade_origin() -> Option<u8> {
+    VOLUME_FADE_ORIGIN.lock().ok()?.take()
+}
+
+fn set_fade_origin(origin: Option<u8>) {
+    if let Ok(mut current) = VOLUME_FADE_ORIGIN.lock() {
+        *current = origin;
+    }
+}
+
+/// Lower the volume to `to` for `duration_ms`, then restore it
+///
+/// Returns the ducked value right away; the restore runs in the background.
+/// A newer duck extends the duck and keeps the original level to restore;
+/// `unduck_volume` restores it early. Never raises the volume.
+#[tauri::command]
+pub async fn duck_volume(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    to: u8,
+    duration_ms: u64,
+) -> Result<u8, String> {
+    let to = validate_percentage(to).map_err(|e| e.to_string())?;
+    let (value, generation) = begin_duck(&state, to, get_volume_internal(), set_volume_internal)
         .await
         .map_err(|e| e.to_string())?;
+    debug!("Restoring the volume in {}ms", duration_ms);
     
-    state.set_cached_wifi(enabled);
-    info!("WiFi set to {}", arg);
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(duration_ms)).await;
+        if DUCK_GENERATION.load(Ordering::SeqCst) != generation {
+            debug!("Volume duck {} superseded", generation);
+            return;
+        }
+        let limits = app.state::<ConfigStore>().get().sink_limits;
+        if let Err(e) = unduck_volume_internal(&app.state::<AppState>(), &limits).await {
+            warn!("Failed to restore the volume after ducking: {}", e);
+        }
+    });
     
-    Ok(enabled)
+    Ok(value)
 }
 
-// ============================================================================
-// Bluetooth Commands (bluetoothctl)
-// ============================================================================
+/// Duck to `to`, reading the level to restore with `read` unless a duck is
+/// already running
+///
+/// Nothing is recorded unless `write` succeeds, so a failed re-duck leaves
+/// the running duck and its restore in place. Returns the ducked value and
+/// the generation its restore belongs to.
+async fn begin_duck<F, Fut>(
+    state: &AppState,
+    to: u8,
+    read: impl std::future::Future<Output = CCResult<u8>>,
+    write: F,
+) -> CCResult<(u8, u64)>
+where
+    F: FnOnce(u8) -> Fut,
+    Fut: std::future::Future<Output = CCResult<()>>,
+{
+    // Back at the pre-fade level first, so a fade is not taken for the origin
+    interrupt_volume_fade(state).await;
+    let origin = match state.duck_origin() {
+        Some(origin) => origin,
+        None => read.await?,
+    };
+    let value = to.min(origin);
+    
+    write(value).await?;
+    state.store_duck_origin(origin);
+    state.set_cached_volume(value);
+    info!("Volume ducked from {}% to {}%", origin, value);
+    
+    Ok((value, DUCK_GENERATION.fetch_add(1, Ordering::SeqCst) + 1))
+}
 
-/// Get Bluetooth enabled status
+/// Drop a running duck without restoring, since an explicit volume change
+/// supersedes the level it would bring back
+fn cancel_duck(state: &AppState) {
+    DUCK_GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Some(origin) = state.take_duck_origin() {
+        debug!("Volume duck from {}% cancelled", origin);
+    }
+}
+
+/// End a duck early, restoring the volume from before it
+///
+/// Returns the restored value, capped by the limit of the sink that is the
+/// default now.
 #[tauri::command]
-pub async fn get_bluetooth_status(state: State<'_, AppState>) -> Result<bool, String> {
-    if let Some(cached) = state.get_cached_bluetooth() {
+pub async fn unduck_volume(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+) -> Result<u8, String> {
+    DUCK_GENERATION.fetch_add(1, Ordering::SeqCst);
+    unduck_volume_internal(&state, &config.get().sink_limits)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn unduck_volume_internal(state: &AppState, limits: &[SinkLimit]) -> CCResult<u8> {
+    let origin = state.take_duck_origin().ok_or_else(|| {
+        ControlCentreError::InvalidArgument("Volume is not ducked".to_string())
+    })?;
+    
+    interrupt_volume_fade(state).await;
+    let value = limit_default_sink_volume(limits, origin).await?;
+    set_volume_internal(value).await?;
+    state.set_cached_volume(value);
+    info!("Volume restored to {}% after ducking", value);
+    Ok(value)
+}
+
+/// Get mute status
+#[tauri::command]
+pub async fn get_mute_status(state: State<'_, AppState>) -> Result<bool, String> {
+    if let Some(cached) = state.get_cached_muted() {
         return Ok(cached);
     }
     
-    let enabled = get_bluetooth_status_internal()
+    let muted = get_mute_status_internal().await.map_err(|e| e.to_string())?;
+    state.set_cached_muted(muted);
+    Ok(muted)
+}
+
+async fn get_mute_status_internal() -> CCResult<bool> {
+    let output = run_command("pactl", &["get-sink-mute", "@DEFAULT_SINK@"]).await?;
+    Ok(output.to_lowercase().contains("yes"))
+}
+
+/// Toggle the default source's mute and mirror it on the mic-mute LED
+///
+/// Returns the new mute state. The LED is optional; laptops without one
+/// simply get the mute toggle.
+#[tauri::command]
+pub async fn toggle_mic_mute_with_led() -> Result<bool, String> {
+    // Toggled by pactl itself, so a concurrent change cannot be undone
+    run_command_no_output("pactl", &["set-source-mute", "@DEFAULT_SOURCE@", "toggle"])
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| missing_source_error(e).to_string())?;
     
-    state.set_cached_bluetooth(enabled);
-    Ok(enabled)
+    let muted = get_mic_mute_internal().await.map_err(|e| e.to_string())?;
+    
+    if let Err(e) = set_mic_mute_led_internal(muted).await {
+        warn!("Failed to update mic-mute LED: {}", e);
+    }
+    
+    info!("Microphone {}", if muted { "muted" } else { "unmuted" });
+    Ok(muted)
 }
 
-async fn get_bluetooth_status_internal() -> CCResult<bool> {
-    let output = run_command("bluetoothctl", &["show"]).await?;
-    Ok(output.contains("Powered: yes"))
+async fn get_mic_state_internal() -> CCResult<MicState> {
+    let volume = run_source_query("get-source-volume").await?;
+    let volume = parse_volume_percent(&volume).ok_or_else(|| {
+        ControlCentreError::ParseError(format!("No volume in pactl output: {}", volume))
+    })?;
+    let muted = get_mic_mute_internal().await?;
+    Ok(MicState { volume, muted })
 }
 
-/// Enable or disable Bluetooth
+async fn get_mic_mute_internal() -> CCResult<bool> {
+    let output = run_source_query("get-source-mute").await?;
+    Ok(output.to_lowercase().contains("yes"))
+}
+
+/// Run a pactl query on the default source
+///
+/// Without any capture device pactl fails with `No such entity`; that is
+/// reported as `DeviceNotFound` so callers can hide the mic control.
+async fn run_source_query(query: &str) -> CCResult<String> {
+    run_command("pactl", &[query, "@DEFAULT_SOURCE@"])
+        .await
+        .map_err(missing_source_error)
+}
+
+/// Report pactl's `No such entity` for the default source as `DeviceNotFound`
+fn missing_source_error(e: ControlCentreError) -> ControlCentreError {
+    match e {
+        ControlCentreError::CommandFailed(stderr) if is_missing_entity_error(&stderr) => {
+            ControlCentreError::DeviceNotFound("No default audio source".to_string())
+        }
+        e => e,
+    }
+}
+
+/// Whether pactl stderr says the requested sink or source does not exist
+fn is_missing_entity_error(stderr: &str) -> bool {
+    stderr.contains("No such entity") || stderr.contains("not found")
+}
+
+/// Mute or unmute the default source, returning the resulting state
+///
+/// Mirrored on the mic-mute LED like `toggle_mic_mute_with_led`.
 #[tauri::command]
-pub async fn set_bluetooth_enabled(state: State<'_, AppState>, enabled: bool) -> Result<bool, String> {
-    let arg = if enabled { "on" } else { "off" };
+pub async fn set_source_mute(muted: bool) -> Result<bool, String> {
+    // Reading first reports a missing source as `DeviceNotFound`
+    get_mic_mute_internal().await.map_err(|e| e.to_string())?;
     
-    run_command_no_output("bluetoothctl", &["power", arg])
+    let value = if muted { "1" } else { "0" };
+    run_command_no_output("pactl", &["set-source-mute", "@DEFAULT_SOURCE@", value])
         .await
         .map_err(|e| e.to_string())?;
     
-    state.set_cached_bluetooth(enabled);
-    info!("Bluetooth set to {}", arg);
+    let actual = get_mic_mute_internal().await.map_err(|e| e.to_string())?;
+    if let Err(e) = set_mic_mute_led_internal(actual).await {
+        warn!("Failed to update mic-mute LED: {}", e);
+    }
     
-    Ok(enabled)
+    info!("Microphone {}", if actual { "muted" } else { "unmuted" });
+    Ok(actual)
 }
 
-// ============================================================================
-// Night Light Commands
-// ============================================================================
-
-/// Get Night Light enabled status
-#[tauri::command]
-pub async fn get_night_light_status(state: State<'_, AppState>) -> Result<bool, String> {
-    if let Some(cached) = state.get_cached_night_light() {
-        return Ok(cached);
+/// Set the `platform::micmute` LED if present
+///
+/// Goes through brightnessctl, which can write LEDs via logind without
+/// root, unlike writing the sysfs file directly.
+async fn set_mic_mute_led_internal(on: bool) -> CCResult<()> {
+    if !std::path::Path::new(MIC_MUTE_LED).exists() {
+        debug!("No mic-mute LED at {}", MIC_MUTE_LED);
+        return Ok(());
     }
     
-    let result = run_command(
-        "gsettings",
-        &["get", "org.gnome.settings-daemon.plugins.color", "night-light-enabled"],
+    let value = if on { "1" } else { "0" };
+    run_command_no_output(
+        "brightnessctl",
+        &["--class=leds", "--device=platform::micmute", "set", value],
     )
-    .await;
-    
-    let enabled = match result {
-        Ok(output) => output.trim() == "true",
-        Err(_) => {
-            warn!("GNOME settings not available, trying alternatives");
-            if let Ok(output) = run_command("pgrep", &["-x", "gammastep"]).await {
-                !output.is_empty()
-            } else if let Ok(output) = run_command("pgrep", &["-x", "redshift"]).await {
-                !output.is_empty()
-            } else {
-                false
-            }
-        }
-    };
-    
-    state.set_cached_night_light(enabled);
-    Ok(enabled)
+    .await
 }
 
-/// Enable or disable Night Light
+/// Get the human-readable name of the default output device
+///
+/// Fails with "Audio unavailable" when the default is the dummy output.
 #[tauri::command]
-pub async fn set_night_light_enabled(state: State<'_, AppState>, enabled: bool) -> Result<bool, String> {
-    let value = if enabled { "true" } else { "false" };
-    
-    let result = run_command_no_output(
-        "gsettings",
-        &["set", "org.gnome.settings-daemon.plugins.color", "night-light-enabled", value],
-    )
-    .await;
+pub async fn get_default_sink_name() -> Result<String, String> {
+    let name = get_default_device_name_internal("sink")
+        .await
+        .map_err(|e| e.to_string())?;
+    ensure_real_sink(&name).map_err(|e| e.to_string())?;
+    Ok(name)
+}
+
+/// Get the human-readable name of the default input device
+#[tauri::command]
+pub async fn get_default_source_name() -> Result<String, String> {
+    get_default_device_name_internal("source").await.map_err(|e| e.to_string())
+}
+
+/// Resolve the default sink/source to its description, falling back to the raw name
+async fn get_default_device_name_internal(kind: &str) -> CCResult<String> {
+    let name = run_command("pactl", &[&format!("get-default-{}", kind)]).await?;
     
-    match result {
-        Ok(_) => {
-            state.set_cached_night_light(enabled);
-            info!("Night Light set to {}", value);
-            Ok(enabled)
-        }
-        Err(_) => {
-            warn!("GNOME settings not available, using gammastep fallback");
-            
-            if enabled {
-                let _ = run_command_no_output("gammastep", &["-O", "4500"]).await;
-            } else {
-                let _ = run_command_no_output("pkill", &["-x", "gammastep"]).await;
-                let _ = run_command_no_output("pkill", &["-x", "wlsunset"]).await;
-            }
-            
-            state.set_cached_night_light(enabled);
-            Ok(enabled)
+    match run_command("pactl", &["list", &format!("{}s", kind)]).await {
+        Ok(list) => Ok(parse_pactl_description(&list, &name).unwrap_or(name)),
+        Err(e) => {
+            warn!("Failed to list {}s, using raw name: {}", kind, e);
+            Ok(name)
         }
     }
 }
 
-// ============================================================================
-// Power Commands
-// ============================================================================
+/// Why the controls do nothing while the dummy output is the default sink
+const DUMMY_SINK_REASON: &str = "the sound server only offers a dummy output; \
+    restarting the audio service usually brings the real devices back";
 
-/// Suspend the system
+/// User services making up the PipeWire sound server
+const AUDIO_SERVICES: [&str; 3] = ["pipewire", "pipewire-pulse", "wireplumber"];
+
+/// Whether a sink name or description is the placeholder the sound server
+/// falls back to without a real output (`auto_null`, "Dummy Output")
+///
+/// Matched exactly, so a real device with "dummy" in its name still counts.
+fn is_dummy_sink(sink: &str) -> bool {
+    matches!(sink.trim(), "auto_null" | "Dummy Output")
+}
+
+/// Fail with `AudioUnavailable` if `sink` is the dummy output
+fn ensure_real_sink(sink: &str) -> CCResult<()> {
+    if is_dummy_sink(sink) {
+        Err(ControlCentreError::AudioUnavailable(DUMMY_SINK_REASON.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+/// Restart the PipeWire user services, e.g. when only the dummy output is left
 #[tauri::command]
-pub async fn suspend_system() -> Result<(), String> {
-    info!("Suspending system...");
-    tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
-    
-    run_command_no_output("loginctl", &["suspend"])
+pub async fn restart_audio_service(state: State<'_, AppState>) -> Result<(), String> {
+    let mut args = vec!["--user", "restart", "--"];
+    args.extend(AUDIO_SERVICES);
+    run_command_no_output("systemctl", &args)
         .await
         .map_err(|e| e.to_string())?;
     
+    state.invalidate_all();
+    info!("Audio services restarted");
     Ok(())
 }
 
-// ============================================================================
-// Aggregate State Command
-// ============================================================================
-
-/// Get all system states at once
+/// Switch the default sink to the next available one, moving playing streams
+///
+/// Returns the new default's description. Suspended sinks are only idle
+/// and stay in the rotation; sinks whose active port is unplugged are skipped.
 #[tauri::command]
-pub async fn get_all_states(state: State<'_, AppState>) -> Result<AllStates, String> {
+pub async fn cycle_default_sink(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+) -> Result<String, String> {
+    let description = cycle_default_sink_internal(&config.get().sink_limits)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    // Volume and mute belong to the sink, so cached values are stale now
     state.invalidate_all();
+    Ok(description)
+}
+
+async fn cycle_default_sink_internal(limits: &[SinkLimit]) -> CCResult<String> {
+    let current = run_command("pactl", &["get-default-sink"]).await?;
+    let sinks = parse_pactl_devices(&run_command("pactl", &["list", "sinks"]).await?);
     
-    let (volume, muted, brightness, max_brightness, network, night_light) = tokio::join!(
-        async { get_volume_internal().await.unwrap_or(50) },
-        async { get_mute_status_internal().await.unwrap_or(false) },
-        async { get_brightness_internal().await.unwrap_or(50) },
-        async { get_max_brightness_internal().await.unwrap_or(100) },
-        async { get_network_state_internal().await },
-        async { get_night_light_internal().await.unwrap_or(false) },
-    );
+    let next = next_sink(&sinks, &current).ok_or_else(|| {
+        ControlCentreError::SystemError("No other available sink to switch to".to_string())
+    })?;
     
-    state.set_cached_volume(volume);
-    state.set_cached_muted(muted);
-    state.set_cached_brightness(brightness);
-    state.set_cached_wifi(network.wifi_enabled);
-    state.set_cached_bluetooth(network.bluetooth_enabled);
-    state.set_cached_night_light(night_light);
+    switch_default_sink_internal(&next.name, limits).await?;
+    Ok(next.description.clone())
+}
+
+/// Make `sink` the default and move every playing stream to it
+///
+/// A sink above its limit is lowered before any stream is moved over.
+async fn switch_default_sink_internal(sink: &str, limits: &[SinkLimit]) -> CCResult<()> {
+    info!("Switching default sink to {}", sink);
+    enforce_sink_limit(limits, sink).await?;
+    run_command_no_output("pactl", &["set-default-sink", sink]).await?;
     
-    Ok(AllStates {
-        volume: VolumeState { volume, muted },
-        brightness: BrightnessState { brightness, max_brightness },
-        network,
-        display: DisplayState { night_light_enabled: night_light },
+    let inputs = run_command("pactl", &["list", "short", "sink-inputs"]).await?;
+    for id in inputs.lines().filter_map(|line| line.split_whitespace().next()) {
+        if let Err(e) = run_command_no_output("pactl", &["move-sink-input", id, sink]).await {
+            warn!("Failed to move stream {} to {}: {}", id, sink, e);
+        }
+    }
+    Ok(())
+}
+
+/// Flip the default sink between the speakers and the last other sink
+///
+/// From the speakers this goes to the headphones/HDMI/Bluetooth sink used
+/// most recently, or the first available one; from any other sink it goes
+/// to the speakers. Playing streams are moved along. Returns the new
+/// default's description.
+#[tauri::command]
+pub async fn toggle_audio_output(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+) -> Result<String, String> {
+    let (current, sinks) = tokio::try_join!(
+        run_command("pactl", &["get-default-sink"]),
+        list_sinks_internal(),
+    )
+    .map_err(|e| e.to_string())?;
+    
+    let remembered = state.get_other_sink();
+    let target = toggle_output_target(&sinks, &current, remembered.as_deref()).ok_or_else(|| {
+        ControlCentreError::SystemError("Only one available sink, nothing to toggle".to_string())
+            .to_string()
+    })?;
+    
+    if sinks.iter().any(|s| s.name == current && !is_speaker_sink(s)) {
+        state.set_other_sink(current.clone());
+    }
+    switch_default_sink_internal(&target.name, &config.get().sink_limits)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    state.invalidate_all();
+    Ok(if target.description.is_empty() {
+        target.name.clone()
+    } else {
+        target.description.clone()
     })
 }
 
-// Internal helpers
-async fn get_volume_internal() -> CCResult<u8> {
-    let output = run_command("pactl", &["get-sink-volume", "@DEFAULT_SINK@"]).await?;
-    let re = Regex::new(r"(\d+)%").map_err(|e| ControlCentreError::ParseError(e.to_string()))?;
-    Ok(re.captures(&output)
-        .and_then(|cap| cap.get(1))
-        .and_then(|m| m.as_str().parse::<u8>().ok())
-        .unwrap_or(50))
+/// Which sink an application stream currently plays to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamRoute {
+    /// Sink input index, as passed to `move_stream`
+    pub input: u32,
+    pub app_name: String,
+    /// Sink name, or `None` if the stream's sink is not in the sink list
+    pub sink: Option<String>,
 }
 
-async fn get_brightness_internal() -> CCResult<u8> {
-    let output = run_command("brightnessctl", &["-m", "info"]).await?;
-    Ok(output.split(',').nth(3)
-        .and_then(|s| s.trim_end_matches('%').parse::<u8>().ok())
-        .unwrap_or(50))
+/// List the playing streams and the sink each is routed to
+#[tauri::command]
+pub async fn list_stream_routes() -> Result<Vec<StreamRoute>, String> {
+    let (inputs, sinks) = tokio::try_join!(list_sink_inputs_internal(), list_sinks_internal())
+        .map_err(|e| e.to_string())?;
+    Ok(stream_routes(&inputs, &sinks))
 }
 
-async fn get_max_brightness_internal() -> CCResult<u32> {
-    let output = run_command("brightnessctl", &["max"]).await?;
-    output.trim().parse::<u32>()
-        .map_err(|e| ControlCentreError::ParseError(e.to_string()))
+/// Send one stream to another sink, leaving the others where they are
+///
+/// Returns the updated route.
+#[tauri::command]
+pub async fn move_stream(input: u32, sink: String) -> Result<StreamRoute, String> {
+    move_stream_internal(input, &sink).await.map_err(|e| e.to_string())
 }
 
-async fn get_network_state_internal() -> NetworkState {
-    let wifi_enabled = run_command("nmcli", &["radio", "wifi"])
-        .await
-        .map(|o| o.to_lowercase().trim() == "enabled")
-        .unwrap_or(false);
+async fn move_stream_internal(input: u32, sink: &str) -> CCResult<StreamRoute> {
+    let (inputs, sinks) = tokio::try_join!(list_sink_inputs_internal(), list_sinks_internal())?;
+    if !inputs.iter().any(|i| i.index == input) {
+        return Err(ControlCentreError::InvalidArgument(format!("Unknown stream: {}", input)));
+    }
+    if !sinks.iter().any(|s| s.name == sink) {
+        return Err(ControlCentreError::InvalidArgument(format!("Unknown sink: {}", sink)));
+    }
     
-    let mut wifi_connected = false;
+    run_command_no_output("pactl", &["move-sink-input", &input.to_string(), sink]).await?;
+    info!("Moved stream {} to {}", input, sink);
+    
+    let inputs = list_sink_inputs_internal().await?;
+    stream_routes(&inputs, &sinks)
+        .into_iter()
+        .find(|route| route.input == input)
+        .ok_or_else(|| ControlCentreError::SystemError(format!("Stream {} ended", input)))
+}
+
+/// Resolve the sink index of each stream to the sink's name
+fn stream_routes(inputs: &[SinkInput], sinks: &[SinkInfo]) -> Vec<StreamRoute> {
+    inputs
+        .iter()
+        .map(|input| StreamRoute {
+            input: input.index,
+            app_name: input.app_name.clone(),
+            sink: sinks
+                .iter()
+                .find(|s| s.index == input.sink)
+                .map(|s| s.name.clone()),
+        })
+        .collect()
+}
+
+/// List the programs connected to the sound server
+#[tauri::command]
+pub async fn list_audio_clients() -> Result<Vec<Client>, String> {
+    list_audio_clients_internal().await.map_err(|e| e.to_string())
+}
+
+/// Disconnect a client from the sound server, e.g. one that holds a
+/// device open and won't let go
+///
+/// Returns the client that was disconnected.
+#[tauri::command]
+pub async fn kill_audio_client(index: u32) -> Result<Client, String> {
+    let client = list_audio_clients_internal()
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|client| client.index == index)
+        .ok_or_else(|| {
+            ControlCentreError::InvalidArgument(format!("Unknown audio client: {}", index))
+                .to_string()
+        })?;
+    
+    run_command_no_output("pactl", &["kill-client", &index.to_string()])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    info!("Disconnected audio client {} ({}, pid {:?})", index, client.app_name, client.pid);
+    Ok(client)
+}
+
+async fn list_audio_clients_internal() -> CCResult<Vec<Client>> {
+    let json = run_command("pactl", &["-f", "json", "list", "clients"]).await?;
+    pactl::parse_clients(&json)
+}
+
+/// Pick the sink `toggle_audio_output` switches to from `current`
+///
+/// Falls back to any other available sink when the preferred kind is
+/// missing, e.g. two headsets and no speakers.
+fn toggle_output_target<'a>(
+    sinks: &'a [SinkInfo],
+    current: &str,
+    remembered: Option<&str>,
+) -> Option<&'a SinkInfo> {
+    let mut candidates = sinks.iter().filter(|s| s.name != current && sink_port_available(s));
+    let on_speakers = sinks.iter().any(|s| s.name == current && is_speaker_sink(s));
+    
+    let preferred = if on_speakers {
+        candidates
+            .clone()
+            .find(|s| Some(s.name.as_str()) == remembered && !is_speaker_sink(s))
+            .or_else(|| candidates.clone().find(|s| !is_speaker_sink(s)))
+    } else {
+        candidates.clone().find(|s| is_speaker_sink(s))
+    };
+    preferred.or_else(|| candidates.next())
+}
+
+/// Whether a sink plays through built-in speakers, judged by its active port
+fn is_speaker_sink(sink: &SinkInfo) -> bool {
+    sink.active_port
+        .as_deref()
+        .is_some_and(|port| port.to_lowercase().contains("speaker"))
+}
+
+/// `false` when the sink's active port is unplugged
+fn sink_port_available(sink: &SinkInfo) -> bool {
+    !sink.ports.iter().any(|p| Some(&p.name) == sink.active_port.as_ref() && !p.available)
+}
+
+/// The available sink after `current`, wrapping around
+fn next_sink<'a>(sinks: &'a [PactlDevice], current: &str) -> Option<&'a PactlDevice> {
+    let start = sinks.iter().position(|s| s.name == current).map_or(0, |i| i + 1);
+    
+    sinks
+        .iter()
+        .cycle()
+        .skip(start)
+        .take(sinks.len())
+        .find(|s| s.available && s.name != current)
+}
+
+/// A sink or source from `pactl list sinks|sources`
+#[derive(Debug, Clone, Default, PartialEq)]
+struct PactlDevice {
+    name: String,
+    description: String,
+    /// `false` when the active port is reported as not available (unplugged)
+    available: bool,
+}
+
+/// Parse the device blocks of `pactl list sinks|sources`
+fn parse_pactl_devices(list: &str) -> Vec<PactlDevice> {
+    #[derive(Default)]
+    struct Block {
+        device: PactlDevice,
+        active_port: Option<String>,
+        unavailable_ports: Vec<String>,
+    }
+    
+    let mut blocks: Vec<Block> = Vec::new();
+    for line in list.lines() {
+        let trimmed = line.trim();
+        
+        // Each device starts with an unindented "Sink #N" / "Source #N"
+        if !line.starts_with(char::is_whitespace) && trimmed.contains(" #") {
+            blocks.push(Block::default());
+            continue;
+        }
+        
+        let Some(block) = blocks.last_mut() else {
+            continue;
+        };
+        if let Some(value) = trimmed.strip_prefix("Name:") {
+            block.device.name = value.trim().to_string();
+        } else if let Some(value) = trimmed.strip_prefix("Description:") {
+            block.device.description = value.trim().to_string();
+        } else if let Some(value) = trimmed.strip_prefix("Active Port:") {
+            block.active_port = Some(value.trim().to_string());
+        } else if trimmed.ends_with("not available)") {
+            if let Some((port, _)) = trimmed.split_once(':') {
+                block.unavailable_ports.push(port.to_string());
+            }
+        }
+    }
+    
+    blocks
+        .into_iter()
+        .map(|block| PactlDevice {
+            available: !block
+                .active_port
+                .is_some_and(|port| block.unavailable_ports.contains(&port)),
+            ..block.device
+        })
+        .collect()
+}
+
+/// Find the description of the device called `name` in `pactl list sinks|sources`
+fn parse_pactl_description(list: &str, name: &str) -> Option<String> {
+    parse_pactl_devices(list)
+        .into_iter()
+        .find(|d| d.name == name)
+        .map(|d| d.description)
+        .filter(|d| !d.is_empty())
+}
+
+/// Get detailed info for a sink, or the default sink when `name` is unset
+#[tauri::command]
+pub async fn get_sink_info(name: Option<String>) -> Result<SinkInfo, String> {
+    get_sink_info_internal(name).await.map_err(|e| e.to_string())
+}
+
+/// Get the sample format, rate and channel count of the default sink
+///
+/// `None` when the sound server does not report it.
+#[tauri::command]
+pub async fn get_sink_format() -> Result<Option<SampleSpec>, String> {
+    get_sink_info_internal(None)
+        .await
+        .map(|sink| sink.sample_spec)
+        .map_err(|e| e.to_string())
+}
+
+/// Switch a sink to another of its ports, returning the updated sink
+#[tauri::command]
+pub async fn set_sink_port(
+    state: State<'_, AppState>,
+    sink: String,
+    port: String,
+) -> Result<SinkInfo, String> {
+    let info = get_sink_info_internal(Some(sink))
+        .await
+        .map_err(|e| e.to_string())?;
+    if !info.ports.iter().any(|p| p.name == port) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Sink {} has no port {}",
+            info.name, port
+        ))
+        .to_string());
+    }
+    
+    run_command_no_output("pactl", &["set-sink-port", &info.name, &port])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    state.invalidate_all();
+    info!("Switched {} to port {}", info.name, port);
+    get_sink_info_internal(Some(info.name))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn get_sink_info_internal(name: Option<String>) -> CCResult<SinkInfo> {
+    let name = match name {
+        Some(name) => name,
+        None => run_command("pactl", &["get-default-sink"]).await?,
+    };
+    
+    list_sinks_internal()
+        .await?
+        .into_iter()
+        .find(|sink| sink.name == name)
+        .ok_or_else(|| ControlCentreError::InvalidArgument(format!("Unknown sink: {}", name)))
+}
+
+pub(crate) async fn list_sinks_internal() -> CCResult<Vec<SinkInfo>> {
+    let json = run_command("pactl", &["-f", "json", "list", "sinks"]).await?;
+    pactl::parse_sinks(&json)
+}
+
+// ============================================================================
+// Audio Snapshot Commands (pactl)
+// ============================================================================
+
+/// Volume of one application's streams in an `AudioSnapshot`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppVolume {
+    /// `application.name` of the stream
+    pub app: String,
+    pub volume: u8,
+    pub muted: bool,
+}
+
+/// Audio configuration that can be saved and reapplied later
+///
+/// Fields that could not be read when the snapshot was taken are `None`
+/// and left alone on restore.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSnapshot {
+    pub default_sink: Option<String>,
+    pub default_source: Option<String>,
+    pub sink_volume: Option<u8>,
+    pub sink_muted: Option<bool>,
+    pub source_volume: Option<u8>,
+    pub source_muted: Option<bool>,
+    pub app_volumes: Vec<AppVolume>,
+}
+
+/// Outcome of each field applied by `restore_audio`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioRestoreResults {
+    pub default_sink: DomainState<String>,
+    pub default_source: DomainState<String>,
+    pub sink_volume: DomainState<u8>,
+    pub sink_muted: DomainState<bool>,
+    pub source_volume: DomainState<u8>,
+    pub source_muted: DomainState<bool>,
+    /// Number of running streams whose volume was restored
+    pub app_volumes: DomainState<usize>,
+}
+
+/// Capture the default devices, their volumes and per-app volumes
+#[tauri::command]
+pub async fn snapshot_audio() -> Result<AudioSnapshot, String> {
+    Ok(snapshot_audio_internal().await)
+}
+
+/// Reapply a snapshot taken with `snapshot_audio`
+///
+/// Every field is applied concurrently and reports its own result.
+/// Per-app volumes apply to streams of those apps that are running now.
+/// The sink volume is capped by the sink's `sink_limits` entry.
+#[tauri::command]
+pub async fn restore_audio(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    snapshot: AudioSnapshot,
+) -> Result<AudioRestoreResults, String> {
+    let results = restore_audio_internal(&snapshot, &config.get().sink_limits).await;
+    state.invalidate_all();
+    Ok(results)
+}
+
+/// Take a snapshot and store it under `name`
+#[tauri::command]
+pub async fn save_audio_snapshot(name: String) -> Result<AudioSnapshot, String> {
+    let path = audio_snapshot_path(&name).map_err(|e| e.to_string())?;
+    let snapshot = snapshot_audio_internal().await;
+    
+    let contents = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(audio_snapshot_dir()).map_err(|e| e.to_string())?;
+    std::fs::write(&path, contents).map_err(|e| e.to_string())?;
+    
+    info!("Saved audio snapshot {} to {}", name, path.display());
+    Ok(snapshot)
+}
+
+/// Restore the snapshot stored under `name`
+#[tauri::command]
+pub async fn load_audio_snapshot(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    name: String,
+) -> Result<AudioRestoreResults, String> {
+    let path = audio_snapshot_path(&name).map_err(|e| e.to_string())?;
+    let contents = std::fs::read_to_string(&path).map_err(|_| {
+        ControlCentreError::InvalidArgument(format!("No audio snapshot named {}", name)).to_string()
+    })?;
+    let snapshot: AudioSnapshot = serde_json::from_str(&contents)
+        .map_err(|e| ControlCentreError::ParseError(e.to_string()).to_string())?;
+    
+    info!("Restoring audio snapshot {}", name);
+    restore_audio(state, config, snapshot).await
+}
+
+fn audio_snapshot_dir() -> std::path::PathBuf {
+    Config::dir().join("audio-snapshots")
+}
+
+/// File of a named snapshot; names are limited to letters, digits, `-` and `_`
+fn audio_snapshot_path(name: &str) -> CCResult<std::path::PathBuf> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Invalid snapshot name: {}",
+            name
+        )));
+    }
+    Ok(audio_snapshot_dir().join(format!("{}.json", name)))
+}
+
+async fn snapshot_audio_internal() -> AudioSnapshot {
+    let (sink, source, inputs) = tokio::join!(
+        get_sink_info_internal(None),
+        get_source_info_internal(),
+        list_sink_inputs_internal(),
+    );
+    
+    let sink = sink.map_err(|e| warn!("Audio snapshot without sink: {}", e)).ok();
+    let source = source.map_err(|e| warn!("Audio snapshot without source: {}", e)).ok();
+    
+    let mut app_volumes: Vec<AppVolume> = Vec::new();
+    for input in inputs.unwrap_or_default() {
+        if !input.app_name.is_empty() && !app_volumes.iter().any(|a| a.app == input.app_name) {
+            app_volumes.push(AppVolume {
+                app: input.app_name,
+                volume: input.volume,
+                muted: input.muted,
+            });
+        }
+    }
+    
+    AudioSnapshot {
+        default_sink: sink.as_ref().map(|s| s.name.clone()),
+        default_source: source.as_ref().map(|s| s.name.clone()),
+        sink_volume: sink.as_ref().map(|s| s.volume),
+        sink_muted: sink.as_ref().map(|s| s.muted),
+        source_volume: source.as_ref().map(|s| s.volume),
+        source_muted: source.as_ref().map(|s| s.muted),
+        app_volumes,
+    }
+}
+
+async fn restore_audio_internal(
+    snapshot: &AudioSnapshot,
+    limits: &[SinkLimit],
+) -> AudioRestoreResults {
+    // Address devices by name so volumes don't depend on the default switching first
+    let sink = snapshot.default_sink.as_deref().unwrap_or("@DEFAULT_SINK@");
+    let source = snapshot.default_source.as_deref().unwrap_or("@DEFAULT_SOURCE@");
+    let flag = |muted: bool| if muted { "1" } else { "0" };
+    
+    let (
+        default_sink,
+        default_source,
+        sink_volume,
+        sink_muted,
+        source_volume,
+        source_muted,
+        app_volumes,
+    ) = tokio::join!(
+        query_domain(snapshot.default_sink.is_some(), async {
+            run_command_no_output("pactl", &["set-default-sink", sink]).await?;
+            Ok(sink.to_string())
+        }),
+        query_domain(snapshot.default_source.is_some(), async {
+            run_command_no_output("pactl", &["set-default-source", source]).await?;
+            Ok(source.to_string())
+        }),
+        query_domain(snapshot.sink_volume.is_some(), async {
+            let volume = snapshot.sink_volume.unwrap_or_default().min(100);
+            let volume = match snapshot.default_sink.as_deref() {
+                Some(sink) => cap_sink_volume(limits, sink, volume),
+                None => limit_default_sink_volume(limits, volume).await?,
+            };
+            run_command_no_output("pactl", &["set-sink-volume", sink, &format!("{}%", volume)]).await?;
+            Ok(volume)
+        }),
+        query_domain(snapshot.sink_muted.is_some(), async {
+            let muted = snapshot.sink_muted.unwrap_or_default();
+            run_command_no_output("pactl", &["set-sink-mute", sink, flag(muted)]).await?;
+            Ok(muted)
+        }),
+        query_domain(snapshot.source_volume.is_some(), async {
+            let volume = snapshot.source_volume.unwrap_or_default().min(100);
+            run_command_no_output("pactl", &["set-source-volume", source, &format!("{}%", volume)]).await?;
+            Ok(volume)
+        }),
+        query_domain(snapshot.source_muted.is_some(), async {
+            let muted = snapshot.source_muted.unwrap_or_default();
+            run_command_no_output("pactl", &["set-source-mute", source, flag(muted)]).await?;
+            Ok(muted)
+        }),
+        query_domain(!snapshot.app_volumes.is_empty(), restore_app_volumes(&snapshot.app_volumes)),
+    );
+    
+    AudioRestoreResults {
+        default_sink,
+        default_source,
+        sink_volume,
+        sink_muted,
+        source_volume,
+        source_muted,
+        app_volumes,
+    }
+}
+
+/// Apply saved per-app volumes to the matching running streams
+///
+/// A stream that fails is logged and skipped; returns how many succeeded.
+async fn restore_app_volumes(app_volumes: &[AppVolume]) -> CCResult<usize> {
+    let mut restored = 0;
+    
+    for input in list_sink_inputs_internal().await? {
+        let Some(saved) = app_volumes.iter().find(|a| a.app == input.app_name) else {
+            continue;
+        };
+        let index = input.index.to_string();
+        let volume = format!("{}%", saved.volume.min(100));
+        let mute = if saved.muted { "1" } else { "0" };
+        
+        let volume_args = ["set-sink-input-volume", &index, &volume];
+        let mute_args = ["set-sink-input-mute", &index, mute];
+        let result = tokio::try_join!(
+            run_command_no_output("pactl", &volume_args),
+            run_command_no_output("pactl", &mute_args),
+        );
+        match result {
+            Ok(_) => restored += 1,
+            Err(e) => warn!("Failed to restore volume of {}: {}", saved.app, e),
+        }
+    }
+    
+    Ok(restored)
+}
+
+/// Mute states from before a panic mute, by device name and stream index
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PanicMuteRestore {
+    pub(crate) sink: Option<(String, bool)>,
+    pub(crate) source: Option<(String, bool)>,
+    pub(crate) inputs: Vec<(u32, bool)>,
+}
+
+/// Outcome of `panic_mute` and `panic_unmute`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PanicMuteResult {
+    /// The default output was (un)muted
+    pub sink: bool,
+    /// The default microphone was (un)muted
+    pub source: bool,
+    /// Number of application streams (un)muted
+    pub streams: usize,
+}
+
+/// Mute the default output, the microphone and every stream at once
+///
+/// The previous mute states are kept for `panic_unmute`. Also reachable
+/// from a compositor keybinding through `control-centre panic-mute`.
+#[tauri::command]
+pub async fn panic_mute(state: State<'_, AppState>) -> Result<PanicMuteResult, String> {
+    Ok(panic_mute_internal(&state).await)
+}
+
+/// Restore the mute states from before `panic_mute`
+#[tauri::command]
+pub async fn panic_unmute(state: State<'_, AppState>) -> Result<PanicMuteResult, String> {
+    panic_unmute_internal(&state).await.map_err(|e| e.to_string())
+}
+
+/// Panic mute, or undo it if it is active; used by the socket command
+pub(crate) async fn toggle_panic_mute(state: &AppState) -> CCResult<PanicMuteResult> {
+    if state.is_panic_muted() {
+        panic_unmute_internal(state).await
+    } else {
+        Ok(panic_mute_internal(state).await)
+    }
+}
+
+async fn panic_mute_internal(state: &AppState) -> PanicMuteResult {
+    let (sink, source, inputs) = tokio::join!(
+        get_sink_info_internal(None),
+        get_source_info_internal(),
+        list_sink_inputs_internal(),
+    );
+    let sink = sink.map_err(|e| warn!("Panic mute without sink: {}", e)).ok();
+    let source = source.map_err(|e| warn!("Panic mute without source: {}", e)).ok();
+    let inputs = inputs.unwrap_or_default();
+    
+    let restore = PanicMuteRestore {
+        sink: sink.as_ref().map(|s| (s.name.clone(), s.muted)),
+        source: source.as_ref().map(|s| (s.name.clone(), s.muted)),
+        inputs: inputs.iter().map(|input| (input.index, input.muted)).collect(),
+    };
+    let muted = PanicMuteRestore {
+        sink: restore.sink.as_ref().map(|(name, _)| (name.clone(), true)),
+        source: restore.source.as_ref().map(|(name, _)| (name.clone(), true)),
+        inputs: restore.inputs.iter().map(|(index, _)| (*index, true)).collect(),
+    };
+    state.store_panic_mute(restore);
+    
+    let result = apply_mute_states(muted).await;
+    state.invalidate(CacheDomain::Volume);
+    info!("Panic mute: {} streams muted", result.streams);
+    result
+}
+
+async fn panic_unmute_internal(state: &AppState) -> CCResult<PanicMuteResult> {
+    let restore = state.take_panic_mute().ok_or_else(|| {
+        ControlCentreError::InvalidArgument("Panic mute is not active".to_string())
+    })?;
+    
+    let result = apply_mute_states(restore).await;
+    state.invalidate(CacheDomain::Volume);
+    info!("Panic mute ended: {} streams restored", result.streams);
+    Ok(result)
+}
+
+/// Set the mute state of the devices and streams concurrently
+///
+/// Failures are logged and left out of the result; a stream that ended
+/// in the meantime simply no longer exists.
+async fn apply_mute_states(states: PanicMuteRestore) -> PanicMuteResult {
+    fn flag(muted: bool) -> &'static str {
+        if muted { "1" } else { "0" }
+    }
+    
+    let mut streams = tokio::task::JoinSet::new();
+    for (index, muted) in states.inputs {
+        streams.spawn(async move {
+            let index = index.to_string();
+            run_command_no_output("pactl", &["set-sink-input-mute", &index, flag(muted)]).await
+        });
+    }
+    
+    let set_device = |command: &'static str, device: Option<(String, bool)>| async move {
+        let Some((name, muted)) = device else {
+            return false;
+        };
+        match run_command_no_output("pactl", &[command, &name, flag(muted)]).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to set mute of {}: {}", name, e);
+                false
+            }
+        }
+    };
+    let (sink, source) = tokio::join!(
+        set_device("set-sink-mute", states.sink),
+        set_device("set-source-mute", states.source),
+    );
+    
+    let mut count = 0;
+    while let Some(result) = streams.join_next().await {
+        match result {
+            Ok(Ok(())) => count += 1,
+            Ok(Err(e)) => debug!("Stream mute not applied: {}", e),
+            Err(e) => warn!("Stream mute task failed: {}", e),
+        }
+    }
+    
+    PanicMuteResult {
+        sink,
+        source,
+        streams: count,
+    }
+}
+
+async fn get_source_info_internal() -> CCResult<SinkInfo> {
+    let name = run_command("pactl", &["get-default-source"]).await?;
+    let json = run_command("pactl", &["-f", "json", "list", "sources"]).await?;
+    pactl::parse_sources(&json)?
+        .into_iter()
+        .find(|source| source.name == name)
+        .ok_or_else(|| ControlCentreError::InvalidArgument(format!("Unknown source: {}", name)))
+}
+
+async fn list_sink_inputs_internal() -> CCResult<Vec<SinkInput>> {
+    let json = run_command("pactl", &["-f", "json", "list", "sink-inputs"]).await?;
+    pactl::parse_sink_inputs(&json)
+}
+
+// ============================================================================
+// Microphone Level Commands (parec)
+// ============================================================================
+
+/// Sample rate recorded for level metering; plenty for a peak meter
+const MIC_SAMPLE_RATE: usize = 16000;
+
+/// Bytes of mono s16le audio sampled by `get_mic_level` (200ms)
+const MIC_LEVEL_BYTES: usize = MIC_SAMPLE_RATE * 2 / 5;
+
+/// Bytes of audio behind each `mic-level` event (50ms)
+const MIC_METER_BYTES: usize = MIC_SAMPLE_RATE * 2 / 20;
+
+/// Longest `get_mic_level` waits for its sample
+const MIC_LEVEL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Sample the default microphone briefly and return its peak level (0.0-1.0)
+#[tauri::command]
+pub async fn get_mic_level() -> Result<f32, String> {
+    get_mic_level_internal().await.map_err(|e| e.to_string())
+}
+
+async fn get_mic_level_internal() -> CCResult<f32> {
+    let mut child = spawn_mic_recorder()?;
+    let mut stdout = child.stdout.take().ok_or_else(|| {
+        ControlCentreError::SystemError("parec has no output".to_string())
+    })?;
+    
+    let mut sample = vec![0; MIC_LEVEL_BYTES];
+    tokio::time::timeout(MIC_LEVEL_TIMEOUT, stdout.read_exact(&mut sample))
+        .await
+        .map_err(|_| ControlCentreError::Timeout("No audio from the microphone".to_string()))??;
+    Ok(peak_level(&sample))
+}
+
+/// Emit `mic-level` events with the peak level about 20 times a second
+///
+/// The recording runs until `stop_mic_monitor` or the window hides.
+#[tauri::command]
+pub async fn start_mic_monitor(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let mut child = spawn_mic_recorder().map_err(|e| e.to_string())?;
+    let Some(mut stdout) = child.stdout.take() else {
+        return Err(ControlCentreError::SystemError("parec has no output".to_string()).to_string());
+    };
+    
+    let task = tauri::async_runtime::spawn(async move {
+        // The child is killed when this task is aborted and drops it
+        let _child = child;
+        let mut chunk = vec![0; MIC_METER_BYTES];
+        while stdout.read_exact(&mut chunk).await.is_ok() {
+            if let Err(e) = app.emit("mic-level", peak_level(&chunk)) {
+                warn!("Failed to emit mic-level: {}", e);
+            }
+        }
+        debug!("Microphone monitor ended");
+    });
+    
+    state.replace_mic_monitor(Some(task));
+    info!("Microphone monitor started");
+    Ok(())
+}
+
+/// Stop the `mic-level` events and the recording behind them
+#[tauri::command]
+pub async fn stop_mic_monitor(state: State<'_, AppState>) -> Result<(), String> {
+    stop_mic_monitor_internal(&state);
+    Ok(())
+}
+
+pub(crate) fn stop_mic_monitor_internal(state: &AppState) {
+    if state.replace_mic_monitor(None) {
+        info!("Microphone monitor stopped");
+    }
+}
+
+/// Record the default source as raw mono s16le
+fn spawn_mic_recorder() -> CCResult<tokio::process::Child> {
+    let rate = format!("--rate={}", MIC_SAMPLE_RATE);
+    tool_command("parec")
+        .args(["--raw", "--format=s16le", "--channels=1", &rate, "--latency-msec=50"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            warn!("Cannot start parec: {}", e);
+            ControlCentreError::from(e)
+        })
+}
+
+/// Peak amplitude of s16le samples, from 0.0 (silence) to 1.0 (full scale)
+fn peak_level(samples: &[u8]) -> f32 {
+    let peak = samples
+        .chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]).unsigned_abs())
+        .max()
+        .unwrap_or(0);
+    (f32::from(peak) / 32768.0).min(1.0)
+}
+
+// ============================================================================
+// Brightness Commands (brightnessctl)
+// ============================================================================
+
+/// Get current brightness percentage (0-100)
+#[tauri::command]
+pub async fn get_brightness(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+) -> Result<u8, String> {
+    if let Some(cached) = state.get_cached_brightness() {
+        return Ok(cached);
+    }
+    
+    let read = get_brightness_internal().await.map_err(|e| e.to_string())?;
+    let brightness = settle_brightness(&state, &config.get(), read);
+    
+    state.set_cached_brightness(brightness);
+    Ok(brightness)
+}
+
+/// Set brightness percentage (1-100)
+///
+/// Out-of-range values are rejected or clamped depending on `mode`, which
+/// defaults to the config's `value_mode`. The applied value never drops
+/// below 1% so the screen stays visible; the applied value is returned.
+#[tauri::command]
+pub async fn set_brightness(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    value: i32,
+    mode: Option<ValueMode>,
+) -> Result<u8, String> {
+    let mode = mode.unwrap_or(config.get().value_mode);
+    let value = normalize_percentage(value, mode).map_err(|e| e.to_string())?;
+    let safe_value = value.max(BRIGHTNESS_FLOOR);
+    
+    match set_brightness_internal(safe_value).await {
+        Ok(()) => {}
+        Err(e) if is_permission_error(&e) => {
+            let allowed = config.get().allow_privileged_brightness;
+            set_brightness_privileged(&state, safe_value, allowed)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Err(e) => return Err(e.to_string()),
+    }
+    auto_brightness::note_manual_change();
+    
+    state.set_cached_brightness(safe_value);
+    state.note_brightness_request(safe_value);
+    info!("Brightness set to {}%", safe_value);
+    
+    Ok(safe_value)
+}
+
+/// Ways to run brightnessctl as root
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Escalation {
+    /// `sudo -n`, when sudoers lets brightnessctl run without a password
+    Sudo,
+    /// `pkexec`, which asks through the polkit agent
+    Pkexec,
+}
+
+impl Escalation {
+    fn command(self) -> &'static str {
+        match self {
+            Escalation::Sudo => "sudo",
+            Escalation::Pkexec => "pkexec",
+        }
+    }
+}
+
+/// Whether a brightnessctl failure means the backlight is not writable
+fn is_permission_error(error: &ControlCentreError) -> bool {
+    match error {
+        ControlCentreError::PermissionDenied(_) => true,
+        ControlCentreError::CommandFailed(stderr) => {
+            stderr.contains("Permission denied") || stderr.contains("root privileges")
+        }
+        _ => false,
+    }
+}
+
+/// Find the least intrusive way to run brightnessctl as root
+async fn detect_brightness_escalation() -> Option<Escalation> {
+    if command_exists("sudo")
+        && run_command_no_output("sudo", &["-n", "-l", "brightnessctl"]).await.is_ok()
+    {
+        Some(Escalation::Sudo)
+    } else if command_exists("pkexec") {
+        Some(Escalation::Pkexec)
+    } else {
+        None
+    }
+}
+
+/// Retry a brightness change as root, if `allowed` by the config
+///
+/// Otherwise fails with `PermissionDenied` explaining how to fix the setup.
+/// The escalation is detected once per run. Writes are debounced by
+/// `PRIVILEGED_BRIGHTNESS_SETTLE` and serialized: only the latest value of
+/// a slider drag is written, by whichever call takes it, and the others
+/// return without running anything.
+async fn set_brightness_privileged(state: &AppState, value: u8, allowed: bool) -> CCResult<()> {
+    let escalation = *state
+        .brightness_escalation()
+        .get_or_init(detect_brightness_escalation)
+        .await;
+    let Some(escalation) = escalation.filter(|_| allowed) else {
+        return Err(ControlCentreError::PermissionDenied(brightness_permission_hint(
+            escalation,
+        )));
+    };
+    
+    PRIVILEGED_BRIGHTNESS.push(value);
+    tokio::time::sleep(PRIVILEGED_BRIGHTNESS_SETTLE).await;
+    let _write = PRIVILEGED_BRIGHTNESS_WRITE.lock().await;
+    let Some(value) = PRIVILEGED_BRIGHTNESS.take() else {
+        debug!("Privileged brightness write superseded by a newer one");
+        return Ok(());
+    };
+    
+    warn!("Backlight not writable, setting brightness via {}", escalation.command());
+    run_command_no_output(
+        escalation.command(),
+        &escalation_args(escalation, &["brightnessctl", "set", &format!("{}%", value)]),
+    )
+    .await
+}
+
+fn escalation_args<'a>(escalation: Escalation, command: &[&'a str]) -> Vec<&'a str> {
+    let mut args = match escalation {
+        Escalation::Sudo => vec!["-n"],
+        Escalation::Pkexec => Vec::new(),
+    };
+    args.extend_from_slice(command);
+    args
+}
+
+/// Guidance for a backlight the user cannot write
+fn brightness_permission_hint(escalation: Option<Escalation>) -> String {
+    let fix = "add your user to the video group or install brightnessctl's udev rules";
+    match escalation {
+        Some(escalation) => format!(
+            "the backlight needs root; {}, or set allow_privileged_brightness to use {}",
+            fix,
+            escalation.command()
+        ),
+        None => format!("the backlight needs root; {}", fix),
+    }
+}
+
+/// Adjust brightness by a signed delta, clamped to 1-100
+///
+/// Returns the applied value.
+#[tauri::command]
+pub async fn adjust_brightness(state: State<'_, AppState>, delta: i8) -> Result<u8, String> {
+    let current = match state.get_cached_brightness() {
+        Some(cached) => cached,
+        None => get_brightness_internal().await.map_err(|e| e.to_string())?,
+    };
+    let value = apply_delta(current, delta, BRIGHTNESS_FLOOR);
+    
+    set_brightness_internal(value).await.map_err(|e| e.to_string())?;
+    auto_brightness::note_manual_change();
+    
+    state.set_cached_brightness(value);
+    state.note_brightness_request(value);
+    info!("Brightness adjusted by {} to {}%", delta, value);
+    
+    Ok(value)
+}
+
+/// Nudge the brightness by the fine scroll step, returning the new value
+///
+/// Coalesced like `nudge_volume`; never drops below 1%.
+#[tauri::command]
+pub async fn nudge_brightness(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    up: bool,
+) -> Result<u8, String> {
+    let current = match BRIGHTNESS_NUDGES.pending().or_else(|| state.get_cached_brightness()) {
+        Some(current) => current,
+        None => get_brightness_internal().await.map_err(|e| e.to_string())?,
+    };
+    let value = apply_delta(current, nudge_delta(&config.get(), up), BRIGHTNESS_FLOOR);
+    state.set_cached_brightness(value);
+    state.note_brightness_request(value);
+    auto_brightness::note_manual_change();
+    
+    if BRIGHTNESS_NUDGES.push(value) {
+        tauri::async_runtime::spawn(async {
+            tokio::time::sleep(NUDGE_COALESCE_WINDOW).await;
+            if let Some(value) = BRIGHTNESS_NUDGES.take() {
+                if let Err(e) = set_brightness_internal(value).await {
+                    warn!("Failed to apply brightness nudge: {}", e);
+                }
+            }
+        });
+    }
+    
+    Ok(value)
+}
+
+/// Enable or disable brightness following the ambient light sensor
+///
+/// The choice is persisted to the config before the task starts. Fails,
+/// leaving auto brightness off, when enabling on a machine without a light
+/// sensor.
+#[tauri::command]
+pub async fn set_auto_brightness(
+    app: AppHandle,
+    config: State<'_, ConfigStore>,
+    enabled: bool,
+) -> Result<bool, String> {
+    config
+        .update(|c| c.auto_brightness.enabled = enabled)
+        .map_err(|e| e.to_string())?;
+    
+    if !enabled {
+        auto_brightness::stop();
+    } else if let Err(e) = auto_brightness::start(app) {
+        if let Err(revert) = config.update(|c| c.auto_brightness.enabled = false) {
+            warn!("Failed to turn auto brightness back off in the config: {}", revert);
+        }
+        return Err(e.to_string());
+    }
+    
+    Ok(enabled)
+}
+
+/// Smooth over brightnessctl rounding right after a brightness change
+fn settle_brightness(state: &AppState, config: &Config, read: u8) -> u8 {
+    let window = config
+        .brightness_hysteresis_ms
+        .unwrap_or(DEFAULT_BRIGHTNESS_HYSTERESIS_MS);
+    state.settle_brightness(read, std::time::Duration::from_millis(window))
+}
+
+/// Get whether auto brightness is enabled
+#[tauri::command]
+pub async fn get_auto_brightness(config: State<'_, ConfigStore>) -> Result<bool, String> {
+    Ok(config.get().auto_brightness.enabled)
+}
+
+/// Get maximum brightness value
+#[tauri::command]
+pub async fn get_max_brightness() -> Result<u32, String> {
+    let output = run_command("brightnessctl", &["max"])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    output
+        .trim()
+        .parse::<u32>()
+        .map_err(|e| format!("Failed to parse max brightness: {}", e))
+}
+
+// ============================================================================
+// External Brightness Commands (ddcutil)
+// ============================================================================
+
+/// VCP feature code of a monitor's brightness control
+const DDC_BRIGHTNESS_VCP: &str = "10";
+
+/// Brightness of a monitor controlled over DDC/CI
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalBrightness {
+    pub current: u32,
+    pub max: u32,
+}
+
+/// Get the brightness of an external monitor, by its `ddcutil detect` number
+///
+/// A DDC read takes seconds, so values are cached for longer than the rest
+/// of the state, survive cache invalidation and are read once in the
+/// background after startup. Reads take turns on the I2C bus.
+#[tauri::command]
+pub async fn get_external_brightness(
+    state: State<'_, AppState>,
+    display: u32,
+) -> Result<ExternalBrightness, String> {
+    if let Some(cached) = state.get_cached_external_brightness(display) {
+        return Ok(cached);
+    }
+    
+    read_external_brightness(&state, display)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Read every DDC monitor once so the panel's first query hits the cache
+async fn prime_external_brightness(state: &AppState) {
+    let detected = {
+        let _bus = state.ddc_bus().lock().await;
+        run_command("ddcutil", &["detect", "--brief"]).await
+    };
+    let displays = match detected {
+        Ok(output) => parse_ddc_displays(&output),
+        Err(e) => {
+            debug!("Skipping the DDC brightness pre-read: {}", ddc_error(e));
+            return;
+        }
+    };
+    
+    for display in displays {
+        if let Err(e) = read_external_brightness(state, display).await {
+            debug!("No DDC brightness for display {}: {}", display, e);
+        }
+    }
+}
+
+/// Read a monitor's brightness into the cache, one `ddcutil` call at a time
+async fn read_external_brightness(state: &AppState, display: u32) -> CCResult<ExternalBrightness> {
+    if display == 0 {
+        return Err(ControlCentreError::InvalidArgument(
+            "ddcutil display numbers start at 1".to_string(),
+        ));
+    }
+    
+    let _bus = state.ddc_bus().lock().await;
+    // Another caller may have read it while this one waited for the bus
+    if let Some(cached) = state.get_cached_external_brightness(display) {
+        return Ok(cached);
+    }
+    
+    let number = display.to_string();
+    let output = run_command(
+        "ddcutil",
+        &["--display", &number, "--brief", "getvcp", DDC_BRIGHTNESS_VCP],
+    )
+    .await
+    .map_err(ddc_error)?;
+    let brightness = parse_ddc_brightness(&output)?;
+    
+    state.set_cached_external_brightness(display, brightness);
+    Ok(brightness)
+}
+
+/// Point I2C permission errors at the `i2c` group, the usual DDC setup issue
+fn ddc_error(error: ControlCentreError) -> ControlCentreError {
+    match &error {
+        ControlCentreError::CommandFailed(stderr)
+            if stderr.contains("Permission denied") || stderr.contains("EACCES") =>
+        {
+            ControlCentreError::PermissionDenied(
+                "Cannot open the monitor's I2C bus; add yourself to the i2c group \
+                 (sudo usermod -aG i2c $USER) and log in again"
+                    .to_string(),
+            )
+        }
+        _ => error,
+    }
+}
+
+/// Parse `VCP 10 C <current> <max>` from `ddcutil --brief getvcp 10`
+fn parse_ddc_brightness(output: &str) -> CCResult<ExternalBrightness> {
+    let invalid =
+        || ControlCentreError::ParseError(format!("Unexpected ddcutil output: {}", output));
+    
+    let line = output
+        .lines()
+        .find(|line| line.starts_with("VCP "))
+        .ok_or_else(invalid)?;
+    match line.split_whitespace().collect::<Vec<_>>()[..] {
+        ["VCP", _, "C", current, max] => Ok(ExternalBrightness {
+            current: current.parse().map_err(|_| invalid())?,
+            max: max.parse().map_err(|_| invalid())?,
+        }),
+        _ => Err(invalid()),
+    }
+}
+
+/// Display numbers from `ddcutil detect --brief`, skipping invalid displays
+fn parse_ddc_displays(output: &str) -> Vec<u32> {
+    output
+        .lines()
+        .filter_map(|line| line.strip_prefix("Display ")?.trim().parse().ok())
+        .collect()
+}
+
+// ============================================================================
+// Flashlight Commands
+// ============================================================================
+
+/// Label of the full-white overlay window
+const FLASHLIGHT_WINDOW: &str = "flashlight";
+
+/// State saved while the flashlight is on, so a crash cannot leave the
+/// screen at full brightness for good
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FlashlightRestore {
+    brightness: u8,
+}
+
+fn flashlight_restore_path() -> PathBuf {
+    Config::dir().join("flashlight.json")
+}
+
+/// Turn the screen into a light: full brightness behind a white overlay
+///
+/// Toggling again closes the overlay and restores the earlier brightness.
+/// Returns whether the flashlight is now on.
+#[tauri::command]
+pub async fn toggle_flashlight(app: AppHandle, state: State<'_, AppState>) -> Result<bool, String> {
+    if let Some(prior) = state.take_flashlight() {
+        if let Some(window) = app.get_webview_window(FLASHLIGHT_WINDOW) {
+            let _ = window.close();
+        }
+        restore_flashlight_brightness(&state, prior).await;
+        return Ok(false);
+    }
+    
+    let prior = match state.get_cached_brightness() {
+        Some(cached) => cached,
+        None => get_brightness_internal().await.map_err(|e| e.to_string())?,
+    };
+    let restore = serde_json::to_string(&FlashlightRestore { brightness: prior })
+        .map_err(|e| e.to_string())?;
+    let path = flashlight_restore_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, restore).map_err(|e| e.to_string())?;
+    state.set_flashlight(prior);
+    
+    set_brightness_internal(100).await.map_err(|e| e.to_string())?;
+    auto_brightness::note_manual_change();
+    state.set_cached_brightness(100);
+    
+    let overlay = tauri::WebviewWindowBuilder::new(
+        &app,
+        FLASHLIGHT_WINDOW,
+        tauri::WebviewUrl::App("flashlight.html".into()),
+    )
+    .title("Flashlight")
+    .fullscreen(true)
+    .decorations(false)
+    .always_on_top(true)
+    .skip_taskbar(true)
+    .build();
+    if let Err(e) = overlay {
+        warn!("Failed to open flashlight overlay, using brightness only: {}", e);
+    }
+    
+    info!("Flashlight on (brightness was {}%)", prior);
+    Ok(true)
+}
+
+async fn restore_flashlight_brightness(state: &AppState, brightness: u8) {
+    match set_brightness_internal(brightness).await {
+        Ok(()) => {
+            state.set_cached_brightness(brightness);
+            info!("Flashlight off, brightness restored to {}%", brightness);
+        }
+        Err(e) => warn!("Failed to restore brightness after flashlight: {}", e),
+    }
+    let _ = std::fs::remove_file(flashlight_restore_path());
+}
+
+/// Restore the brightness of a flashlight left on by a previous run
+pub(crate) async fn restore_interrupted_flashlight(state: &AppState) {
+    let Ok(saved) = std::fs::read_to_string(flashlight_restore_path()) else {
+        return;
+    };
+    match serde_json::from_str::<FlashlightRestore>(&saved) {
+        Ok(restore) => {
+            info!("Flashlight was left on, restoring brightness");
+            restore_flashlight_brightness(state, restore.brightness).await;
+        }
+        Err(e) => {
+            warn!("Ignoring invalid flashlight state: {}", e);
+            let _ = std::fs::remove_file(flashlight_restore_path());
+        }
+    }
+}
+
+// ============================================================================
+// Keyboard RGB Commands (OpenRGB)
+// ============================================================================
+
+/// Get the keyboard color last applied via `set_kbd_rgb`, as `#RRGGBB`
+///
+/// OpenRGB's CLI cannot report the current color, so this is `None` until
+/// a color has been set in this session.
+#[tauri::command]
+pub async fn get_kbd_rgb(state: State<'_, AppState>) -> Result<Option<String>, String> {
+    require_openrgb().map_err(|e| e.to_string())?;
+    Ok(state.get_kbd_rgb())
+}
+
+/// Set a static keyboard color from a `#RRGGBB` or `RRGGBB` string
+///
+/// Returns the applied color as `#RRGGBB`.
+#[tauri::command]
+pub async fn set_kbd_rgb(state: State<'_, AppState>, hex: String) -> Result<String, String> {
+    let color = parse_hex_color(&hex).map_err(|e| e.to_string())?;
+    require_openrgb().map_err(|e| e.to_string())?;
+    
+    run_command_no_output("openrgb", &["--mode", "static", "--color", &color])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    let color = format!("#{}", color);
+    state.set_kbd_rgb(color.clone());
+    info!("Keyboard color set to {}", color);
+    
+    Ok(color)
+}
+
+fn require_openrgb() -> CCResult<()> {
+    if command_exists("openrgb") {
+        Ok(())
+    } else {
+        Err(ControlCentreError::CommandNotFound("openrgb".to_string()))
+    }
+}
+
+/// Validate a hex color, returning it as uppercase `RRGGBB` for OpenRGB
+fn parse_hex_color(hex: &str) -> CCResult<String> {
+    let digits = hex.trim().strip_prefix('#').unwrap_or(hex.trim());
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Invalid color '{}', expected #RRGGBB",
+            hex
+        )));
+    }
+    
+    Ok(digits.to_ascii_uppercase())
+}
+
+// ============================================================================
+// Radio Backends (NetworkManager / bluetoothctl, rfkill fallback)
+// ============================================================================
+
+/// Tools capable of switching a radio
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RadioBackend {
+    /// NetworkManager via nmcli (WiFi only)
+    NetworkManager,
+    /// BlueZ via bluetoothctl (Bluetooth only)
+    Bluetoothctl,
+    /// Kernel rfkill switches, available without any daemon
+    Rfkill,
+}
+
+/// Backends used for WiFi and Bluetooth, chosen once at startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RadioBackends {
+    pub wifi: RadioBackend,
+    pub bluetooth: RadioBackend,
+}
+
+impl RadioBackends {
+    /// Prefer the native tools and fall back to rfkill when they are missing
+    pub fn detect() -> Self {
+        let pick = |native: &str, backend| {
+            if command_exists(native) || !command_exists("rfkill") {
+                backend
+            } else {
+                RadioBackend::Rfkill
+            }
+        };
+        
+        Self {
+            wifi: pick("nmcli", RadioBackend::NetworkManager),
+            bluetooth: pick("bluetoothctl", RadioBackend::Bluetoothctl),
+        }
+    }
+}
+
+/// A radio switch reported by rfkill
+#[derive(Debug, Clone, PartialEq)]
+struct RfkillDevice {
+    /// `wlan` or `bluetooth` (other types are kept verbatim)
+    kind: String,
+    blocked: bool,
+}
+
+/// Parse `rfkill --json`
+///
+/// The array key differs between util-linux versions (`rfkilldevices` or
+/// empty), so the first array in the object is used.
+fn parse_rfkill_json(json: &str) -> CCResult<Vec<RfkillDevice>> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| ControlCentreError::ParseError(e.to_string()))?;
+    
+    let devices = value
+        .as_object()
+        .and_then(|map| map.values().find_map(|v| v.as_array()))
+        .ok_or_else(|| ControlCentreError::ParseError(format!("Unexpected rfkill JSON: {}", json)))?;
+    
+    Ok(devices
+        .iter()
+        .map(|device| {
+            let field = |name: &str| device.get(name).and_then(|v| v.as_str()).unwrap_or_default();
+            RfkillDevice {
+                kind: field("type").to_string(),
+                blocked: field("soft") == "blocked" || field("hard") == "blocked",
+            }
+        })
+        .collect())
+}
+
+/// Parse the plain `rfkill list` output of older util-linux versions
+fn parse_rfkill_list(output: &str) -> Vec<RfkillDevice> {
+    let mut devices: Vec<RfkillDevice> = Vec::new();
+    
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(value) = trimmed
+            .strip_prefix("Soft blocked:")
+            .or_else(|| trimmed.strip_prefix("Hard blocked:"))
+        {
+            if let Some(device) = devices.last_mut() {
+                device.blocked |= value.trim() == "yes";
+            }
+        } else if !line.starts_with(char::is_whitespace) && !trimmed.is_empty() {
+            // Device header: "<id>: <name>: <type>"
+            let kind = match trimmed.rsplit(": ").next().unwrap_or_default() {
+                "Wireless LAN" => "wlan",
+                "Bluetooth" => "bluetooth",
+                other => other,
+            };
+            devices.push(RfkillDevice {
+                kind: kind.to_lowercase(),
+                blocked: false,
+            });
+        }
+    }
+    
+    devices
+}
+
+/// Whether any radio of the given rfkill type is unblocked
+async fn get_rfkill_enabled_internal(kind: &str) -> CCResult<bool> {
+    let devices = match run_command("rfkill", &["--json"]).await {
+        Ok(json) => parse_rfkill_json(&json)?,
+        Err(_) => parse_rfkill_list(&run_command("rfkill", &["list"]).await?),
+    };
+    
+    Ok(devices.iter().any(|d| d.kind == kind && !d.blocked))
+}
+
+async fn set_rfkill_enabled_internal(kind: &str, enabled: bool) -> CCResult<()> {
+    let action = if enabled { "unblock" } else { "block" };
+    run_command_no_output("rfkill", &[action, kind]).await
+}
+
+// ============================================================================
+// WiFi Commands (NetworkManager via nmcli)
+// ============================================================================
+
+/// Get WiFi enabled status
+#[tauri::command]
+pub async fn get_wifi_status(state: State<'_, AppState>) -> Result<NetworkState, String> {
+    let network = get_network_state_internal(&state)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    state.set_cached_wifi(network.wifi_enabled);
+    state.set_cached_bluetooth(network.bluetooth_enabled);
+    
+    Ok(network)
+}
+
+/// Enable or disable WiFi
+#[tauri::command]
+pub async fn set_wifi_enabled(state: State<'_, AppState>, enabled: bool) -> Result<bool, String> {
+    let backend = state.radio_backends().wifi;
+    switch_wifi_radio(backend, enabled)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    // A hard block (physical switch) can keep the radio off despite success
+    tokio::time::sleep(RADIO_SETTLE_DELAY).await;
+    let actual = confirm_radio_state("WiFi", enabled, get_wifi_enabled_internal(backend).await);
+    
+    state.set_cached_wifi(actual);
+    info!("WiFi set to {}", if actual { "on" } else { "off" });
+    
+    Ok(actual)
+}
+
+/// Switch the WiFi radio without reading it back
+async fn switch_wifi_radio(backend: RadioBackend, enabled: bool) -> CCResult<()> {
+    match backend {
+        RadioBackend::Rfkill => set_rfkill_enabled_internal("wlan", enabled).await,
+        _ => {
+            let arg = if enabled { "on" } else { "off" };
+            run_command_no_output("nmcli", &["radio", "wifi", arg]).await
+        }
+    }
+}
+
+/// Compare the re-read radio state with the requested one
+///
+/// Falls back to the requested state if it could not be read back.
+fn confirm_radio_state(radio: &str, requested: bool, read_back: CCResult<bool>) -> bool {
+    match read_back {
+        Ok(actual) => {
+            if actual != requested {
+                let state = if actual { "on" } else { "off" };
+                warn!("{} is still {} after switching; is it hard-blocked?", radio, state);
+            }
+            actual
+        }
+        Err(e) => {
+            warn!("Failed to re-read {} state: {}", radio, e);
+            requested
+        }
+    }
+}
+
+async fn get_wifi_enabled_internal(backend: RadioBackend) -> CCResult<bool> {
+    match backend {
+        RadioBackend::Rfkill => get_rfkill_enabled_internal("wlan").await,
+        _ => parse_nmcli_radio(&run_command("nmcli", &["-t", "radio", "wifi"]).await?),
+    }
+}
+
+/// Parse the `enabled`/`disabled` state printed by `nmcli -t radio wifi`
+///
+/// Anything else (e.g. a translated word) is an error rather than "off".
+fn parse_nmcli_radio(output: &str) -> CCResult<bool> {
+    match output.trim().to_lowercase().as_str() {
+        "enabled" => Ok(true),
+        "disabled" => Ok(false),
+        other => Err(ControlCentreError::ParseError(format!(
+            "Unexpected nmcli radio state: {}",
+            other
+        ))),
+    }
+}
+
+// ============================================================================
+// Connection Commands (NetworkManager via nmcli)
+// ============================================================================
+
+/// Whether a NetworkManager connection is marked metered
+///
+/// NetworkManager's own guess (`yes (guessed)`) counts as metered.
+#[tauri::command]
+pub async fn get_metered(connection: String) -> Result<bool, String> {
+    get_metered_internal(&connection).await.map_err(|e| e.to_string())
+}
+
+/// Mark a NetworkManager connection as metered or not, returning the new value
+#[tauri::command]
+pub async fn set_metered(connection: String, metered: bool) -> Result<bool, String> {
+    ensure_connection_exists(&connection)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    let value = if metered { "yes" } else { "no" };
+    run_command_no_output("nmcli", &["connection", "modify", &connection, "connection.metered", value])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    info!("Connection {} metered set to {}", connection, value);
+    get_metered_internal(&connection).await.map_err(|e| e.to_string())
+}
+
+/// Whether NetworkManager joins a connection automatically
+#[tauri::command]
+pub async fn get_autoconnect(connection: String) -> Result<bool, String> {
+    get_autoconnect_internal(&connection).await.map_err(|e| e.to_string())
+}
+
+/// Enable or disable automatically joining a connection, returning the new value
+#[tauri::command]
+pub async fn set_autoconnect(connection: String, autoconnect: bool) -> Result<bool, String> {
+    ensure_connection_exists(&connection)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    let value = if autoconnect { "yes" } else { "no" };
+    run_command_no_output("nmcli", &["connection", "modify", &connection, "connection.autoconnect", value])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    info!("Connection {} autoconnect set to {}", connection, value);
+    get_autoconnect_internal(&connection).await.map_err(|e| e.to_string())
+}
+
+/// Get the powersave setting of a WiFi connection
+///
+/// `None` means the connection defers to NetworkManager's global default
+/// (or has powersave handling turned off entirely).
+#[tauri::command]
+pub async fn get_wifi_powersave(connection: String) -> Result<Option<bool>, String> {
+    get_wifi_powersave_internal(&connection)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Enable or disable WiFi powersave for a connection, returning the new value
+///
+/// Disabling it avoids latency spikes. An active connection is brought up
+/// again so the change takes effect immediately.
+#[tauri::command]
+pub async fn set_wifi_powersave(connection: String, on: bool) -> Result<Option<bool>, String> {
+    set_wifi_powersave_internal(&connection, on)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn get_wifi_powersave_internal(connection: &str) -> CCResult<Option<bool>> {
+    ensure_wifi_connection(connection).await?;
+    
+    let output = run_command(
+        "nmcli",
+        &["-t", "-f", "802-11-wireless.powersave", "connection", "show", connection],
+    )
+    .await?;
+    parse_nmcli_powersave(&output)
+}
+
+async fn set_wifi_powersave_internal(connection: &str, on: bool) -> CCResult<Option<bool>> {
+    ensure_wifi_connection(connection).await?;
+    
+    // NM_SETTING_WIRELESS_POWERSAVE_ENABLE / _DISABLE
+    let value = if on { "3" } else { "2" };
+    run_command_no_output(
+        "nmcli",
+        &["connection", "modify", connection, "802-11-wireless.powersave", value],
+    )
+    .await?;
+    
+    let active = run_command(
+        "nmcli",
+        &["-t", "-f", "NAME,UUID", "connection", "show", "--active"],
+    )
+    .await?;
+    let is_active = active.lines().any(|line| {
+        let (name, uuid) = split_nmcli_terse(line);
+        name == connection || uuid == connection
+    });
+    if is_active {
+        run_command_no_output("nmcli", &["connection", "up", connection]).await?;
+    }
+    
+    info!("Connection {} powersave set to {}", connection, if on { "on" } else { "off" });
+    get_wifi_powersave_internal(connection).await
+}
+
+/// Check that a connection exists and is a WiFi connection
+async fn ensure_wifi_connection(connection: &str) -> CCResult<()> {
+    ensure_connection_exists(connection).await?;
+    
+    let output = run_command(
+        "nmcli",
+        &["-t", "-f", "connection.type", "connection", "show", connection],
+    )
+    .await?;
+    let kind = output.trim().strip_prefix("connection.type:").unwrap_or(output.trim());
+    
+    if kind == "802-11-wireless" {
+        Ok(())
+    } else {
+        Err(ControlCentreError::InvalidArgument(format!(
+            "{} is not a WiFi connection",
+            connection
+        )))
+    }
+}
+
+/// Parse `802-11-wireless.powersave:<value>` from `nmcli -t`
+///
+/// nmcli prints the number, optionally followed by its name, e.g. `3 (enable)`.
+fn parse_nmcli_powersave(output: &str) -> CCResult<Option<bool>> {
+    let value = output
+        .trim()
+        .strip_prefix("802-11-wireless.powersave:")
+        .unwrap_or(output.trim());
+    
+    match value.split_whitespace().next() {
+        Some("3") | Some("enable") => Ok(Some(true)),
+        Some("2") | Some("disable") => Ok(Some(false)),
+        Some("0") | Some("default") | Some("1") | Some("ignore") => Ok(None),
+        _ => Err(ControlCentreError::ParseError(format!(
+            "Unexpected powersave value: {}",
+            value
+        ))),
+    }
+}
+
+/// Kind of the connection that currently carries traffic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionKind {
+    Wifi,
+    Ethernet,
+    Vpn,
+    None,
+}
+
+/// The connection the panel's network icon should represent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrimaryConnection {
+    pub kind: ConnectionKind,
+    /// Connection name as shown by NetworkManager
+    pub name: Option<String>,
+    /// WiFi signal strength in percent
+    pub strength: Option<u8>,
+}
+
+/// Get the primary active connection, preferring VPN over ethernet over WiFi
+#[tauri::command]
+pub async fn get_primary_connection() -> Result<PrimaryConnection, String> {
+    get_primary_connection_internal().await.map_err(|e| e.to_string())
+}
+
+async fn get_primary_connection_internal() -> CCResult<PrimaryConnection> {
+    let (devices, active) = tokio::join!(
+        run_command("nmcli", &["-t", "-f", "TYPE,STATE,CONNECTION", "device", "status"]),
+        run_command("nmcli", &["-t", "-f", "TYPE,NAME", "connection", "show", "--active"]),
+    );
+    // VPN plugin connections have no device of their own, so the active list
+    // is only a supplement
+    let (kind, name) = pick_primary_connection(&devices?, &active.unwrap_or_default());
+    
+    let strength = if kind == ConnectionKind::Wifi {
+        run_command("nmcli", &["-t", "-f", "ACTIVE,SIGNAL", "device", "wifi"])
+            .await
+            .ok()
+            .and_then(|output| {
+                output
+                    .lines()
+                    .find_map(|line| line.strip_prefix("yes:"))
+                    .and_then(|signal| signal.trim().parse::<u8>().ok())
+            })
+    } else {
+        None
+    };
+    
+    Ok(PrimaryConnection { kind, name, strength })
+}
+
+/// Pick the highest priority connection from `nmcli device status` and
+/// `nmcli connection show --active` terse output
+fn pick_primary_connection(devices: &str, active: &str) -> (ConnectionKind, Option<String>) {
+    let classify = |kind: &str| match kind {
+        "wifi" | "802-11-wireless" => Some(ConnectionKind::Wifi),
+        "ethernet" | "802-3-ethernet" => Some(ConnectionKind::Ethernet),
+        "vpn" | "wireguard" | "tun" => Some(ConnectionKind::Vpn),
+        _ => None,
+    };
+    let priority = |kind: ConnectionKind| match kind {
+        ConnectionKind::Vpn => 3,
+        ConnectionKind::Ethernet => 2,
+        ConnectionKind::Wifi => 1,
+        ConnectionKind::None => 0,
+    };
+    
+    let from_devices = devices.lines().filter_map(|line| {
+        let (kind, rest) = split_nmcli_terse(line);
+        let (state, connection) = split_nmcli_terse(&rest);
+        let name = split_nmcli_terse(&connection).0;
+        (state == "connected" && !name.is_empty()).then_some((classify(&kind)?, name))
+    });
+    let from_active = active.lines().filter_map(|line| {
+        let (kind, name) = split_nmcli_terse(line);
+        let name = split_nmcli_terse(&name).0;
+        Some((classify(&kind)?, name))
+    });
+    
+    from_devices
+        .chain(from_active)
+        .max_by_key(|(kind, _)| priority(*kind))
+        .map(|(kind, name)| (kind, Some(name)))
+        .unwrap_or((ConnectionKind::None, None))
+}
+
+async fn get_metered_internal(connection: &str) -> CCResult<bool> {
+    ensure_connection_exists(connection).await?;
+    
+    let output = run_command(
+        "nmcli",
+        &["-t", "-f", "connection.metered", "connection", "show", connection],
+    )
+    .await?;
+    parse_nmcli_metered(&output)
+}
+
+async fn get_autoconnect_internal(connection: &str) -> CCResult<bool> {
+    ensure_connection_exists(connection).await?;
+    
+    let output = run_command(
+        "nmcli",
+        &["-t", "-f", "connection.autoconnect", "connection", "show", connection],
+    )
+    .await?;
+    parse_nmcli_autoconnect(&output)
+}
+
+/// Check a connection name or UUID against `nmcli connection show`
+async fn ensure_connection_exists(connection: &str) -> CCResult<()> {
+    let output = run_command("nmcli", &["-t", "-f", "NAME,UUID", "connection", "show"]).await?;
+    
+    let exists = output.lines().any(|line| {
+        let (name, uuid) = split_nmcli_terse(line);
+        name == connection || uuid == connection
+    });
+    
+    if exists {
+        Ok(())
+    } else {
+        Err(ControlCentreError::InvalidArgument(format!(
+            "Unknown connection: {}",
+            connection
+        )))
+    }
+}
+
+/// Split a two-field `nmcli -t` line, unescaping `\:` inside the first field
+fn split_nmcli_terse(line: &str) -> (String, String) {
+    let mut first = String::new();
+    let mut chars = line.chars();
+    
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => first.extend(chars.next()),
+            ':' => break,
+            _ => first.push(c),
+        }
+    }
+    
+    (first, chars.collect())
+}
+
+/// Parse `connection.metered:<value>` from `nmcli -t`
+fn parse_nmcli_metered(output: &str) -> CCResult<bool> {
+    let value = output
+        .trim()
+        .strip_prefix("connection.metered:")
+        .unwrap_or(output.trim());
+    
+    match value.split_whitespace().next() {
+        Some("yes") => Ok(true),
+        Some("no") | Some("unknown") => Ok(false),
+        _ => Err(ControlCentreError::ParseError(format!(
+            "Unexpected metered value: {}",
+            value
+        ))),
+    }
+}
+
+/// Parse `connection.autoconnect:<yes|no>` from `nmcli -t`
+fn parse_nmcli_autoconnect(output: &str) -> CCResult<bool> {
+    let value = output
+        .trim()
+        .strip_prefix("connection.autoconnect:")
+        .unwrap_or(output.trim());
+    
+    match value {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(ControlCentreError::ParseError(format!(
+            "Unexpected autoconnect value: {}",
+            value
+        ))),
+    }
+}
+
+// ============================================================================
+// Bluetooth Commands (bluetoothctl)
+// ============================================================================
+
+/// Get Bluetooth enabled status
+#[tauri::command]
+pub async fn get_bluetooth_status(state: State<'_, AppState>) -> Result<bool, String> {
+    if let Some(cached) = state.get_cached_bluetooth() {
+        return Ok(cached);
+    }
+    
+    let enabled = get_bluetooth_status_internal(state.radio_backends().bluetooth)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    state.set_cached_bluetooth(enabled);
+    Ok(enabled)
+}
+
+async fn get_bluetooth_status_internal(backend: RadioBackend) -> CCResult<bool> {
+    match backend {
+        RadioBackend::Rfkill => get_rfkill_enabled_internal("bluetooth").await,
+        _ => {
+            let output = run_command("bluetoothctl", &["show"]).await?;
+            Ok(output.contains("Powered: yes"))
+        }
+    }
+}
+
+/// Whether a device is connected, and the battery of the first one reporting it
+///
+/// Newly connected devices are noted in the connection history.
+async fn get_bluetooth_connection_internal(state: &AppState) -> (bool, Option<u8>) {
+    let Ok(output) = run_command("bluetoothctl", &["devices", "Connected"]).await else {
+        return (false, None);
+    };
+    
+    let addresses = parse_bluetooth_devices(&output);
+    note_bluetooth_connections(state, &addresses);
+    let mut battery = None;
+    for address in &addresses {
+        if let Ok(info) = run_command("bluetoothctl", &["info", address]).await {
+            battery = parse_bluetooth_battery(&info);
+            if battery.is_some() {
+                break;
+            }
+        }
+    }
+    
+    (!addresses.is_empty(), battery)
+}
+
+/// Extract device addresses from `bluetoothctl devices` lines (`Device <addr> <name>`)
+fn parse_bluetooth_devices(output: &str) -> Vec<String> {
+    parse_bluetooth_device_names(output)
+        .into_iter()
+        .map(|(address, _)| address)
+        .collect()
+}
+
+/// Extract `(address, name)` pairs from `bluetoothctl devices` lines
+///
+/// Devices without a name are reported under their address.
+fn parse_bluetooth_device_names(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Device "))
+        .filter_map(|rest| {
+            let (address, name) = rest.split_once(' ').unwrap_or((rest, ""));
+            let address = address.trim();
+            let name = if name.trim().is_empty() { address } else { name.trim() };
+            (!address.is_empty()).then(|| (address.to_string(), name.to_string()))
+        })
+        .collect()
+}
+
+/// Extract the percentage from `Battery Percentage: 0x4b (75)` in `bluetoothctl info`
+fn parse_bluetooth_battery(info: &str) -> Option<u8> {
+    let value = info
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Battery Percentage:"))?;
+    
+    let decimal = value
+        .split_once('(')
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .and_then(|(n, _)| n.trim().parse::<u8>().ok());
+    
+    decimal
+        .or_else(|| u8::from_str_radix(value.trim().trim_start_matches("0x"), 16).ok())
+        .filter(|p| *p <= 100)
+}
+
+/// Enable or disable Bluetooth
+///
+/// With `bt_auto_reconnect` set, enabling also reconnects paired devices in
+/// the background and emits `bluetooth-reconnected` with the names of the
+/// ones that came back.
+#[tauri::command]
+pub async fn set_bluetooth_enabled(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    enabled: bool,
+) -> Result<bool, String> {
+    let backend = state.radio_backends().bluetooth;
+    switch_bluetooth_radio(backend, enabled)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    tokio::time::sleep(RADIO_SETTLE_DELAY).await;
+    let actual = confirm_radio_state("Bluetooth", enabled, get_bluetooth_status_internal(backend).await);
+    
+    state.set_cached_bluetooth(actual);
+    info!("Bluetooth set to {}", if actual { "on" } else { "off" });
+    
+    if actual && enabled && config.get().bt_auto_reconnect && backend != RadioBackend::Rfkill {
+        tauri::async_runtime::spawn(async move {
+            let reconnected = reconnect_paired_devices().await;
+            if !reconnected.is_empty() {
+                app.state::<AppState>().invalidate_all();
+                let _ = app.emit("bluetooth-reconnected", reconnected);
+            }
+        });
+    }
+    
+    Ok(actual)
+}
+
+/// Switch the Bluetooth radio without reading it back
+async fn switch_bluetooth_radio(backend: RadioBackend, enabled: bool) -> CCResult<()> {
+    match backend {
+        RadioBackend::Rfkill => set_rfkill_enabled_internal("bluetooth", enabled).await,
+        _ => {
+            let arg = if enabled { "on" } else { "off" };
+            run_command_no_output("bluetoothctl", &["power", arg]).await
+        }
+    }
+}
+
+/// Make this machine visible to other Bluetooth devices, or hide it
+///
+/// bluetoothctl turns discoverability off again after its
+/// `discoverable-timeout`; `timeout` (seconds, 0 for never) replaces it
+/// before enabling. Returns the state reported afterwards.
+#[tauri::command]
+pub async fn set_bluetooth_discoverable(
+    enabled: bool,
+    timeout: Option<u32>,
+) -> Result<bool, String> {
+    if let (true, Some(timeout)) = (enabled, timeout) {
+        run_command_no_output("bluetoothctl", &["discoverable-timeout", &timeout.to_string()])
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    
+    set_bluetooth_flag_internal("discoverable", "Discoverable", enabled)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Allow or refuse pairing requests from other devices
+///
+/// Returns the state reported afterwards.
+#[tauri::command]
+pub async fn set_bluetooth_pairable(enabled: bool) -> Result<bool, String> {
+    set_bluetooth_flag_internal("pairable", "Pairable", enabled)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Switch a controller flag via `bluetoothctl <command> on|off` and read it back
+async fn set_bluetooth_flag_internal(command: &str, key: &str, enabled: bool) -> CCResult<bool> {
+    let arg = if enabled { "on" } else { "off" };
+    run_command_no_output("bluetoothctl", &[command, arg]).await?;
+    
+    let output = run_command("bluetoothctl", &["show"]).await?;
+    let actual = parse_bluetooth_flag(&output, key).ok_or_else(|| {
+        ControlCentreError::ParseError(format!("No {} in bluetoothctl show output", key))
+    })?;
+    if actual != enabled {
+        warn!("Bluetooth {} is still {} after setting it {}", command, actual, arg);
+    }
+    
+    info!("Bluetooth {} set to {}", command, arg);
+    Ok(actual)
+}
+
+/// Read a `Key: yes|no` line from `bluetoothctl show`
+fn parse_bluetooth_flag(output: &str, key: &str) -> Option<bool> {
+    output.lines().find_map(|line| {
+        let value = line.trim().strip_prefix(key)?.strip_prefix(':')?;
+        match value.trim() {
+            "yes" => Some(true),
+            "no" => Some(false),
+            _ => None,
+        }
+    })
+}
+
+/// Connect all paired devices that are not connected yet, concurrently
+///
+/// A device failing or timing out does not affect the others. Returns the
+/// names of the devices that connected.
+async fn reconnect_paired_devices() -> Vec<String> {
+    let paired = match run_command("bluetoothctl", &["devices", "Paired"]).await {
+        Ok(output) => parse_bluetooth_device_names(&output),
+        Err(e) => {
+            warn!("Cannot list paired Bluetooth devices: {}", e);
+            return Vec::new();
+        }
+    };
+    let connected = run_command("bluetoothctl", &["devices", "Connected"])
+        .await
+        .map(|output| parse_bluetooth_devices(&output))
+        .unwrap_or_default();
+    
+    connect_bluetooth_devices(paired.into_iter().filter(|(a, _)| !connected.contains(a))).await
+}
+
+/// Connect the given `(address, name)` devices concurrently
+///
+/// Returns the names of the devices that connected.
+async fn connect_bluetooth_devices(
+    devices: impl IntoIterator<Item = (String, String)>,
+) -> Vec<String> {
+    let mut attempts = tokio::task::JoinSet::new();
+    for (address, name) in devices {
+        attempts.spawn(async move {
+            let result = tokio::time::timeout(
+                BLUETOOTH_RECONNECT_TIMEOUT,
+                run_command_no_output("bluetoothctl", &["connect", &address]),
+            )
+            .await
+            .unwrap_or_else(|_| {
+                Err(ControlCentreError::Timeout(format!("Connecting to {} timed out", name)))
+            });
+            (name, result)
+        });
+    }
+    
+    let mut reconnected = Vec::new();
+    while let Some(attempt) = attempts.join_next().await {
+        match attempt {
+            Ok((name, Ok(()))) => {
+                info!("Reconnected Bluetooth device {}", name);
+                reconnected.push(name);
+            }
+            Ok((name, Err(e))) => debug!("Could not reconnect {}: {}", name, e),
+            Err(e) => warn!("Bluetooth reconnect task failed: {}", e),
+        }
+    }
+    reconnected
+}
+
+/// A paired device with when the panel last saw it connect
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentBluetoothDevice {
+    pub address: String,
+    pub name: String,
+    pub connected: bool,
+    /// Unix time (seconds) of the last connection seen; `None` if never seen
+    pub last_connected: Option<u64>,
+}
+
+/// List the paired devices, most recently connected first
+///
+/// Connection times are those the panel observed, kept across restarts in
+/// the state directory; devices never seen connected come last.
+#[tauri::command]
+pub async fn get_recent_bluetooth_devices(
+    state: State<'_, AppState>,
+) -> Result<Vec<RecentBluetoothDevice>, String> {
+    let (paired, connected) = tokio::try_join!(
+        run_command("bluetoothctl", &["devices", "Paired"]),
+        run_command("bluetoothctl", &["devices", "Connected"]),
+    )
+    .map_err(|e| e.to_string())?;
+    
+    let connected = parse_bluetooth_devices(&connected);
+    note_bluetooth_connections(&state, &connected);
+    Ok(recent_bluetooth_devices(
+        parse_bluetooth_device_names(&paired),
+        &connected,
+        &state.bluetooth_history(),
+    ))
+}
+
+/// Connect a paired device by address
+#[tauri::command]
+pub async fn connect_bluetooth_device(
+    state: State<'_, AppState>,
+    address: String,
+) -> Result<(), String> {
+    let paired = run_command("bluetoothctl", &["devices", "Paired"])
+        .await
+        .map_err(|e| e.to_string())?;
+    if !parse_bluetooth_devices(&paired).contains(&address) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "No paired Bluetooth device {}",
+            address
+        ))
+        .to_string());
+    }
+    
+    tokio::time::timeout(
+        BLUETOOTH_RECONNECT_TIMEOUT,
+        run_command_no_output("bluetoothctl", &["connect", &address]),
+    )
+    .await
+    .map_err(|_| ControlCentreError::Timeout(format!("Connecting to {} timed out", address)))
+    .and_then(|result| result)
+    .map_err(|e| e.to_string())?;
+    
+    info!("Connected Bluetooth device {}", address);
+    if let Ok(connected) = run_command("bluetoothctl", &["devices", "Connected"]).await {
+        note_bluetooth_connections(&state, &parse_bluetooth_devices(&connected));
+    }
+    // A headset brings its own sink along
+    state.invalidate_all();
+    Ok(())
+}
+
+/// Order paired devices by their last connection, newest first
+///
+/// Connected devices come before all others; devices without any history
+/// come last, by name.
+fn recent_bluetooth_devices(
+    paired: Vec<(String, String)>,
+    connected: &[String],
+    history: &BTreeMap<String, u64>,
+) -> Vec<RecentBluetoothDevice> {
+    let mut devices: Vec<RecentBluetoothDevice> = paired
+        .into_iter()
+        .map(|(address, name)| RecentBluetoothDevice {
+            connected: connected.contains(&address),
+            last_connected: history.get(&address).copied(),
+            address,
+            name,
+        })
+        .collect();
+    
+    devices.sort_by(|a, b| {
+        let recency = |d: &RecentBluetoothDevice| (d.connected, d.last_connected);
+        recency(b).cmp(&recency(a)).then_with(|| a.name.cmp(&b.name))
+    });
+    devices
+}
+
+/// Note the connected devices, saving the history if any connected anew
+fn note_bluetooth_connections(state: &AppState, connected: &[String]) {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    if state.note_bluetooth_connected(connected, now) {
+        save_bluetooth_history(&state.bluetooth_history());
+    }
+}
+
+/// Per-user state directory (`$XDG_STATE_HOME` or `~/.local/state`)
+fn state_dir() -> PathBuf {
+    std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| {
+            let home = std::env::var_os("HOME").unwrap_or_default();
+            PathBuf::from(home).join(".local/state")
+        })
+        .join("control-centre")
+}
+
+fn bluetooth_history_path() -> PathBuf {
+    state_dir().join("bluetooth-history.json")
+}
+
+/// Last connection time of each Bluetooth device by address, from disk
+pub(crate) fn load_bluetooth_history() -> BTreeMap<String, u64> {
+    std::fs::read_to_string(bluetooth_history_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_bluetooth_history(history: &BTreeMap<String, u64>) {
+    let path = bluetooth_history_path();
+    let result = serde_json::to_string_pretty(history)
+        .map_err(|e| ControlCentreError::ParseError(e.to_string()))
+        .and_then(|contents| {
+            std::fs::create_dir_all(state_dir())?;
+            Ok(std::fs::write(&path, contents)?)
+        });
+    if let Err(e) = result {
+        warn!("Failed to save Bluetooth history to {}: {}", path.display(), e);
+    }
+}
+
+// ============================================================================
+// Airplane Mode Commands
+// ============================================================================
+
+/// Radio states from before airplane mode, with what was connected on them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AirplaneRestore {
+    pub(crate) wifi: bool,
+    /// Active WiFi connection name
+    pub(crate) wifi_connection: Option<String>,
+    pub(crate) bluetooth: bool,
+    /// Connected Bluetooth devices as `(address, name)`
+    pub(crate) bluetooth_devices: Vec<(String, String)>,
+}
+
+/// Outcome of `set_airplane_mode`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AirplaneModeResult {
+    /// Airplane mode is on afterwards
+    pub enabled: bool,
+    pub wifi_enabled: bool,
+    /// The WiFi connection from before airplane mode is active again
+    pub wifi_reconnected: bool,
+    pub bluetooth_enabled: bool,
+    /// Names of the Bluetooth devices that reconnected
+    pub bluetooth_reconnected: Vec<String>,
+}
+
+/// Whether airplane mode is on
+#[tauri::command]
+pub async fn get_airplane_mode(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.is_airplane_mode())
+}
+
+/// Turn WiFi and Bluetooth off, or back to how they were before
+///
+/// Turning airplane mode off re-enables only the radios that were on,
+/// brings the WiFi connection back up and reconnects the Bluetooth devices
+/// that were connected (every paired one with `bt_auto_reconnect`).
+/// Returns what was restored.
+#[tauri::command]
+pub async fn set_airplane_mode(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    enabled: bool,
+) -> Result<AirplaneModeResult, String> {
+    if enabled {
+        airplane_mode_on_internal(&state).await
+    } else {
+        airplane_mode_off_internal(&state, config.get().bt_auto_reconnect).await
+    }
+    .map_err(|e| e.to_string())
+}
+
+/// Save the radio states and switch off the radios that are on
+///
+/// A radio whose state cannot be read is left alone. The states are saved
+/// before switching, so a failed switch can still be undone.
+async fn airplane_mode_on_internal(state: &AppState) -> CCResult<AirplaneModeResult> {
+    if state.is_airplane_mode() {
+        return Err(ControlCentreError::InvalidArgument(
+            "Airplane mode is already on".to_string(),
+        ));
+    }
+    
+    let backends = state.radio_backends();
+    let (wifi, bluetooth, wifi_connection, bluetooth_devices) = tokio::join!(
+        get_wifi_enabled_internal(backends.wifi),
+        get_bluetooth_status_internal(backends.bluetooth),
+        active_wifi_connection(backends.wifi),
+        connected_bluetooth_devices(backends.bluetooth),
+    );
+    let readable = |radio: &str, enabled: CCResult<bool>| {
+        enabled
+            .map_err(|e| warn!("Cannot read {} state, leaving it alone: {}", radio, e))
+            .unwrap_or(false)
+    };
+    let restore = AirplaneRestore {
+        wifi: readable("WiFi", wifi),
+        wifi_connection,
+        bluetooth: readable("Bluetooth", bluetooth),
+        bluetooth_devices,
+    };
+    let (wifi, bluetooth) = (restore.wifi, restore.bluetooth);
+    state.store_airplane_mode(restore);
+    
+    if wifi {
+        switch_wifi_radio(backends.wifi, false).await?;
+    }
+    if bluetooth {
+        switch_bluetooth_radio(backends.bluetooth, false).await?;
+    }
+    tokio::time::sleep(RADIO_SETTLE_DELAY).await;
+    
+    let (wifi, bluetooth) = tokio::join!(
+        get_wifi_enabled_internal(backends.wifi),
+        get_bluetooth_status_internal(backends.bluetooth),
+    );
+    let wifi_enabled = confirm_radio_state("WiFi", false, wifi);
+    let bluetooth_enabled = confirm_radio_state("Bluetooth", false, bluetooth);
+    state.set_cached_wifi(wifi_enabled);
+    state.set_cached_bluetooth(bluetooth_enabled);
+    info!("Airplane mode on");
+    
+    Ok(AirplaneModeResult {
+        enabled: true,
+        wifi_enabled,
+        bluetooth_enabled,
+        ..Default::default()
+    })
+}
+
+/// Restore both radios and what was connected on them, concurrently
+async fn airplane_mode_off_internal(
+    state: &AppState,
+    auto_reconnect: bool,
+) -> CCResult<AirplaneModeResult> {
+    let restore = state.take_airplane_mode().ok_or_else(|| {
+        ControlCentreError::InvalidArgument("Airplane mode is not active".to_string())
+    })?;
+    
+    let backends = state.radio_backends();
+    let ((wifi_enabled, wifi_reconnected), (bluetooth_enabled, bluetooth_reconnected)) =
+        tokio::join!(
+            restore_wifi(backends.wifi, &restore),
+            restore_bluetooth(backends.bluetooth, &restore, auto_reconnect),
+        );
+    
+    // Reconnected headsets bring their own sinks along
+    state.invalidate_all();
+    state.set_cached_wifi(wifi_enabled);
+    state.set_cached_bluetooth(bluetooth_enabled);
+    info!(
+        "Airplane mode off (WiFi reconnected: {}, Bluetooth devices reconnected: {})",
+        wifi_reconnected,
+        bluetooth_reconnected.len()
+    );
+    
+    Ok(AirplaneModeResult {
+        enabled: false,
+        wifi_enabled,
+        wifi_reconnected,
+        bluetooth_enabled,
+        bluetooth_reconnected,
+    })
+}
+
+/// Re-enable WiFi if it was on and bring its connection back up
+///
+/// Returns the radio state and whether the connection came back.
+async fn restore_wifi(backend: RadioBackend, restore: &AirplaneRestore) -> (bool, bool) {
+    if !restore.wifi {
+        return (false, false);
+    }
+    if let Err(e) = switch_wifi_radio(backend, true).await {
+        warn!("Failed to re-enable WiFi: {}", e);
+        return (false, false);
+    }
+    tokio::time::sleep(RADIO_SETTLE_DELAY).await;
+    let enabled = confirm_radio_state("WiFi", true, get_wifi_enabled_internal(backend).await);
+    
+    let Some(connection) = restore.wifi_connection.as_deref().filter(|_| enabled) else {
+        return (enabled, false);
+    };
+    let result = tokio::time::timeout(
+        WIFI_RECONNECT_TIMEOUT,
+        run_command_no_output("nmcli", &["connection", "up", connection]),
+    )
+    .await;
+    match result {
+        Ok(Ok(())) => {
+            info!("Reconnected WiFi {}", connection);
+            (enabled, true)
+        }
+        Ok(Err(e)) => {
+            warn!("Failed to reconnect WiFi {}: {}", connection, e);
+            (enabled, false)
+        }
+        Err(_) => {
+            warn!("Reconnecting WiFi {} timed out", connection);
+            (enabled, false)
+        }
+    }
+}
+
+/// Re-enable Bluetooth if it was on and reconnect its devices
+///
+/// Returns the radio state and the names of the devices that reconnected.
+async fn restore_bluetooth(
+    backend: RadioBackend,
+    restore: &AirplaneRestore,
+    auto_reconnect: bool,
+) -> (bool, Vec<String>) {
+    if !restore.bluetooth {
+        return (false, Vec::new());
+    }
+    if let Err(e) = switch_bluetooth_radio(backend, true).await {
+        warn!("Failed to re-enable Bluetooth: {}", e);
+        return (false, Vec::new());
+    }
+    tokio::time::sleep(RADIO_SETTLE_DELAY).await;
+    let enabled =
+        confirm_radio_state("Bluetooth", true, get_bluetooth_status_internal(backend).await);
+    
+    // rfkill only knows about the switch, not the devices
+    if !enabled || backend == RadioBackend::Rfkill {
+        return (enabled, Vec::new());
+    }
+    let reconnected = if auto_reconnect {
+        reconnect_paired_devices().await
+    } else {
+        connect_bluetooth_devices(restore.bluetooth_devices.clone()).await
+    };
+    (enabled, reconnected)
+}
+
+/// Name of the active WiFi connection, if NetworkManager manages WiFi
+async fn active_wifi_connection(backend: RadioBackend) -> Option<String> {
+    if backend == RadioBackend::Rfkill {
+        return None;
+    }
+    let active = run_command("nmcli", &["-t", "-f", "TYPE,NAME", "connection", "show", "--active"])
+        .await
+        .map_err(|e| warn!("Cannot list active connections: {}", e))
+        .ok()?;
+    parse_active_wifi(&active)
+}
+
+/// First WiFi connection in `nmcli -t -f TYPE,NAME connection show --active`
+fn parse_active_wifi(active: &str) -> Option<String> {
+    active.lines().find_map(|line| {
+        let (kind, name) = split_nmcli_terse(line);
+        let name = split_nmcli_terse(&name).0;
+        (matches!(kind.as_str(), "wifi" | "802-11-wireless") && !name.is_empty()).then_some(name)
+    })
+}
+
+/// Connected Bluetooth devices as `(address, name)`, if BlueZ is in use
+async fn connected_bluetooth_devices(backend: RadioBackend) -> Vec<(String, String)> {
+    if backend == RadioBackend::Rfkill {
+        return Vec::new();
+    }
+    run_command("bluetoothctl", &["devices", "Connected"])
+        .await
+        .map(|output| parse_bluetooth_device_names(&output))
+        .unwrap_or_default()
+}
+
+// ============================================================================
+// Night Light Commands
+// ============================================================================
+
+/// Colour temperature applied when Night Light is on (Kelvin)
+const NIGHT_LIGHT_TEMPERATURE: u32 = 4500;
+
+/// Neutral colour temperature used when Night Light is off (Kelvin)
+const NEUTRAL_TEMPERATURE: u32 = 6500;
+
+/// Temperatures accepted by `set_night_light_for_output` (Kelvin)
+const NIGHT_LIGHT_TEMPERATURE_RANGE: std::ops::RangeInclusive<u32> = 1000..=10000;
+
+/// How long `gammastep -p` may take, e.g. waiting on a location provider
+const GAMMASTEP_PRINT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// gammastep options that set the period and temperature in print mode: the
+/// location, the day and night temperatures and the config file holding them
+const GAMMASTEP_PRINT_OPTIONS: [&str; 3] = ["-l", "-t", "-c"];
+
+/// D-Bus coordinates of the wl-gammarelay-rs helper
+const GAMMARELAY_SERVICE: &str = "rs.wl-gammarelay";
+const GAMMARELAY_INTERFACE: &str = "rs.wl.gammarelay";
+
+/// Backends capable of driving Night Light
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NightLightBackend {
+    /// GNOME settings daemon via gsettings
+    Gnome,
+    /// gammastep in one-shot mode
+    Gammastep,
+    /// wlsunset pinned to a fixed temperature
+    Wlsunset,
+    /// wlr-gamma-control protocol via the wl-gammarelay-rs helper
+    WlrGamma,
+}
+
+impl NightLightBackend {
+    /// Daemon process backing this backend, if it runs one
+    fn process_name(self) -> Option<&'static str> {
+        match self {
+            NightLightBackend::Gammastep => Some("gammastep"),
+            NightLightBackend::Wlsunset => Some("wlsunset"),
+            NightLightBackend::Gnome | NightLightBackend::WlrGamma => None,
+        }
+    }
+}
+
+/// Night light daemons that fight over the gamma ramps when run together
+const NIGHT_LIGHT_DAEMONS: [&str; 3] = ["gammastep", "wlsunset", "redshift"];
+
+/// Whether the running compositor implements wlr-gamma-control
+///
+/// Niri, Sway, Hyprland and most wlroots compositors expose the protocol.
+fn compositor_supports_gamma_control() -> bool {
+    const SOCKET_VARS: [&str; 3] = ["NIRI_SOCKET", "SWAYSOCK", "HYPRLAND_INSTANCE_SIGNATURE"];
+    const DESKTOPS: [&str; 6] = ["niri", "sway", "hyprland", "river", "wayfire", "labwc"];
+    
+    if SOCKET_VARS.iter().any(|var| std::env::var_os(var).is_some()) {
+        return true;
+    }
+    
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .map(|desktop| {
+            let desktop = desktop.to_lowercase();
+            DESKTOPS.iter().any(|name| desktop.contains(name))
+        })
+        .unwrap_or(false)
+}
+
+/// Detect the best available Night Light backend
+async fn detect_night_light_backend() -> Option<NightLightBackend> {
+    let gnome = run_command(
+        "gsettings",
+        &["get", "org.gnome.settings-daemon.plugins.color", "night-light-enabled"],
+    )
+    .await;
+    
+    if gnome.is_ok() {
+        Some(NightLightBackend::Gnome)
+    } else if command_exists("gammastep") {
+        Some(NightLightBackend::Gammastep)
+    } else if command_exists("wlsunset") {
+        Some(NightLightBackend::Wlsunset)
+    } else if compositor_supports_gamma_control() && command_exists("wl-gammarelay-rs") {
+        Some(NightLightBackend::WlrGamma)
+    } else {
+        None
+    }
+}
+
+/// Resolve the authoritative Night Light backend
+///
+/// The first successful detection is recorded in `AppState` and reused, so
+/// getters and setters never consult different backends.
+async fn resolve_night_light_backend(state: &AppState) -> Option<NightLightBackend> {
+    if let Some(backend) = state.get_night_light_backend() {
+        return Some(backend);
+    }
+    
+    let backend = detect_night_light_backend().await?;
+    info!("Using {:?} as Night Light backend", backend);
+    state.set_night_light_backend(backend);
+    Some(backend)
+}
+
+/// Stop night light processes left over from backends other than `active`
+///
+/// A gammastep from a previous session would otherwise keep tinting the
+/// screen after the authoritative backend has turned Night Light off.
+async fn stop_stray_night_light_processes(active: NightLightBackend) {
+    for process in NIGHT_LIGHT_DAEMONS {
+        if active.process_name() != Some(process)
+            && run_command_no_output("pkill", &["-x", process]).await.is_ok()
+        {
+            info!("Stopped stray {} process", process);
+        }
+    }
+}
+
+/// Night light daemons currently running, found with a single `pgrep`
+async fn running_night_light_daemons() -> Vec<&'static str> {
+    let pattern = NIGHT_LIGHT_DAEMONS.join("|");
+    // pgrep exits non-zero when nothing matches
+    match run_command("pgrep", &["-x", "-l", &pattern]).await {
+        Ok(output) => parse_pgrep_daemons(&output),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Known night light daemons named in `pgrep -l` output (`<pid> <name>`)
+fn parse_pgrep_daemons(output: &str) -> Vec<&'static str> {
+    NIGHT_LIGHT_DAEMONS
+        .into_iter()
+        .filter(|daemon| {
+            output
+                .lines()
+                .any(|line| line.split_whitespace().nth(1) == Some(*daemon))
+        })
+        .collect()
+}
+
+/// Whether more than one night light daemon is running
+///
+/// They would take turns setting the gamma ramps and the screen
+/// temperature flickers. Cached with the Night Light status, and logged
+/// only when the set of running daemons changes.
+async fn night_light_conflict(state: &AppState) -> bool {
+    if let Some(cached) = state.get_cached_night_light_conflict() {
+        return cached;
+    }
+    
+    let running = running_night_light_daemons().await;
+    let conflict = running.len() > 1;
+    let previous = state.replace_night_light_daemons(running.clone());
+    if previous != running {
+        if conflict {
+            warn!("Conflicting night light daemons running: {}", running.join(", "));
+        } else if previous.len() > 1 {
+            info!("Night light daemons no longer conflict");
+        }
+    }
+    
+    state.set_cached_night_light_conflict(conflict);
+    conflict
+}
+
+/// Pick the daemon to keep out of several running ones
+///
+/// The active backend's own daemon wins; otherwise the first one found.
+fn night_light_daemon_to_keep(
+    running: &[&'static str],
+    active: Option<NightLightBackend>,
+) -> Option<&'static str> {
+    active
+        .and_then(NightLightBackend::process_name)
+        .filter(|process| running.contains(process))
+        .or_else(|| running.first().copied())
+}
+
+/// Stop all but one of several running night light daemons
+///
+/// Returns the names of the stopped processes.
+#[tauri::command]
+pub async fn resolve_night_light_conflict(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let running = running_night_light_daemons().await;
+    let backend = resolve_night_light_backend(&state).await;
+    let keep = night_light_daemon_to_keep(&running, backend);
+    
+    let mut stopped = Vec::new();
+    for process in running.into_iter().filter(|p| Some(*p) != keep) {
+        run_command_no_output("pkill", &["-x", process])
+            .await
+            .map_err(|e| e.to_string())?;
+        stopped.push(process.to_string());
+    }
+    
+    if !stopped.is_empty() {
+        info!("Stopped {} to resolve night light conflict, kept {:?}", stopped.join(", "), keep);
+    }
+    state.invalidate_all();
+    Ok(stopped)
+}
+
+/// Get the Night Light backend in use, if any
+#[tauri::command]
+pub async fn get_night_light_backend(
+    state: State<'_, AppState>,
+) -> Result<Option<NightLightBackend>, String> {
+    Ok(resolve_night_light_backend(&state).await)
+}
+
+/// Get Night Light enabled status
+#[tauri::command]
+pub async fn get_night_light_status(state: State<'_, AppState>) -> Result<bool, String> {
+    if let Some(cached) = state.get_cached_night_light() {
+        return Ok(cached);
+    }
+    
+    let enabled = get_night_light_internal(&state).await.map_err(|e| e.to_string())?;
+    
+    state.set_cached_night_light(enabled);
+    Ok(enabled)
+}
+
+/// Where a reported screen temperature comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureSource {
+    /// Read from the backend, so also reflects changes made outside the app
+    Backend,
+    /// The backend cannot tell; this is what the app last applied
+    LastSet,
+    /// Neither the backend nor the app know the temperature
+    Unknown,
+}
+
+/// Result of `get_night_light_temperature`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NightLightTemperature {
+    /// Kelvin; `None` when `source` is `Unknown`
+    pub temperature: Option<u32>,
+    pub source: TemperatureSource,
+}
+
+/// Get the screen temperature currently applied by Night Light
+///
+/// Neutral (6500K) while Night Light is off. Daemons started outside the
+/// app are read too: gammastep from its `-O` argument or print mode,
+/// GNOME and wl-gammarelay-rs from their settings.
+#[tauri::command]
+pub async fn get_night_light_temperature(
+    state: State<'_, AppState>,
+) -> Result<NightLightTemperature, String> {
+    let backend = resolve_night_light_backend(&state).await;
+    let read = match backend {
+        Some(backend) => read_night_light_temperature(backend).await,
+        None => Ok(None),
+    };
+    
+    let read = read.unwrap_or_else(|e| {
+        debug!("Could not read Night Light temperature from {:?}: {}", backend, e);
+        None
+    });
+    Ok(match (read, state.night_light_temperature()) {
+        (Some(temperature), _) => NightLightTemperature {
+            temperature: Some(temperature),
+            source: TemperatureSource::Backend,
+        },
+        (None, Some(temperature)) => NightLightTemperature {
+            temperature: Some(temperature),
+            source: TemperatureSource::LastSet,
+        },
+        (None, None) => NightLightTemperature {
+            temperature: None,
+            source: TemperatureSource::Unknown,
+        },
+    })
+}
+
+/// Read the applied temperature from the backend; `None` if it cannot tell
+async fn read_night_light_temperature(backend: NightLightBackend) -> CCResult<Option<u32>> {
+    match backend {
+        NightLightBackend::Gnome => {
+            let schema = "org.gnome.settings-daemon.plugins.color";
+            let enabled = run_command("gsettings", &["get", schema, "night-light-enabled"]).await?;
+            if enabled.trim() != "true" {
+                return Ok(Some(NEUTRAL_TEMPERATURE));
+            }
+            let output =
+                run_command("gsettings", &["get", schema, "night-light-temperature"]).await?;
+            Ok(parse_gvariant_uint(&output))
+        }
+        NightLightBackend::Gammastep => {
+            // Gamma resets when gammastep exits, so no process means neutral
+            let Some(cmdline) = process_cmdline("gammastep").await? else {
+                return Ok(Some(NEUTRAL_TEMPERATURE));
+            };
+            let args = split_cmdline(&cmdline);
+            if let Some(temperature) = cmdline_value(&args, "-O") {
+                return Ok(temperature.parse().ok());
+            }
+            
+            // Rerun with the same location and temperatures in print mode
+            let mut print_args = gammastep_print_args(&args);
+            print_args.push("-p");
+            let output = gammastep_print(&print_args).await?;
+            Ok(parse_gammastep_print(&output))
+        }
+        NightLightBackend::Wlsunset => {
+            let Some(cmdline) = process_cmdline("wlsunset").await? else {
+                return Ok(Some(NEUTRAL_TEMPERATURE));
+            };
+            Ok(parse_wlsunset_pinned(&split_cmdline(&cmdline)))
+        }
+        NightLightBackend::WlrGamma => {
+            let output = run_command(
+                "busctl",
+                &["--user", "get-property", GAMMARELAY_SERVICE, "/", GAMMARELAY_INTERFACE, "Temperature"],
+            )
+            .await?;
+            Ok(parse_busctl_uint(&output))
+        }
+    }
+}
+
+/// Temperature applied by `set_night_light_internal`, `None` when a schedule
+/// lets it vary over the day
+fn applied_temperature(enabled: bool, schedule: Option<&NightLightSchedule>) -> Option<u32> {
+    match (enabled, schedule) {
+        (false, _) => Some(NEUTRAL_TEMPERATURE),
+        (true, None) => Some(NIGHT_LIGHT_TEMPERATURE),
+        (true, Some(_)) => None,
+    }
+}
+
+/// Parse a `gsettings get` unsigned reply such as `uint32 4000`
+fn parse_gvariant_uint(output: &str) -> Option<u32> {
+    output.split_whitespace().last()?.parse().ok()
+}
+
+/// The `-l`, `-t` and `-c` options of a running gammastep, values included
+///
+/// Anything else (`-m`, `-P`, hooks) is left out so the print run cannot
+/// touch the screen or start other programs.
+fn gammastep_print_args(args: &[String]) -> Vec<&str> {
+    let mut kept = Vec::new();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if GAMMASTEP_PRINT_OPTIONS.contains(&arg.as_str()) {
+            kept.push(arg.as_str());
+            kept.extend(iter.next().map(String::as_str));
+        } else if GAMMASTEP_PRINT_OPTIONS.iter().any(|option| arg.starts_with(option)) {
+            kept.push(arg.as_str());
+        }
+    }
+    kept
+}
+
+/// Run `gammastep` in print mode, killing it if it does not finish in time
+async fn gammastep_print(args: &[&str]) -> CCResult<String> {
+    debug!("Running command: gammastep {:?}", args);
+    
+    let started = std::time::Instant::now();
+    let run = tool_command("gammastep")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output();
+    let result = tokio::time::timeout(GAMMASTEP_PRINT_TIMEOUT, run)
+        .await
+        .map_err(|_| ControlCentreError::Timeout("gammastep -p did not finish".to_string()))?;
+    record_command("gammastep", args, started, &result);
+    
+    let output = result?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(ControlCentreError::CommandFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+/// Temperature from `gammastep -p` (`Color temperature: 4500K`)
+fn parse_gammastep_print(output: &str) -> Option<u32> {
+    output.lines().find_map(|line| {
+        let (_, value) = line.split_once("Color temperature:")?;
+        value.trim().trim_end_matches('K').parse().ok()
+    })
+}
+
+/// Temperature of a wlsunset pinned the way `set_night_light_internal` does it
+///
+/// A pinned instance has a day temperature (`-T`) one above the night one
+/// (`-t`); any other instance follows the sun and has no fixed value.
+fn parse_wlsunset_pinned(args: &[String]) -> Option<u32> {
+    let low: u32 = cmdline_value(args, "-t")?.parse().ok()?;
+    let high: u32 = cmdline_value(args, "-T")?.parse().ok()?;
+    (high == low + 1).then_some(low)
+}
+
+/// Enable or disable Night Light
+///
+/// Returns the resulting display state, including the temperature now
+/// applied, so the UI can update without reading it back separately.
+#[tauri::command]
+pub async fn set_night_light_enabled(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    enabled: bool,
+) -> Result<DisplayState, String> {
+    let backend = resolve_night_light_backend(&state).await.ok_or_else(|| {
+        ControlCentreError::CommandNotFound(
+            "No Night Light backend (gsettings, gammastep, wlsunset, wl-gammarelay-rs) available"
+                .to_string(),
+        )
+        .to_string()
+    })?;
+    
+    let schedule = config.get().night_light_schedule;
+    set_night_light_internal(backend, enabled, schedule.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    if !enabled {
+        stop_stray_night_light_processes(backend).await;
+    }
+    if backend == NightLightBackend::Wlsunset {
+        // Restarting wlsunset ended the per-output instances as well
+        state.clear_output_night_lights();
+    }
+    
+    state.set_night_light_temperature(applied_temperature(enabled, schedule.as_ref()));
+    // Daemons may have been started or stopped, so check for a conflict afresh
+    state.invalidate(CacheDomain::Display);
+    state.set_cached_night_light(enabled);
+    info!("Night Light set to {} via {:?}", enabled, backend);
+    
+    let conflict = night_light_conflict(&state).await;
+    Ok(display_state(&state, enabled, schedule.as_ref(), conflict))
+}
+
+/// Build the `DisplayState` for a known Night Light status
+fn display_state(
+    state: &AppState,
+    enabled: bool,
+    schedule: Option<&NightLightSchedule>,
+    conflict: bool,
+) -> DisplayState {
+    DisplayState {
+        night_light_enabled: enabled,
+        night_light_from: schedule.map(|s| s.from.clone()),
+        night_light_to: schedule.map(|s| s.to.clone()),
+        conflict,
+        night_light_outputs: state.night_light_outputs(),
+        night_light_temperature: effective_temperature(enabled, state.night_light_temperature()),
+    }
+}
+
+/// Temperature to report for Night Light: the last one applied, or the
+/// usual Night Light temperature when that is unknown or neutral
+fn effective_temperature(enabled: bool, last_set: Option<u32>) -> Option<u32> {
+    enabled.then(|| {
+        last_set
+            .filter(|temperature| *temperature != NEUTRAL_TEMPERATURE)
+            .unwrap_or(NIGHT_LIGHT_TEMPERATURE)
+    })
+}
+
+/// Set explicit Night Light on/off times (`HH:MM`)
+///
+/// GNOME applies the schedule itself; gammastep and wlsunset are restarted
+/// with it if Night Light is on, and pick it up the next time it is enabled
+/// otherwise.
+#[tauri::command]
+pub async fn set_night_light_times(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    from: String,
+    to: String,
+) -> Result<NightLightSchedule, String> {
+    let from_hours = parse_hhmm(&from).map_err(|e| e.to_string())?;
+    let to_hours = parse_hhmm(&to).map_err(|e| e.to_string())?;
+    let schedule = NightLightSchedule { from, to };
+    
+    let backend = resolve_night_light_backend(&state).await.ok_or_else(|| {
+        ControlCentreError::CommandNotFound("No Night Light backend available".to_string())
+            .to_string()
+    })?;
+    
+    match backend {
+        NightLightBackend::Gnome => {
+            let schema = "org.gnome.settings-daemon.plugins.color";
+            let settings = [
+                ("night-light-schedule-automatic", "false".to_string()),
+                ("night-light-schedule-from", from_hours.to_string()),
+                ("night-light-schedule-to", to_hours.to_string()),
+            ];
+            for (key, value) in settings {
+                run_command_no_output("gsettings", &["set", schema, key, &value])
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        NightLightBackend::Gammastep | NightLightBackend::Wlsunset => {
+            if get_night_light_internal(&state).await.unwrap_or(false) {
+                set_night_light_internal(backend, true, Some(&schedule))
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+        NightLightBackend::WlrGamma => {
+            return Err(ControlCentreError::SystemError(
+                "wl-gammarelay-rs has no scheduler; install gammastep or wlsunset".to_string(),
+            )
+            .to_string());
+        }
+    }
+    
+    config
+        .update(|c| c.night_light_schedule = Some(schedule.clone()))
+        .map_err(|e| e.to_string())?;
+    
+    info!("Night Light scheduled {} to {} via {:?}", schedule.from, schedule.to, backend);
+    Ok(schedule)
+}
+
+/// Parse `HH:MM` into fractional hours, as GNOME's schedule keys expect
+fn parse_hhmm(time: &str) -> CCResult<f64> {
+    let invalid = || ControlCentreError::InvalidArgument(format!("Invalid time (expected HH:MM): {}", time));
+    
+    let (hours, minutes) = time.split_once(':').ok_or_else(invalid)?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return Err(invalid());
+    }
+    let hours = hours.parse::<u8>().map_err(|_| invalid())?;
+    let minutes = minutes.parse::<u8>().map_err(|_| invalid())?;
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+    
+    Ok(hours as f64 + minutes as f64 / 60.0)
+}
+
+async fn set_night_light_internal(
+    backend: NightLightBackend,
+    enabled: bool,
+    schedule: Option<&NightLightSchedule>,
+) -> CCResult<()> {
+    match backend {
+        NightLightBackend::Gnome => {
+            let value = if enabled { "true" } else { "false" };
+            run_command_no_output(
+                "gsettings",
+                &["set", "org.gnome.settings-daemon.plugins.color", "night-light-enabled", value],
+            )
+            .await
+        }
+        NightLightBackend::Gammastep => {
+            // Gamma resets when the client exits on Wayland, so it must keep running
+            let _ = run_command_no_output("pkill", &["-x", "gammastep"]).await;
+            if enabled {
+                match schedule {
+                    Some(schedule) => {
+                        let path = write_gammastep_schedule(schedule)?;
+                        spawn_detached("gammastep", &["-c", &path.to_string_lossy()])?;
+                    }
+                    None => {
+                        spawn_detached("gammastep", &["-O", &NIGHT_LIGHT_TEMPERATURE.to_string()])?;
+                    }
+                }
+            }
+            Ok(())
+        }
+        NightLightBackend::Wlsunset => {
+            let _ = run_command_no_output("pkill", &["-x", "wlsunset"]).await;
+            if enabled {
+                let args = wlsunset_global_args(schedule);
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                spawn_detached("wlsunset", &args)?;
+            }
+            Ok(())
+        }
+        NightLightBackend::WlrGamma => {
+            ensure_gammarelay_running().await?;
+            let temperature = if enabled { NIGHT_LIGHT_TEMPERATURE } else { NEUTRAL_TEMPERATURE };
+            run_command_no_output(
+                "busctl",
+                &[
+                    "--user",
+                    "set-property",
+                    GAMMARELAY_SERVICE,
+                    "/",
+                    GAMMARELAY_INTERFACE,
+                    "Temperature",
+                    "q",
+                    &temperature.to_string(),
+                ],
+            )
+            .await
+        }
+    }
+}
+
+/// Arguments of the global wlsunset instance, before any `-o` restriction
+fn wlsunset_global_args(schedule: Option<&NightLightSchedule>) -> Vec<String> {
+    let low = NIGHT_LIGHT_TEMPERATURE.to_string();
+    match schedule {
+        Some(schedule) => vec![
+            "-T".to_string(),
+            NEUTRAL_TEMPERATURE.to_string(),
+            "-t".to_string(),
+            low,
+            "-S".to_string(),
+            schedule.to.clone(),
+            "-s".to_string(),
+            schedule.from.clone(),
+        ],
+        // Day and night temperatures must differ; pin both to the night value
+        None => vec![
+            "-T".to_string(),
+            (NIGHT_LIGHT_TEMPERATURE + 1).to_string(),
+            "-t".to_string(),
+            low,
+        ],
+    }
+}
+
+/// Whether `pgrep -a -x wlsunset` lists an instance other than the
+/// per-output ones recorded in `per_output`
+///
+/// The global instance runs unrestricted, or with `-o` for the outputs
+/// that have no setting of their own.
+fn has_global_wlsunset(pgrep_output: &str, per_output: &BTreeMap<String, u32>) -> bool {
+    pgrep_output.lines().filter(|line| !line.trim().is_empty()).any(|line| {
+        let args: Vec<&str> = line.split_whitespace().skip(2).collect();
+        let targets: Vec<&str> = args
+            .windows(2)
+            .filter(|pair| pair[0] == "-o")
+            .map(|pair| pair[1])
+            .collect();
+        targets.is_empty() || targets.iter().any(|output| !per_output.contains_key(*output))
+    })
+}
+
+async fn global_wlsunset_running(per_output: &BTreeMap<String, u32>) -> bool {
+    run_command("pgrep", &["-a", "-x", "wlsunset"])
+        .await
+        .is_ok_and(|output| has_global_wlsunset(&output, per_output))
+}
+
+async fn ensure_gammarelay_running() -> CCResult<()> {
+    if run_command("busctl", &["--user", "status", GAMMARELAY_SERVICE]).await.is_err() {
+        spawn_detached("wl-gammarelay-rs", &["run"])?;
+        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    }
+    Ok(())
+}
+
+/// Result of `set_night_light_for_output`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputNightLight {
+    pub output: String,
+    pub enabled: bool,
+    pub temperature: u32,
+    /// `false` when the backend cannot target one output and Night Light
+    /// was switched for all of them instead
+    pub per_output: bool,
+}
+
+/// Turn Night Light on or off for a single output
+///
+/// Supported with wlsunset and wl-gammarelay-rs; GNOME and gammastep fall
+/// back to switching Night Light globally. `temp` defaults to the usual
+/// Night Light temperature.
+#[tauri::command]
+pub async fn set_night_light_for_output(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    output: String,
+    enabled: bool,
+    temp: Option<u32>,
+) -> Result<OutputNightLight, String> {
+    let temperature = temp.unwrap_or(NIGHT_LIGHT_TEMPERATURE);
+    if !NIGHT_LIGHT_TEMPERATURE_RANGE.contains(&temperature) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Temperature must be {}-{}K, got {}",
+            NIGHT_LIGHT_TEMPERATURE_RANGE.start(),
+            NIGHT_LIGHT_TEMPERATURE_RANGE.end(),
+            temperature
+        ))
+        .to_string());
+    }
+    let mut known_outputs = Vec::new();
+    if state.compositor() == Compositor::Niri {
+        let outputs = get_niri_outputs_internal(&state).await.map_err(|e| e.to_string())?;
+        find_output(&outputs, &output).map_err(|e| e.to_string())?;
+        known_outputs = outputs.into_iter().map(|o| o.name).collect();
+    }
+    
+    let backend = resolve_night_light_backend(&state).await.ok_or_else(|| {
+        ControlCentreError::CommandNotFound("No Night Light backend available".to_string())
+            .to_string()
+    })?;
+    
+    let per_output = matches!(backend, NightLightBackend::Wlsunset | NightLightBackend::WlrGamma);
+    if per_output {
+        let mut outputs = state.night_light_outputs();
+        if enabled {
+            outputs.insert(output.clone(), temperature);
+        } else {
+            outputs.remove(&output);
+        }
+        let global = OutputNightLightGlobal {
+            schedule: config.get().night_light_schedule,
+            known_outputs,
+            previous: state.night_light_outputs(),
+        };
+        apply_output_night_light(backend, &output, &outputs, &global)
+            .await
+            .map_err(|e| e.to_string())?;
+        // Only record what was applied; the global status may have changed too
+        state.set_output_night_light(&output, enabled.then_some(temperature));
+        state.invalidate(CacheDomain::Display);
+    } else {
+        warn!("{:?} cannot target one output, switching Night Light globally", backend);
+        let schedule = config.get().night_light_schedule;
+        set_night_light_internal(backend, enabled, schedule.as_ref())
+            .await
+            .map_err(|e| e.to_string())?;
+        state.set_night_light_temperature(applied_temperature(enabled, schedule.as_ref()));
+        state.set_cached_night_light(enabled);
+    }
+    
+    info!("Night Light on {} set to {} ({}K)", output, enabled, temperature);
+    Ok(OutputNightLight {
+        output,
+        enabled,
+        temperature,
+        per_output,
+    })
+}
+
+/// What a per-output change needs to keep global wlsunset Night Light going
+struct OutputNightLightGlobal {
+    schedule: Option<NightLightSchedule>,
+    /// Every connected output, empty when the compositor cannot list them
+    known_outputs: Vec<String>,
+    /// Per-output settings before this change, to tell their instances apart
+    previous: BTreeMap<String, u32>,
+}
+
+/// Apply the per-output Night Light map after `changed` was updated
+async fn apply_output_night_light(
+    backend: NightLightBackend,
+    changed: &str,
+    outputs: &BTreeMap<String, u32>,
+    global: &OutputNightLightGlobal,
+) -> CCResult<()> {
+    match backend {
+        NightLightBackend::Wlsunset => {
+            // One pinned wlsunset per output; the global instance, if any, is
+            // restarted for the remaining outputs
+            let global_enabled = global_wlsunset_running(&global.previous).await;
+            let _ = run_command_no_output("pkill", &["-x", "wlsunset"]).await;
+            for (output, temperature) in outputs {
+                let low = temperature.to_string();
+                let high = (temperature + 1).to_string();
+                spawn_detached("wlsunset", &["-o", output, "-T", &high, "-t", &low])?;
+            }
+            if global_enabled {
+                let mut args = wlsunset_global_args(global.schedule.as_ref());
+                if !outputs.is_empty() {
+                    if global.known_outputs.is_empty() {
+                        warn!("Cannot list outputs, global Night Light stays off");
+                        return Ok(());
+                    }
+                    let remaining =
+                        global.known_outputs.iter().filter(|o| !outputs.contains_key(*o));
+                    for output in remaining {
+                        args.extend(["-o".to_string(), output.clone()]);
+                    }
+                    if !args.iter().any(|arg| arg == "-o") {
+                        // Every output has its own setting
+                        return Ok(());
+                    }
+                }
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                spawn_detached("wlsunset", &args)?;
+            }
+            Ok(())
+        }
+        NightLightBackend::WlrGamma => {
+            ensure_gammarelay_running().await?;
+            let temperature = outputs.get(changed).copied().unwrap_or(NEUTRAL_TEMPERATURE);
+            run_command_no_output(
+                "busctl",
+                &[
+                    "--user",
+                    "set-property",
+                    GAMMARELAY_SERVICE,
+                    &gammarelay_output_path(changed),
+                    GAMMARELAY_INTERFACE,
+                    "Temperature",
+                    "q",
+                    &temperature.to_string(),
+                ],
+            )
+            .await
+        }
+        NightLightBackend::Gnome | NightLightBackend::Gammastep => Err(
+            ControlCentreError::SystemError(format!("{:?} has no per-output Night Light", backend)),
+        ),
+    }
+}
+
+/// D-Bus object of one output in wl-gammarelay-rs (`eDP-1` -> `/outputs/eDP_1`)
+fn gammarelay_output_path(output: &str) -> String {
+    let name: String = output
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("/outputs/{}", name)
+}
+
+/// Write a gammastep config switching at the scheduled times
+fn write_gammastep_schedule(schedule: &NightLightSchedule) -> CCResult<std::path::PathBuf> {
+    let path = Config::dir().join("gammastep.ini");
+    let contents = format!(
+        "[general]\ntemp-day={}\ntemp-night={}\ndusk-time={}\ndawn-time={}\n",
+        NEUTRAL_TEMPERATURE, NIGHT_LIGHT_TEMPERATURE, schedule.from, schedule.to
+    );
+    
+    std::fs::create_dir_all(Config::dir())?;
+    std::fs::write(&path, contents)?;
+    Ok(path)
+}
+
+// ============================================================================
+// Appearance Commands (gsettings / dconf / settings portal / GTK settings.ini)
+// ============================================================================
+
+/// System-wide light/dark preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    /// Value of `org.gnome.desktop.interface color-scheme`, which the
+    /// freedesktop portal exposes as `org.freedesktop.appearance color-scheme`
+    fn gsettings_value(self) -> &'static str {
+        match self {
+            Self::Light => "default",
+            Self::Dark => "prefer-dark",
+        }
+    }
+}
+
+/// dconf key behind `org.gnome.desktop.interface color-scheme`
+const DCONF_COLOR_SCHEME_KEY: &str = "/org/gnome/desktop/interface/color-scheme";
+
+/// Get the system color scheme
+///
+/// Read from gsettings, else from the settings portal, else from GTK's
+/// settings.ini.
+#[tauri::command]
+pub async fn get_color_scheme() -> Result<ColorScheme, String> {
+    get_color_scheme_internal().await.map_err(|e| e.to_string())
+}
+
+/// Set the system color scheme and sync the panel's own theme
+///
+/// Written with gsettings, or with dconf where only that is installed; the
+/// GNOME and GTK portal backends serve this key as the portal's
+/// `org.freedesktop.appearance color-scheme`, which the portal itself
+/// cannot write. Without either the dark-theme preference in GTK's
+/// settings.ini is set instead, which only GTK apps follow. Returns the
+/// applied scheme.
+#[tauri::command]
+pub async fn set_color_scheme(app: AppHandle, scheme: ColorScheme) -> Result<ColorScheme, String> {
+    if command_exists("gsettings") {
+        run_command_no_output(
+            "gsettings",
+            &["set", "org.gnome.desktop.interface", "color-scheme", scheme.gsettings_value()],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    } else if command_exists("dconf") {
+        let value = format!("'{}'", scheme.gsettings_value());
+        run_command_no_output("dconf", &["write", DCONF_COLOR_SCHEME_KEY, &value])
+            .await
+            .map_err(|e| e.to_string())?;
+    } else {
+        warn!("Neither gsettings nor dconf found, the color scheme only reaches GTK apps");
+        write_gtk_prefer_dark(scheme == ColorScheme::Dark).map_err(|e| e.to_string())?;
+    }
+    
+    info!("Color scheme set to {:?}", scheme);
+    if let Err(e) = app.emit("color-scheme-changed", scheme) {
+        warn!("Failed to emit color-scheme-changed: {}", e);
+    }
+    
+    Ok(scheme)
+}
+
+async fn get_color_scheme_internal() -> CCResult<ColorScheme> {
+    if command_exists("gsettings") {
+        let output =
+            run_command("gsettings", &["get", "org.gnome.desktop.interface", "color-scheme"]).await?;
+        return Ok(if output.contains("dark") {
+            ColorScheme::Dark
+        } else {
+            ColorScheme::Light
+        });
+    }
+    
+    let portal = run_command(
+        "busctl",
+        &[
+            "--user",
+            "call",
+            "org.freedesktop.portal.Desktop",
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+            "ReadOne",
+            "ss",
+            "org.freedesktop.appearance",
+            "color-scheme",
+        ],
+    )
+    .await;
+    if let Some(scheme) = portal.ok().as_deref().and_then(parse_portal_color_scheme) {
+        return Ok(scheme);
+    }
+    
+    let contents = std::fs::read_to_string(gtk_settings_path("gtk-3.0")).unwrap_or_default();
+    let dark = contents.lines().any(|line| {
+        line.split_once('=').is_some_and(|(key, value)| {
+            key.trim() == GTK_PREFER_DARK_KEY && matches!(value.trim(), "1" | "true")
+        })
+    });
+    Ok(if dark { ColorScheme::Dark } else { ColorScheme::Light })
+}
+
+/// Parse the portal's `color-scheme` reply such as `v u 1`
+///
+/// 1 prefers dark; 0 (no preference) and 2 (prefers light) count as light.
+fn parse_portal_color_scheme(output: &str) -> Option<ColorScheme> {
+    match output.split_whitespace().last()?.parse::<u32>().ok()? {
+        1 => Some(ColorScheme::Dark),
+        0 | 2 => Some(ColorScheme::Light),
+        _ => None,
+    }
+}
+
+/// Key in GTK's settings.ini selecting the dark variant of the theme
+const GTK_PREFER_DARK_KEY: &str = "gtk-application-prefer-dark-theme";
+
+fn gtk_settings_path(version: &str) -> std::path::PathBuf {
+    Config::base_dir().join(version).join("settings.ini")
+}
+
+/// Fallback without gsettings or dconf: set the dark-theme preference GTK
+/// apps read; other toolkits and the portal do not see it
+fn write_gtk_prefer_dark(dark: bool) -> CCResult<()> {
+    for version in ["gtk-3.0", "gtk-4.0"] {
+        let path = gtk_settings_path(version);
+        let contents = std::fs::read_to_string(&path).unwrap_or_default();
+        let value = if dark { "1" } else { "0" };
+        let updated = set_ini_value(&contents, "Settings", GTK_PREFER_DARK_KEY, value);
+        
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, updated)?;
+        debug!("Updated {}", path.display());
+    }
+    Ok(())
+}
+
+/// Set `key=value` in `[section]` of an ini file, adding either if missing
+fn set_ini_value(contents: &str, section: &str, key: &str, value: &str) -> String {
+    let header = format!("[{}]", section);
+    let entry = format!("{}={}", key, value);
+    
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let Some(start) = lines.iter().position(|l| l.trim() == header) else {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(header);
+        lines.push(entry);
+        return lines.join("\n") + "\n";
+    };
+    
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .map_or(lines.len(), |i| start + 1 + i);
+    
+    match lines[start + 1..end]
+        .iter()
+        .position(|l| l.split_once('=').is_some_and(|(k, _)| k.trim() == key))
+    {
+        Some(i) => lines[start + 1 + i] = entry,
+        None => lines.insert(end, entry),
+    }
+    
+    lines.join("\n") + "\n"
+}
+
+/// Panel background opacity without a configured `window_opacity`; matches
+/// the alpha of `--bg-primary` in styles.css
+const DEFAULT_WINDOW_OPACITY: f32 = 0.85;
+
+/// Get the panel background opacity (0.0-1.0)
+#[tauri::command]
+pub async fn get_window_opacity(config: State<'_, ConfigStore>) -> Result<f32, String> {
+    Ok(config.get().window_opacity.unwrap_or(DEFAULT_WINDOW_OPACITY))
+}
+
+/// Set and save the panel background opacity (0.0-1.0)
+///
+/// The frontend applies it on `window-opacity-changed`. How much of the
+/// desktop shows through blurred is up to the compositor's blur settings.
+#[tauri::command]
+pub async fn set_window_opacity(
+    app: AppHandle,
+    config: State<'_, ConfigStore>,
+    level: f32,
+) -> Result<f32, String> {
+    let level = validate_opacity(level).map_err(|e| e.to_string())?;
+    config
+        .update(|c| c.window_opacity = Some(level))
+        .map_err(|e| e.to_string())?;
+    
+    info!("Window opacity set to {}", level);
+    if let Err(e) = app.emit("window-opacity-changed", level) {
+        warn!("Failed to emit window-opacity-changed: {}", e);
+    }
+    
+    Ok(level)
+}
+
+fn validate_opacity(level: f32) -> CCResult<f32> {
+    if (0.0..=1.0).contains(&level) {
+        Ok(level)
+    } else {
+        Err(ControlCentreError::InvalidArgument(format!(
+            "Opacity must be between 0.0 and 1.0, got {}",
+            level
+        )))
+    }
+}
+
+// ============================================================================
+// Wallpaper Commands (swww / swaybg / hyprpaper)
+// ============================================================================
+
+/// File extensions accepted by `set_wallpaper`
+const WALLPAPER_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+/// Tools that can draw the wallpaper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WallpaperBackend {
+    /// swww, talking to a running swww-daemon
+    Swww,
+    /// swaybg, restarted for every change
+    Swaybg,
+    /// hyprpaper via hyprctl
+    Hyprpaper,
+}
+
+/// The wallpaper shown and the tool showing it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Wallpaper {
+    pub backend: WallpaperBackend,
+    /// Image path, `None` when the backend shows a plain color or nothing
+    pub path: Option<String>,
+}
+
+/// Get the current wallpaper
+#[tauri::command]
+pub async fn get_wallpaper() -> Result<Wallpaper, String> {
+    let backend = detect_wallpaper_backend()
+        .await
+        .ok_or_else(no_wallpaper_backend)
+        .map_err(|e| e.to_string())?;
+    
+    let path = get_wallpaper_path_internal(backend).await.map_err(|e| e.to_string())?;
+    Ok(Wallpaper { backend, path })
+}
+
+/// Show the image at `path` as wallpaper on all outputs
+#[tauri::command]
+pub async fn set_wallpaper(path: String) -> Result<Wallpaper, String> {
+    set_wallpaper_internal(&path).await.map_err(|e| e.to_string())
+}
+
+async fn set_wallpaper_internal(path: &str) -> CCResult<Wallpaper> {
+    let path = validate_wallpaper_path(path)?;
+    let backend = detect_wallpaper_backend().await.ok_or_else(no_wallpaper_backend)?;
+    
+    match backend {
+        WallpaperBackend::Swww => run_command_no_output("swww", &["img", &path]).await?,
+        WallpaperBackend::Swaybg => {
+            let _ = run_command_no_output("pkill", &["-x", "swaybg"]).await;
+            spawn_detached("swaybg", &["-m", "fill", "-i", &path])?;
+        }
+        WallpaperBackend::Hyprpaper => {
+            run_command_no_output("hyprctl", &["hyprpaper", "preload", &path]).await?;
+            run_command_no_output("hyprctl", &["hyprpaper", "wallpaper", &format!(",{}", path)])
+                .await?;
+        }
+    }
+    
+    info!("Wallpaper set to {} via {:?}", path, backend);
+    Ok(Wallpaper { backend, path: Some(path) })
+}
+
+fn no_wallpaper_backend() -> ControlCentreError {
+    ControlCentreError::CommandNotFound(
+        "No wallpaper backend (swww, swaybg, hyprpaper) available".to_string(),
+    )
+}
+
+/// Pick the wallpaper tool, preferring whichever daemon is already running
+///
+/// With nothing running only swaybg can be used, as it needs no daemon.
+async fn detect_wallpaper_backend() -> Option<WallpaperBackend> {
+    const DAEMONS: [(&str, WallpaperBackend); 3] = [
+        ("swww-daemon", WallpaperBackend::Swww),
+        ("hyprpaper", WallpaperBackend::Hyprpaper),
+        ("swaybg", WallpaperBackend::Swaybg),
+    ];
+    
+    for (process, backend) in DAEMONS {
+        if run_command("pgrep", &["-x", process]).await.is_ok() {
+            return Some(backend);
+        }
+    }
+    command_exists("swaybg").then_some(WallpaperBackend::Swaybg)
+}
+
+async fn get_wallpaper_path_internal(backend: WallpaperBackend) -> CCResult<Option<String>> {
+    match backend {
+        WallpaperBackend::Swww => Ok(parse_swww_query(&run_command("swww", &["query"]).await?)),
+        WallpaperBackend::Hyprpaper => Ok(parse_hyprpaper_active(
+            &run_command("hyprctl", &["hyprpaper", "listactive"]).await?,
+        )),
+        WallpaperBackend::Swaybg => {
+            Ok(process_cmdline("swaybg").await?.and_then(|cmdline| parse_swaybg_cmdline(&cmdline)))
+        }
+    }
+}
+
+/// Check that `path` is an existing image file, returning it canonicalized
+fn validate_wallpaper_path(path: &str) -> CCResult<String> {
+    let path = std::path::Path::new(path);
+    let is_image = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| WALLPAPER_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+    if !is_image {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Not an image file: {}",
+            path.display()
+        )));
+    }
+    
+    let canonical = path.canonicalize().map_err(|_| {
+        ControlCentreError::InvalidArgument(format!("No such file: {}", path.display()))
+    })?;
+    if !canonical.is_file() {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Not a file: {}",
+            path.display()
+        )));
+    }
+    Ok(canonical.to_string_lossy().into_owned())
+}
+
+/// Image of the first output in `swww query` output
+///
+/// Lines look like `eDP-1: 1920x1080, scale: 1, currently displaying: image: /a.png`.
+fn parse_swww_query(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (_, image) = line.split_once("image: ")?;
+        let image = image.trim();
+        (!image.is_empty()).then(|| image.to_string())
+    })
+}
+
+/// Image of the first output in `hyprctl hyprpaper listactive` (`eDP-1 = /a.png`)
+fn parse_hyprpaper_active(output: &str) -> Option<String> {
+    output.lines().find_map(|line| {
+        let (_, image) = line.split_once(" = ")?;
+        let image = image.trim();
+        (!image.is_empty()).then(|| image.to_string())
+    })
+}
+
+/// Image passed to swaybg via `-i`/`--image`, from its NUL-separated cmdline
+fn parse_swaybg_cmdline(cmdline: &[u8]) -> Option<String> {
+    let args = split_cmdline(cmdline);
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if arg == "-i" || arg == "--image" {
+            args.get(i + 1).cloned()
+        } else {
+            arg.strip_prefix("--image=").map(str::to_string)
+        }
+    })
+}
+
+// ============================================================================
+// Notification Commands (mako / swaync)
+// ============================================================================
+
+/// Supported notification daemons
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NotificationDaemon {
+    Mako,
+    Swaync,
+}
+
+/// Detect which notification daemon is running, if any
+async fn detect_notification_daemon() -> Option<NotificationDaemon> {
+    if run_command("pgrep", &["-x", "swaync"]).await.is_ok() {
+        Some(NotificationDaemon::Swaync)
+    } else if run_command("pgrep", &["-x", "mako"]).await.is_ok() {
+        Some(NotificationDaemon::Mako)
+    } else {
+        None
+    }
+}
+
+/// Get the notification history from the running daemon
+///
+/// Returns an empty list when no supported daemon is running or it keeps
+/// no history.
+#[tauri::command]
+pub async fn get_notification_history() -> Result<Vec<Notification>, String> {
+    let history = match detect_notification_daemon().await {
+        Some(NotificationDaemon::Swaync) => run_command("swaync-client", &["--get-history"])
+            .await
+            .map(|output| parse_swaync_history(&output)),
+        Some(NotificationDaemon::Mako) => run_command("makoctl", &["history"])
+            .await
+            .map(|output| parse_mako_history(&output)),
+        None => {
+            debug!("No notification daemon detected");
+            return Ok(Vec::new());
+        }
+    };
+    
+    Ok(history.unwrap_or_else(|e| {
+        warn!("Failed to read notification history: {}", e);
+        Vec::new()
+    }))
+}
+
+/// Dismiss all visible notifications
+#[tauri::command]
+pub async fn dismiss_all_notifications() -> Result<(), String> {
+    match detect_notification_daemon().await {
+        Some(NotificationDaemon::Swaync) => {
+            run_command_no_output("swaync-client", &["--close-all"]).await
+        }
+        Some(NotificationDaemon::Mako) => {
+            run_command_no_output("makoctl", &["dismiss", "--all"]).await
+        }
+        None => Err(no_notification_daemon()),
+    }
+    .map_err(|e| e.to_string())?;
+    
+    info!("All notifications dismissed");
+    Ok(())
+}
+
+/// Mode a daemon is in when no other mode is active
+const DEFAULT_NOTIFICATION_MODE: &str = "default";
+
+/// Mode name the plain Do Not Disturb toggle is exposed as
+const DND_NOTIFICATION_MODE: &str = "do-not-disturb";
+
+/// Get the notification daemon's current mode and the modes it has
+///
+/// mako's modes are read from the `[mode=...]` sections of its config.
+/// swaync only has Do Not Disturb, offered as the `default` and
+/// `do-not-disturb` modes.
+#[tauri::command]
+pub async fn get_notification_mode() -> Result<NotificationMode, String> {
+    let daemon = detect_notification_daemon()
+        .await
+        .ok_or_else(no_notification_daemon)
+        .map_err(|e| e.to_string())?;
+    get_notification_mode_internal(daemon).await.map_err(|e| e.to_string())
+}
+
+/// Switch the notification daemon to `mode`, returning the new mode
+#[tauri::command]
+pub async fn set_notification_mode(mode: String) -> Result<NotificationMode, String> {
+    let daemon = detect_notification_daemon()
+        .await
+        .ok_or_else(no_notification_daemon)
+        .map_err(|e| e.to_string())?;
+    
+    match daemon {
+        NotificationDaemon::Mako => {
+            validate_mako_mode(&mode).map_err(|e| e.to_string())?;
+            run_command_no_output("makoctl", &["mode", "-s", &mode]).await
+        }
+        NotificationDaemon::Swaync => {
+            let flag = match mode.as_str() {
+                DEFAULT_NOTIFICATION_MODE => "--dnd-off",
+                DND_NOTIFICATION_MODE => "--dnd-on",
+                _ => {
+                    return Err(ControlCentreError::InvalidArgument(format!(
+                        "swaync has no mode '{}'",
+                        mode
+                    ))
+                    .to_string())
+                }
+            };
+            run_command_no_output("swaync-client", &[flag]).await
+        }
+    }
+    .map_err(|e| e.to_string())?;
+    
+    info!("Notification mode set to {}", mode);
+    get_notification_mode_internal(daemon).await.map_err(|e| e.to_string())
+}
+
+fn no_notification_daemon() -> ControlCentreError {
+    ControlCentreError::CommandNotFound(
+        "No supported notification daemon (mako, swaync) is running".to_string(),
+    )
+}
+
+async fn get_notification_mode_internal(daemon: NotificationDaemon) -> CCResult<NotificationMode> {
+    match daemon {
+        NotificationDaemon::Mako => {
+            let output = run_command("makoctl", &["mode"]).await?;
+            let config = tokio::fs::read_to_string(Config::base_dir().join("mako").join("config"))
+                .await
+                .unwrap_or_default();
+            Ok(mako_notification_mode(&output, &config))
+        }
+        NotificationDaemon::Swaync => {
+            let output = run_command("swaync-client", &["--get-dnd"]).await?;
+            let mode = match output.trim() {
+                "true" => DND_NOTIFICATION_MODE,
+                "false" => DEFAULT_NOTIFICATION_MODE,
+                other => {
+                    return Err(ControlCentreError::ParseError(format!(
+                        "Unexpected swaync-client --get-dnd output: {}",
+                        other
+                    )))
+                }
+            };
+            Ok(NotificationMode {
+                mode: mode.to_string(),
+                available: vec![
+                    DEFAULT_NOTIFICATION_MODE.to_string(),
+                    DND_NOTIFICATION_MODE.to_string(),
+                ],
+            })
+        }
+    }
+}
+
+/// Combine `makoctl mode` output with the modes defined in mako's config
+///
+/// `makoctl mode` lists the active modes, the most recently set last.
+fn mako_notification_mode(active: &str, config: &str) -> NotificationMode {
+    let mode = active
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .unwrap_or(DEFAULT_NOTIFICATION_MODE)
+        .to_string();
+    
+    let mut available = vec![DEFAULT_NOTIFICATION_MODE.to_string()];
+    let defined = config.lines().filter_map(|line| {
+        line.trim()
+            .strip_prefix('[')?
+            .strip_suffix(']')?
+            .split_whitespace()
+            .find_map(|criterion| criterion.strip_prefix("mode="))
+    });
+    for name in defined.chain(std::iter::once(mode.as_str())) {
+        if !available.iter().any(|known| known == name) {
+            available.push(name.to_string());
+        }
+    }
+    
+    NotificationMode { mode, available }
+}
+
+/// Mode names are passed to makoctl as one argument; keep them to plain words
+fn validate_mako_mode(mode: &str) -> CCResult<()> {
+    let valid = !mode.is_empty()
+        && !mode.starts_with('-')
+        && mode
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ControlCentreError::InvalidArgument(format!(
+            "Invalid notification mode: {}",
+            mode
+        )))
+    }
+}
+
+/// Parse swaync's JSON history (an array of notification objects)
+fn parse_swaync_history(output: &str) -> Vec<Notification> {
+    let Ok(serde_json::Value::Array(entries)) = serde_json::from_str(output) else {
+        return Vec::new();
+    };
+    
+    let field = |entry: &serde_json::Value, keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| entry.get(*key))
+            .map(|value| match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+    };
+    
+    entries
+        .iter()
+        .map(|entry| Notification {
+            app: field(entry, &["app_name", "app-name", "appName"]).unwrap_or_default(),
+            summary: field(entry, &["summary"]).unwrap_or_default(),
+            body: field(entry, &["body"]).unwrap_or_default(),
+            time: field(entry, &["time", "timestamp"]),
+        })
+        .collect()
+}
+
+/// Parse `makoctl history` text output
+///
+/// Each entry starts with `Notification <id>: <summary>` followed by
+/// indented `Key: value` lines.
+fn parse_mako_history(output: &str) -> Vec<Notification> {
+    let mut notifications = Vec::new();
+    
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("Notification ") {
+            let summary = rest.split_once(": ").map(|(_, s)| s).unwrap_or("");
+            notifications.push(Notification {
+                app: String::new(),
+                summary: summary.to_string(),
+                body: String::new(),
+                time: None,
+            });
+        } else if let Some(current) = notifications.last_mut() {
+            let Some((key, value)) = line.trim().split_once(": ") else {
+                continue;
+            };
+            match key {
+                "App name" => current.app = value.to_string(),
+                "Body" => current.body = value.to_string(),
+                "Time" => current.time = Some(value.to_string()),
+                _ => {}
+            }
+        }
+    }
+    
+    notifications
+}
+
+// ============================================================================
+// Compositor Detection
+// ============================================================================
+
+/// Wayland compositor the panel is running under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compositor {
+    Niri,
+    Sway,
+    Hyprland,
+    Other,
+}
+
+impl Compositor {
+    /// Detect the compositor from its IPC socket variables, falling back
+    /// to `$XDG_CURRENT_DESKTOP`
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+    
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let set = |name: &str| var(name).is_some_and(|v| !v.is_empty());
+        
+        if set("NIRI_SOCKET") {
+            return Self::Niri;
+        }
+        if set("SWAYSOCK") {
+            return Self::Sway;
+        }
+        if set("HYPRLAND_INSTANCE_SIGNATURE") {
+            return Self::Hyprland;
+        }
+        
+        let desktop = var("XDG_CURRENT_DESKTOP").unwrap_or_default().to_lowercase();
+        desktop
+            .split(':')
+            .find_map(|name| match name {
+                "niri" => Some(Self::Niri),
+                "sway" => Some(Self::Sway),
+                "hyprland" => Some(Self::Hyprland),
+                _ => None,
+            })
+            .unwrap_or(Self::Other)
+    }
+}
+
+/// Get the compositor detected at startup
+#[tauri::command]
+pub async fn detect_compositor(state: State<'_, AppState>) -> Result<Compositor, String> {
+    Ok(state.compositor())
+}
+
+// ============================================================================
+// Display Commands (Niri)
+// ============================================================================
+
+/// List connected outputs with their available and current modes
+#[tauri::command]
+pub async fn get_display_modes(
+    state: State<'_, AppState>,
+) -> Result<Vec<DisplayOutput>, String> {
+    let outputs = get_niri_outputs_internal(&state)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(outputs.iter().map(DisplayOutput::from).collect())
+}
+
+/// Switch an output to the given `<width>x<height>[@<refresh>]` mode
+#[tauri::command]
+pub async fn set_display_mode(
+    state: State<'_, AppState>,
+    output: String,
+    mode: String,
+) -> Result<(), String> {
+    if !niri::is_valid_mode_string(&mode) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Invalid display mode: {}",
+            mode
+        ))
+        .to_string());
+    }
+    
+    let outputs = get_niri_outputs_internal(&state)
+        .await
+        .map_err(|e| e.to_string())?;
+    find_output(&outputs, &output).map_err(|e| e.to_string())?;
+    
+    info!("Setting mode of {} to {}", output, mode);
+    run_command_no_output("niri", &["msg", "output", &output, "mode", &mode])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(())
+}
+
+/// Turn an output on or off, returning the new state
+///
+/// Refuses to disable the last enabled output.
+#[tauri::command]
+pub async fn set_output_enabled(
+    state: State<'_, AppState>,
+    output: String,
+    enabled: bool,
+) -> Result<bool, String> {
+    let outputs = get_niri_outputs_internal(&state)
+        .await
+        .map_err(|e| e.to_string())?;
+    validate_output_toggle(&outputs, &output, enabled).map_err(|e| e.to_string())?;
+    
+    let action = if enabled { "on" } else { "off" };
+    info!("Turning output {} {}", output, action);
+    run_command_no_output("niri", &["msg", "output", &output, action])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(enabled)
+}
+
+/// Mirror or extend the enabled outputs and remember the choice
+///
+/// With a single enabled output this is a no-op. The choice is re-applied
+/// at startup and whenever a monitor is plugged in or removed.
+#[tauri::command]
+pub async fn set_display_arrangement(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    mode: DisplayArrangement,
+) -> Result<(), String> {
+    apply_display_arrangement(&state, mode)
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    config
+        .update(|c| c.display_arrangement = Some(mode))
+        .map_err(|e| e.to_string())
+}
+
+async fn apply_display_arrangement(state: &AppState, mode: DisplayArrangement) -> CCResult<()> {
+    let outputs = get_niri_outputs_internal(state).await?;
+    
+    let positions = niri::arrangement_positions(&outputs, mode);
+    if positions.len() > 1 {
+        info!("Arranging {} outputs: {:?}", positions.len(), mode);
+        for (name, x, y) in positions {
+            let (x, y) = (x.to_string(), y.to_string());
+            run_command_no_output("niri", &["msg", "output", &name, "position", "set", &x, &y])
+                .await?;
+        }
+    } else {
+        debug!("Single enabled output, nothing to arrange");
+    }
+    Ok(())
+}
+
+/// Re-apply the saved display arrangement, if any, under Niri
+pub(crate) async fn reapply_display_arrangement(state: &AppState, config: &ConfigStore) {
+    let Some(mode) = config.get().display_arrangement else {
+        return;
+    };
+    if state.compositor() != Compositor::Niri {
+        return;
+    }
+    if let Err(e) = apply_display_arrangement(state, mode).await {
+        warn!("Could not re-apply the {:?} display arrangement: {}", mode, e);
+    }
+}
+
+// ============================================================================
+// Input Commands (Niri config / gsettings)
+// ============================================================================
+
+/// GNOME keyboard settings schema, used outside Niri
+const GNOME_KEYBOARD_SCHEMA: &str = "org.gnome.desktop.peripherals.keyboard";
+
+/// Niri's repeat settings when its config does not set them
+const NIRI_DEFAULT_REPEAT_RATE: u32 = 25;
+const NIRI_DEFAULT_REPEAT_DELAY: u32 = 600;
+
+/// Accepted repeat rates (characters per second)
+const REPEAT_RATE_RANGE: std::ops::RangeInclusive<u32> = 1..=100;
+
+/// Accepted repeat delays (milliseconds)
+const REPEAT_DELAY_RANGE: std::ops::RangeInclusive<u32> = 100..=2000;
+
+/// Keyboard auto-repeat settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyboardRepeat {
+    /// Repeats per second
+    pub rate: u32,
+    /// Milliseconds before repeating starts
+    pub delay: u32,
+}
+
+/// Get the keyboard repeat rate and delay
+#[tauri::command]
+pub async fn get_keyboard_repeat(state: State<'_, AppState>) -> Result<KeyboardRepeat, String> {
+    get_keyboard_repeat_internal(state.compositor())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Set the keyboard repeat rate (1-100/s) and delay (100-2000ms)
+///
+/// Under Niri this edits `input { keyboard { } }` in its config, which Niri
+/// reloads on its own; elsewhere the GNOME keyboard settings are used.
+/// Returns the values read back afterwards.
+#[tauri::command]
+pub async fn set_keyboard_repeat(
+    state: State<'_, AppState>,
+    rate: u32,
+    delay: u32,
+) -> Result<KeyboardRepeat, String> {
+    validate_keyboard_repeat(rate, delay).map_err(|e| e.to_string())?;
+    
+    let compositor = state.compositor();
+    if compositor == Compositor::Niri {
+        let settings = [
+            ("repeat-rate", rate.to_string()),
+            ("repeat-delay", delay.to_string()),
+        ];
+        update_niri_input_config("keyboard", &settings).map_err(|e| e.to_string())?;
+    } else {
+        let interval = (1000 / rate).to_string();
+        let delay = delay.to_string();
+        for (key, value) in [("repeat-interval", interval), ("delay", delay)] {
+            run_command_no_output("gsettings", &["set", GNOME_KEYBOARD_SCHEMA, key, &value])
+                .await
+                .map_err(|_| live_input_unsupported(compositor).to_string())?;
+        }
+    }
+    
+    info!("Keyboard repeat set to {}/s after {}ms", rate, delay);
+    get_keyboard_repeat_internal(compositor)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn get_keyboard_repeat_internal(compositor: Compositor) -> CCResult<KeyboardRepeat> {
+    if compositor == Compositor::Niri {
+        let kdl = read_niri_config()?;
+        let setting = |key, default| {
+            niri::input_setting(&kdl, "keyboard", key)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(default)
+        };
+        return Ok(KeyboardRepeat {
+            rate: setting("repeat-rate", NIRI_DEFAULT_REPEAT_RATE),
+            delay: setting("repeat-delay", NIRI_DEFAULT_REPEAT_DELAY),
+        });
+    }
+    
+    let get = |key| async move {
+        let output = run_command("gsettings", &["get", GNOME_KEYBOARD_SCHEMA, key])
+            .await
+            .map_err(|_| live_input_unsupported(compositor))?;
+        parse_gsettings_uint(&output).ok_or_else(|| {
+            ControlCentreError::ParseError(format!("Unexpected gsettings value: {}", output))
+        })
+    };
+    let (interval, delay) = tokio::try_join!(get("repeat-interval"), get("delay"))?;
+    
+    Ok(KeyboardRepeat {
+        rate: 1000 / interval.max(1),
+        delay,
+    })
+}
+
+/// A pointer device kind with its own acceleration setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointerDevice {
+    Mouse,
+    Touchpad,
+}
+
+impl PointerDevice {
+    /// Block name in Niri's `input` section
+    fn niri_block(self) -> &'static str {
+        match self {
+            PointerDevice::Mouse => "mouse",
+            PointerDevice::Touchpad => "touchpad",
+        }
+    }
+    
+    fn gnome_schema(self) -> &'static str {
+        match self {
+            PointerDevice::Mouse => "org.gnome.desktop.peripherals.mouse",
+            PointerDevice::Touchpad => "org.gnome.desktop.peripherals.touchpad",
+        }
+    }
+}
+
+/// Get the pointer acceleration (-1.0 to 1.0) of a mouse or touchpad
+///
+/// Returns `None` when no such device is connected.
+#[tauri::command]
+pub async fn get_pointer_speed(
+    state: State<'_, AppState>,
+    device: PointerDevice,
+) -> Result<Option<f32>, String> {
+    if !pointer_device_present(device) {
+        return Ok(None);
+    }
+    
+    get_pointer_speed_internal(state.compositor(), device)
+        .await
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+/// Set the pointer acceleration (-1.0 to 1.0) of a mouse or touchpad
+///
+/// Applied the same way as `set_keyboard_repeat`. Returns the value read
+/// back afterwards.
+#[tauri::command]
+pub async fn set_pointer_speed(
+    state: State<'_, AppState>,
+    device: PointerDevice,
+    speed: f32,
+) -> Result<f32, String> {
+    if !(-1.0..=1.0).contains(&speed) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Pointer speed must be between -1.0 and 1.0, got {}",
+            speed
+        ))
+        .to_string());
+    }
+    if !pointer_device_present(device) {
+        return Err(
+            ControlCentreError::SystemError(format!("No {:?} connected", device)).to_string(),
+        );
+    }
+    
+    let compositor = state.compositor();
+    let value = format!("{:.2}", speed);
+    if compositor == Compositor::Niri {
+        update_niri_input_config(device.niri_block(), &[("accel-speed", value)])
+            .map_err(|e| e.to_string())?;
+    } else {
+        run_command_no_output("gsettings", &["set", device.gnome_schema(), "speed", &value])
+            .await
+            .map_err(|_| live_input_unsupported(compositor).to_string())?;
+    }
+    
+    info!("{:?} speed set to {:.2}", device, speed);
+    get_pointer_speed_internal(compositor, device)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn get_pointer_speed_internal(compositor: Compositor, device: PointerDevice) -> CCResult<f32> {
+    let raw = if compositor == Compositor::Niri {
+        // Niri leaves acceleration at libinput's default of 0 when unset
+        niri::input_setting(&read_niri_config()?, device.niri_block(), "accel-speed")
+            .unwrap_or_else(|| "0".to_string())
+    } else {
+        run_command("gsettings", &["get", device.gnome_schema(), "speed"])
+            .await
+            .map_err(|_| live_input_unsupported(compositor))?
+    };
+    
+    raw.trim()
+        .parse::<f32>()
+        .map_err(|_| ControlCentreError::ParseError(format!("Unexpected pointer speed: {}", raw)))
+}
+
+/// Whether a device of this kind shows up in `/proc/bus/input/devices`
+///
+/// If the list cannot be read the device is assumed present, so the
+/// backend gets to report the real error.
+fn pointer_device_present(device: PointerDevice) -> bool {
+    match std::fs::read_to_string("/proc/bus/input/devices") {
+        Ok(devices) => parse_pointer_devices(&devices).contains(&device),
+        Err(_) => true,
+    }
+}
+
+/// Pointer device kinds listed in `/proc/bus/input/devices`
+fn parse_pointer_devices(devices: &str) -> Vec<PointerDevice> {
+    let mut found = Vec::new();
+    
+    for block in devices.split("\n\n") {
+        let field = |prefix: &str| {
+            block
+                .lines()
+                .find_map(|line| line.strip_prefix(prefix))
+                .unwrap_or_default()
+        };
+        let name = field("N: Name=").to_lowercase();
+        let is_pointer = field("H: Handlers=").split_whitespace().any(|h| h.starts_with("mouse"));
+        if !is_pointer {
+            continue;
+        }
+        
+        let kind = if name.contains("touchpad") || name.contains("trackpad") {
+            PointerDevice::Touchpad
+        } else {
+            PointerDevice::Mouse
+        };
+        if !found.contains(&kind) {
+            found.push(kind);
+        }
+    }
+    
+    found
+}
+
+fn validate_keyboard_repeat(rate: u32, delay: u32) -> CCResult<()> {
+    if !REPEAT_RATE_RANGE.contains(&rate) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Repeat rate must be {}-{} per second, got {}",
+            REPEAT_RATE_RANGE.start(),
+            REPEAT_RATE_RANGE.end(),
+            rate
+        )));
+    }
+    if !REPEAT_DELAY_RANGE.contains(&delay) {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Repeat delay must be {}-{}ms, got {}",
+            REPEAT_DELAY_RANGE.start(),
+            REPEAT_DELAY_RANGE.end(),
+            delay
+        )));
+    }
+    Ok(())
+}
+
+/// Parse a `gsettings get` unsigned reply such as `uint32 30`
+fn parse_gsettings_uint(output: &str) -> Option<u32> {
+    output.split_whitespace().last()?.parse().ok()
+}
+
+/// Error for input settings that cannot be changed live here
+fn live_input_unsupported(compositor: Compositor) -> ControlCentreError {
+    ControlCentreError::SystemError(format!(
+        "Input settings can only be changed live under Niri or GNOME, running under {:?}",
+        compositor
+    ))
+}
+
+fn read_niri_config() -> CCResult<String> {
+    let path = niri::config_path();
+    std::fs::read_to_string(&path).map_err(|e| {
+        ControlCentreError::SystemError(format!("Cannot read Niri config {}: {}", path.display(), e))
+    })
+}
+
+/// Write settings into an `input` device block of the Niri config
+fn update_niri_input_config(device: &str, settings: &[(&str, String)]) -> CCResult<()> {
+    let mut kdl = read_niri_config()?;
+    for (key, value) in settings {
+        kdl = niri::set_input_setting(&kdl, device, key, value).ok_or_else(|| {
+            ControlCentreError::SystemError(format!(
+                "Niri config has no `input {{ {} {{ }} }}` block to update",
+                device
+            ))
+        })?;
+    }
+    
+    write_atomically(&niri::config_path(), &kdl)?;
+    debug!("Updated Niri input {} settings", device);
+    Ok(())
+}
+
+/// Replace a file through a temporary sibling and a rename, so a reader
+/// such as Niri's config watcher never sees a half-written file
+///
+/// Symlinks are resolved first so a managed dotfile stays a link, and the
+/// original permissions are kept.
+fn write_atomically(path: &std::path::Path, contents: &str) -> CCResult<()> {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{}.control-centre.tmp", name));
+    
+    std::fs::write(&tmp, contents)?;
+    if let Ok(metadata) = std::fs::metadata(&path) {
+        let _ = std::fs::set_permissions(&tmp, metadata.permissions());
+    }
+    if let Err(e) = std::fs::rename(&tmp, &path) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// System Commands (/proc)
+// ============================================================================
+
+/// Uptime and load for the system card
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemOverview {
+    /// Seconds since boot
+    pub uptime_secs: u64,
+    /// 1, 5 and 15 minute load averages
+    pub load_average: [f32; 3],
+}
+
+/// Get uptime and load averages
+#[tauri::command]
+pub async fn get_system_overview() -> Result<SystemOverview, String> {
+    get_system_overview_internal().await.map_err(|e| e.to_string())
+}
+
+async fn get_system_overview_internal() -> CCResult<SystemOverview> {
+    let (uptime, loadavg) = tokio::try_join!(
+        tokio::fs::read_to_string("/proc/uptime"),
+        tokio::fs::read_to_string("/proc/loadavg"),
+    )?;
+    
+    Ok(SystemOverview {
+        uptime_secs: parse_uptime(&uptime)?,
+        load_average: parse_loadavg(&loadavg)?,
+    })
+}
+
+/// Parse whole seconds from `/proc/uptime` (`<uptime> <idle>`)
+fn parse_uptime(contents: &str) -> CCResult<u64> {
+    contents
+        .split_whitespace()
+        .next()
+        .and_then(|secs| secs.parse::<f64>().ok())
+        .map(|secs| secs as u64)
+        .ok_or_else(|| ControlCentreError::ParseError(format!("Unexpected /proc/uptime: {}", contents)))
+}
+
+/// Parse the three load averages from `/proc/loadavg`
+fn parse_loadavg(contents: &str) -> CCResult<[f32; 3]> {
+    let invalid = || ControlCentreError::ParseError(format!("Unexpected /proc/loadavg: {}", contents));
+    
+    let mut fields = contents.split_whitespace().map(|f| f.parse::<f32>().map_err(|_| invalid()));
+    let mut next = || fields.next().unwrap_or_else(|| Err(invalid()));
+    Ok([next()?, next()?, next()?])
+}
+
+// ============================================================================
+// Time Sync Commands (timedatectl)
+// ============================================================================
+
+/// Automatic clock synchronization for the date/time card
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeSyncStatus {
+    /// NTP sync is turned on
+    pub ntp_enabled: bool,
+    /// The clock is currently synchronized
+    pub synchronized: bool,
+}
+
+/// Get whether NTP sync is on and the clock synchronized
+#[tauri::command]
+pub async fn get_time_sync_status(state: State<'_, AppState>) -> Result<TimeSyncStatus, String> {
+    ensure_timedatectl(&state.capabilities()).map_err(|e| e.to_string())?;
+    get_time_sync_status_internal().await.map_err(|e| e.to_string())
+}
+
+/// Turn NTP sync on or off, returning the new status
+#[tauri::command]
+pub async fn set_ntp(state: State<'_, AppState>, enabled: bool) -> Result<TimeSyncStatus, String> {
+    ensure_timedatectl(&state.capabilities()).map_err(|e| e.to_string())?;
+    
+    let value = if enabled { "true" } else { "false" };
+    run_command_no_output("timedatectl", &["set-ntp", value])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    info!("NTP sync {}", if enabled { "enabled" } else { "disabled" });
+    get_time_sync_status_internal().await.map_err(|e| e.to_string())
+}
+
+fn ensure_timedatectl(capabilities: &Capabilities) -> CCResult<()> {
+    if capabilities.timedatectl {
+        Ok(())
+    } else {
+        Err(ControlCentreError::CommandNotFound("timedatectl".to_string()))
+    }
+}
+
+async fn get_time_sync_status_internal() -> CCResult<TimeSyncStatus> {
+    let output = run_command("timedatectl", &["show", "-p", "NTP", "-p", "NTPSynchronized"]).await?;
+    parse_time_sync_status(&output)
+}
+
+/// Parse the `NTP=` and `NTPSynchronized=` lines of `timedatectl show`
+fn parse_time_sync_status(output: &str) -> CCResult<TimeSyncStatus> {
+    let property = |name: &str| -> CCResult<bool> {
+        let value = output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix(name)?.strip_prefix('='))
+            .ok_or_else(|| {
+                ControlCentreError::ParseError(format!("timedatectl did not report {}", name))
+            })?;
+        
+        match value {
+            "yes" => Ok(true),
+            "no" => Ok(false),
+            _ => Err(ControlCentreError::ParseError(format!(
+                "Unexpected {} value: {}",
+                name,
+                value
+            ))),
+        }
+    };
+    
+    Ok(TimeSyncStatus {
+        ntp_enabled: property("NTP")?,
+        synchronized: property("NTPSynchronized")?,
+    })
+}
+
+// ============================================================================
+// Service Commands (systemd --user)
+// ============================================================================
+
+/// State of a systemd user service
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceStatus {
+    Active,
+    Inactive,
+    Failed,
+}
+
+/// Get the state of an allowlisted user service
+#[tauri::command]
+pub async fn get_service_status(
+    config: State<'_, ConfigStore>,
+    name: String,
+) -> Result<ServiceStatus, String> {
+    ensure_service_allowed(&config.get(), &name).map_err(|e| e.to_string())?;
+    get_service_status_internal(&name).await.map_err(|e| e.to_string())
+}
+
+/// Start or stop an allowlisted user service, returning its new state
+#[tauri::command]
+pub async fn set_service(
+    config: State<'_, ConfigStore>,
+    name: String,
+    enabled: bool,
+) -> Result<ServiceStatus, String> {
+    ensure_service_allowed(&config.get(), &name).map_err(|e| e.to_string())?;
+    
+    let action = if enabled { "start" } else { "stop" };
+    run_command_no_output("systemctl", &["--user", action, "--", &name])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    info!("User service {} {}", name, if enabled { "started" } else { "stopped" });
+    get_service_status_internal(&name).await.map_err(|e| e.to_string())
+}
+
+/// Refuse services that are not listed in the `services` config key
+fn ensure_service_allowed(config: &Config, name: &str) -> CCResult<()> {
+    if config.services.iter().any(|service| service == name) {
+        Ok(())
+    } else {
+        Err(ControlCentreError::PermissionDenied(format!(
+            "Service '{}' is not in the services allowlist",
+            name
+        )))
+    }
+}
+
+async fn get_service_status_internal(name: &str) -> CCResult<ServiceStatus> {
+    // is-active exits non-zero for anything but an active unit, so the
+    // state word on stdout is read regardless of the exit status
+    let args = ["--user", "is-active", "--", name];
+    let started = std::time::Instant::now();
+    let result = tool_command("systemctl")
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await;
+    record_command("systemctl", &args, started, &result);
+    let output = result?;
+    
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_service_status(&stdout).ok_or_else(|| {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        ControlCentreError::CommandFailed(format!(
+            "Unexpected systemctl is-active output for {}: {} {}",
+            name,
+            stdout.trim(),
+            stderr
+        ))
+    })
+}
+
+/// Parse the state word printed by `systemctl is-active`
+///
+/// Units that are starting or reloading count as active and units that
+/// are stopping as inactive, so a toggle never starts a service twice.
+fn parse_service_status(output: &str) -> Option<ServiceStatus> {
+    match output.lines().next()?.trim() {
+        "active" | "activating" | "reloading" | "refreshing" => Some(ServiceStatus::Active),
+        "inactive" | "deactivating" => Some(ServiceStatus::Inactive),
+        "failed" => Some(ServiceStatus::Failed),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Power Commands
+// ============================================================================
+
+/// Guard a power action against being triggered twice in quick succession
+fn begin_power_action(state: &AppState, action: &'static str) -> CCResult<()> {
+    if state.try_begin_power_action(action) {
+        Ok(())
+    } else {
+        warn!("Ignoring repeated {} request", action);
+        Err(ControlCentreError::SystemError(format!(
+            "{} already in progress",
+            action
+        )))
+    }
+}
+
+/// Suspend the system
+#[tauri::command]
+pub async fn suspend_system(state: State<'_, AppState>) -> Result<(), String> {
+    begin_power_action(&state, "suspend").map_err(|e| e.to_string())?;
+    
+    info!("Suspending system...");
+    tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    
+    run_command_no_output("loginctl", &["suspend"])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    Ok(())
+}
+
+/// Power actions that must be confirmed with a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerAction {
+    Suspend,
+    Reboot,
+    Shutdown,
+}
+
+impl PowerAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            PowerAction::Suspend => "suspend",
+            PowerAction::Reboot => "reboot",
+            PowerAction::Shutdown => "shutdown",
+        }
+    }
+    
+    fn command(self) -> (&'static str, &'static str) {
+        match self {
+            PowerAction::Suspend => ("loginctl", "suspend"),
+            PowerAction::Reboot => ("systemctl", "reboot"),
+            PowerAction::Shutdown => ("systemctl", "poweroff"),
+        }
+    }
+}
+
+/// Start a power action, returning the token `confirm_power_action` needs
+///
+/// The token is valid for about 10 seconds and can be used once.
+#[tauri::command]
+pub async fn request_power_action(
+    state: State<'_, AppState>,
+    action: PowerAction,
+) -> Result<String, String> {
+    let token = new_power_token();
+    state.issue_power_token(action.as_str(), token.clone());
+    
+    info!("{} requested, awaiting confirmation", action.as_str());
+    Ok(token)
+}
+
+/// Run a power action requested with `request_power_action`
+#[tauri::command]
+pub async fn confirm_power_action(
+    state: State<'_, AppState>,
+    action: PowerAction,
+    token: String,
+) -> Result<(), String> {
+    let name = action.as_str();
+    let rejected = |reason: String| {
+        warn!("Rejected {} confirmation: {}", name, reason);
+        Err(ControlCentreError::InvalidArgument(reason).to_string())
+    };
+    match state.take_power_token(name, &token) {
+        PowerTokenCheck::Valid => {}
+        PowerTokenCheck::Missing => return rejected(format!("No pending {} request", name)),
+        PowerTokenCheck::Expired => {
+            return rejected(format!("Confirmation for {} expired, request it again", name))
+        }
+        PowerTokenCheck::Mismatch => {
+            return rejected(format!("Invalid confirmation token for {}", name))
+        }
+    }
+    
+    begin_power_action(&state, name).map_err(|e| e.to_string())?;
+    info!("Running confirmed {}", name);
+    
+    let (cmd, arg) = action.command();
+    run_command_no_output(cmd, &[arg]).await.map_err(|e| e.to_string())
+}
+
+/// Unpredictable token for a power action confirmation
+fn new_power_token() -> String {
+    use std::hash::{BuildHasher, Hasher};
+    
+    // RandomState is seeded randomly per instance; mix in the time as well
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    if let Ok(elapsed) = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+    format!("{:016x}", hasher.finish())
+}
+
+/// A lock held by a program to delay or block sleep, idle or shutdown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inhibitor {
+    pub who: String,
+    pub why: String,
+    /// `block` or `delay`
+    pub mode: String,
+    /// Colon-separated operations, e.g. `sleep:idle`
+    pub what: String,
+}
+
+/// List inhibitor locks, by default only those affecting sleep or idle
+#[tauri::command]
+pub async fn get_inhibitors(all: Option<bool>) -> Result<Vec<Inhibitor>, String> {
+    let output = run_command("systemd-inhibit", &["--list"])
+        .await
+        .map_err(|e| e.to_string())?;
+    
+    let all = all.unwrap_or(false);
+    Ok(parse_inhibitors(&output)
+        .into_iter()
+        .filter(|inhibitor| all || inhibitor.what.split(':').any(|w| w == "sleep" || w == "idle"))
+        .collect())
+}
+
+/// Parse the table printed by `systemd-inhibit --list`
+///
+/// WHO and WHY may contain spaces, so rows are cut at the header's column
+/// offsets rather than split on whitespace.
+fn parse_inhibitors(output: &str) -> Vec<Inhibitor> {
+    let mut lines = output.lines();
+    let Some(header) = lines.find(|line| line.trim_start().starts_with("WHO")) else {
+        return Vec::new();
+    };
+    
+    let header: Vec<char> = header.chars().collect();
+    let columns: Vec<(String, usize)> = (0..header.len())
+        .filter(|&i| !header[i].is_whitespace() && (i == 0 || header[i - 1].is_whitespace()))
+        .map(|start| {
+            let name: String = header[start..].iter().take_while(|c| !c.is_whitespace()).collect();
+            (name, start)
+        })
+        .collect();
+    
+    lines
+        .take_while(|line| !line.trim().is_empty())
+        .map(|line| {
+            let row: Vec<char> = line.chars().collect();
+            let field = |name: &str| {
+                let index = columns.iter().position(|(column, _)| column == name)?;
+                let start = columns[index].1.min(row.len());
+                let end = columns.get(index + 1).map_or(row.len(), |(_, next)| (*next).min(row.len()));
+                Some(row[start..end].iter().collect::<String>().trim().to_string())
+            };
+            Inhibitor {
+                who: field("WHO").unwrap_or_default(),
+                why: field("WHY").unwrap_or_default(),
+                mode: field("MODE").unwrap_or_default(),
+                what: field("WHAT").unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+// ============================================================================
+// Reset Command
+// ============================================================================
+
+/// Volume restored by `reset_defaults` when the config has none
+const FALLBACK_DEFAULT_VOLUME: u8 = 50;
+
+/// Brightness restored by `reset_defaults` when the config has none
+const FALLBACK_DEFAULT_BRIGHTNESS: u8 = 70;
+
+/// Outcome of each setting touched by `reset_defaults`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResetResults {
+    pub volume: DomainState<u8>,
+    pub muted: DomainState<bool>,
+    pub brightness: DomainState<u8>,
+    pub night_light_enabled: DomainState<bool>,
+}
+
+/// Restore volume, mute, brightness and Night Light to their defaults
+///
+/// All four are applied concurrently and each reports its own result, so
+/// a missing backend for one does not stop the others. Network and power
+/// settings are deliberately left alone.
+#[tauri::command]
+pub async fn reset_defaults(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+) -> Result<ResetResults, String> {
+    let config = config.get();
+    let volume = config.default_volume.unwrap_or(FALLBACK_DEFAULT_VOLUME).min(100);
+    let brightness = config
+        .default_brightness
+        .unwrap_or(FALLBACK_DEFAULT_BRIGHTNESS)
+        .clamp(BRIGHTNESS_FLOOR, 100);
+    
+    interrupt_volume_fade(&state).await;
+    let (volume, muted, brightness, night_light_enabled) = tokio::join!(
+        async {
+            let volume = limit_default_sink_volume(&config.sink_limits, volume).await?;
+            set_volume_internal(volume).await?;
+            Ok(volume)
+        },
+        async {
+            run_command_no_output("pactl", &["set-sink-mute", "@DEFAULT_SINK@", "0"]).await?;
+            Ok(false)
+        },
+        async {
+            set_brightness_internal(brightness).await?;
+            auto_brightness::note_manual_change();
+            Ok(brightness)
+        },
+        async {
+            let backend = resolve_night_light_backend(&state).await.ok_or_else(|| {
+                ControlCentreError::CommandNotFound("No Night Light backend available".to_string())
+            })?;
+            set_night_light_internal(backend, false, None).await?;
+            stop_stray_night_light_processes(backend).await;
+            Ok(false)
+        },
+    );
+    
+    if let Ok(volume) = volume {
+        state.set_cached_volume(volume);
+    }
+    if let Ok(muted) = muted {
+        state.set_cached_muted(muted);
+    }
+    if let Ok(brightness) = brightness {
+        state.set_cached_brightness(brightness);
+    }
+    if let Ok(enabled) = night_light_enabled {
+        state.set_cached_night_light(enabled);
+    }
+    
+    info!("Reset controls to defaults");
+    Ok(ResetResults {
+        volume: volume.into(),
+        muted: muted.into(),
+        brightness: brightness.into(),
+        night_light_enabled: night_light_enabled.into(),
+    })
+}
+
+// ============================================================================
+// Aggregate State Command
+// ============================================================================
+
+/// Delay before the startup warm-up, leaving the first paint uncontested
+const WARM_UP_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Get all system states at once
+///
+/// Each domain reports whether its backend is available; unavailable
+/// domains carry the error instead of placeholder values.
+#[tauri::command]
+pub async fn get_all_states(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+) -> Result<AllStates, String> {
+    state.invalidate_all();
+    
+    let all = get_all_states_internal(&state, &config.get()).await;
+    for e in all.errors() {
+        warn!("Backend query failed: {}", e);
+    }
+    
+    cache_all_states(&state, &all);
+    Ok(all)
+}
+
+/// Get only the cheap audio and brightness states
+///
+/// Meant for a quick first paint; the frontend follows up with
+/// `get_all_states` for the slower network and Night Light backends.
+#[tauri::command]
+pub async fn get_fast_states(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+) -> Result<FastStates, String> {
+    let config = config.get();
+    let cards = resolve_enabled_cards(&config);
+    let enabled = |card| cards.contains(&card);
+    
+    let (volume, brightness) = tokio::join!(
+        query_domain(enabled(Card::Audio), get_volume_state_internal()),
+        query_domain(
+            enabled(Card::Brightness),
+            get_brightness_state_internal(&state, &config),
+        ),
+    );
+    
+    if let Some(volume) = &volume.value {
+        state.set_cached_volume(volume.volume);
+        state.set_cached_muted(volume.muted);
+    }
+    if let Some(brightness) = &brightness.value {
+        state.set_cached_brightness(brightness.brightness);
+    }
+    Ok(FastStates { volume, brightness })
+}
+
+/// Query all enabled backends once in the background after startup
+///
+/// Fills the per-domain caches and wakes the backends so the panel's first
+/// queries do not wait on cold daemons. Disabled cards and missing tools
+/// are skipped like in any other aggregate query.
+pub(crate) fn warm_up_caches(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(WARM_UP_DELAY).await;
+        
+        let state = app.state::<AppState>();
+        let config = app.state::<ConfigStore>().get();
+        let all = get_all_states_internal(&state, &config).await;
+        
+        cache_all_states(&state, &all);
+        debug!("Startup warm-up finished");
+        
+        if resolve_enabled_cards(&config).contains(&Card::Brightness) && command_exists("ddcutil") {
+            prime_external_brightness(&state).await;
+        }
+    });
+}
+
+/// Refresh the per-domain caches from an aggregate query
+fn cache_all_states(state: &AppState, all: &AllStates) {
+    if let Some(volume) = &all.volume.value {
+        state.set_cached_volume(volume.volume);
+        state.set_cached_muted(volume.muted);
+    }
+    if let Some(brightness) = &all.brightness.value {
+        state.set_cached_brightness(brightness.brightness);
+    }
+    if let Some(network) = &all.network.value {
+        state.set_cached_wifi(network.wifi_enabled);
+        state.set_cached_bluetooth(network.bluetooth_enabled);
+    }
+    if let Some(display) = &all.display.value {
+        state.set_cached_night_light(display.night_light_enabled);
+    }
+}
+
+/// Query the backends of all enabled cards concurrently
+pub(crate) async fn get_all_states_internal(state: &AppState, config: &Config) -> AllStates {
+    let cards = resolve_enabled_cards(config);
+    let enabled = |card| cards.contains(&card);
+    let schedule = config.night_light_schedule.as_ref();
+    
+    let (volume, mic, brightness, network, display) = tokio::join!(
+        query_domain(enabled(Card::Audio), get_volume_state_internal()),
+        query_domain(enabled(Card::Audio), get_mic_state_internal()),
+        query_domain(
+            enabled(Card::Brightness),
+            get_brightness_state_internal(state, config),
+        ),
+        query_domain(
+            enabled(Card::Network) || enabled(Card::Bluetooth),
+            get_network_state_internal(state),
+        ),
+        query_domain(enabled(Card::NightLight), async {
+            let night_light_enabled = get_night_light_internal(state).await?;
+            let conflict = night_light_conflict(state).await;
+            Ok(display_state(state, night_light_enabled, schedule, conflict))
+        }),
+    );
+    
+    AllStates {
+        volume,
+        mic,
+        brightness,
+        network,
+        display,
+    }
+}
+
+async fn get_volume_state_internal() -> CCResult<VolumeState> {
+    let (sink, volume, muted) = tokio::try_join!(
+        run_command("pactl", &["get-default-sink"]),
+        get_volume_internal(),
+        get_mute_status_internal(),
+    )?;
+    ensure_real_sink(&sink)?;
+    Ok(VolumeState { volume, muted })
+}
+
+async fn get_brightness_state_internal(
+    state: &AppState,
+    config: &Config,
+) -> CCResult<BrightnessState> {
+    let (read, max_brightness) =
+        tokio::try_join!(get_brightness_internal(), get_max_brightness_internal())?;
+    Ok(BrightnessState {
+        brightness: settle_brightness(state, config, read),
+        max_brightness,
+    })
+}
+
+/// Run a domain query only if its card is enabled
+async fn query_domain<T>(
+    enabled: bool,
+    query: impl std::future::Future<Output = CCResult<T>>,
+) -> DomainState<T> {
+    if enabled {
+        query.await.into()
+    } else {
+        DomainState::disabled()
+    }
+}
+
+/// Drop cached readings so the next read hits the backend
+///
+/// Clears every domain when `domain` is `None`, otherwise just the named
+/// one (`volume`, `brightness`, `network` or `display`). Returns the
+/// domains that were cleared.
+#[tauri::command]
+pub async fn invalidate_cache(
+    state: State<'_, AppState>,
+    domain: Option<String>,
+) -> Result<Vec<CacheDomain>, String> {
+    let domains = match domain {
+        None => CacheDomain::ALL.to_vec(),
+        Some(name) => vec![CacheDomain::from_name(&name).ok_or_else(|| {
+            ControlCentreError::InvalidArgument(format!("Unknown cache domain: {}", name))
+                .to_string()
+        })?],
+    };
+    
+    for domain in &domains {
+        state.invalidate(*domain);
+    }
+    debug!("Invalidated caches: {:?}", domains);
+    Ok(domains)
+}
+
+// ============================================================================
+// Polling Commands
+// ============================================================================
+
+/// Shortest accepted polling interval
+const MIN_POLLING_INTERVAL_MS: u64 = 250;
+
+/// Push `states-updated` events with the aggregate state every `interval_ms`
+///
+/// Polling pauses while the window is hidden and resumes when it is shown.
+#[tauri::command]
+pub async fn start_polling(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    interval_ms: u64,
+) -> Result<(), String> {
+    let interval_ms = interval_ms.max(MIN_POLLING_INTERVAL_MS);
+    info!("Polling aggregate state every {}ms", interval_ms);
+    
+    state.set_polling_interval(Some(interval_ms));
+    // A preloaded webview starts polling while hidden; `show_window` resumes it
+    if crate::WINDOW_VISIBLE.load(Ordering::SeqCst) {
+        resume_polling(&app);
+    }
+    Ok(())
+}
+
+/// Stop pushing state updates
+#[tauri::command]
+pub async fn stop_polling(state: State<'_, AppState>) -> Result<(), String> {
+    state.set_polling_interval(None);
+    pause_polling(&state);
+    info!("Polling stopped");
+    Ok(())
+}
+
+/// (Re)start the polling task if polling has been requested
+pub(crate) fn resume_polling(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Some(interval_ms) = state.polling_interval() else {
+        return;
+    };
+    
+    let app = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_millis(interval_ms)).await;
+            
+            let state = app.state::<AppState>();
+            let config = app.state::<ConfigStore>().get();
+            state.invalidate_all();
+            let all = get_all_states_internal(&state, &config).await;
+            cache_all_states(&state, &all);
+            
+            if let Err(e) = app.emit("states-updated", &all) {
+                warn!("Failed to emit states-updated: {}", e);
+            }
+        }
+    });
+    
+    state.replace_polling_task(Some(task));
+}
+
+/// Cancel the polling task, keeping the interval for the next resume
+pub(crate) fn pause_polling(state: &AppState) {
+    state.replace_polling_task(None);
+}
+
+// ============================================================================
+// Card Commands
+// ============================================================================
+
+/// Get the cards the panel should render
+#[tauri::command]
+pub async fn get_enabled_cards(config: State<'_, ConfigStore>) -> Result<Vec<Card>, String> {
+    Ok(resolve_enabled_cards(&config.get()))
+}
+
+/// Configured cards, or every card whose backend tool is installed
+pub(crate) fn resolve_enabled_cards(config: &Config) -> Vec<Card> {
+    if let Some(cards) = &config.enabled_cards {
+        return cards.clone();
+    }
+    
+    Card::ALL
+        .into_iter()
+        .filter(|card| card_backend_installed(*card))
+        .collect()
+}
+
+fn card_backend_installed(card: Card) -> bool {
+    card_backend_tools(card).iter().any(|tool| command_exists(tool))
+}
+
+/// Tools that can back a card; any one of them is enough
+fn card_backend_tools(card: Card) -> &'static [&'static str] {
+    match card {
+        Card::Audio => &["pactl"],
+        Card::Brightness => &["brightnessctl"],
+        Card::Network => &["nmcli", "rfkill"],
+        Card::Bluetooth => &["bluetoothctl", "rfkill"],
+        Card::NightLight => &["gsettings", "gammastep", "wlsunset", "wl-gammarelay-rs"],
+        Card::Power => &["loginctl"],
+        Card::Media => &["playerctl"],
+    }
+}
+
+/// Which card backends are installed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendAvailability {
+    pub audio: bool,
+    pub brightness: bool,
+    pub network: bool,
+    pub bluetooth: bool,
+    pub night_light: bool,
+    pub power: bool,
+    pub media: bool,
+}
+
+impl BackendAvailability {
+    fn detect() -> Self {
+        Self {
+            audio: card_backend_installed(Card::Audio),
+            brightness: card_backend_installed(Card::Brightness),
+            network: card_backend_installed(Card::Network),
+            bluetooth: card_backend_installed(Card::Bluetooth),
+            night_light: card_backend_installed(Card::NightLight),
+            power: card_backend_installed(Card::Power),
+            media: card_backend_installed(Card::Media),
+        }
+    }
+}
+
+/// Everything the frontend needs to decide what to render
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub compositor: Compositor,
+    pub backends: BackendAvailability,
+    /// Niri's IPC socket is reachable, needed for display and input settings
+    pub niri_ipc: bool,
+    /// A system battery (not a peripheral one) is present
+    pub battery: bool,
+    /// A keyboard backlight LED is exposed under `/sys/class/leds`
+    pub kbd_backlight: bool,
+    pub has_touchpad: bool,
+    /// `timedatectl` is installed, needed for the time sync controls
+    pub timedatectl: bool,
+}
+
+impl Capabilities {
+    /// Probe the system; cheap and synchronous, run once for `AppState`
+    pub fn detect(compositor: Compositor) -> Self {
+        let niri_ipc = compositor == Compositor::Niri
+            && std::env::var_os("NIRI_SOCKET")
+                .is_some_and(|socket| std::path::Path::new(&socket).exists());
+        
+        Self {
+            compositor,
+            backends: BackendAvailability::detect(),
+            niri_ipc,
+            battery: !battery_monitor::find_batteries().is_empty(),
+            kbd_backlight: has_kbd_backlight(),
+            has_touchpad: pointer_device_present(PointerDevice::Touchpad),
+            timedatectl: command_exists("timedatectl"),
+        }
+    }
+}
+
+/// Get the capabilities detected at startup
+#[tauri::command]
+pub async fn get_capabilities(state: State<'_, AppState>) -> Result<Capabilities, String> {
+    Ok(state.capabilities())
+}
+
+fn has_kbd_backlight() -> bool {
+    std::fs::read_dir("/sys/class/leds")
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.file_name().to_string_lossy().contains("kbd_backlight"))
+        })
+        .unwrap_or(false)
+}
+
+// ============================================================================
+// Update Commands (curl)
+// ============================================================================
+
+/// Longest an update check may take, in seconds
+const UPDATE_CHECK_TIMEOUT_SECS: &str = "10";
+
+/// Characters of an unexpected update response quoted in the error
+const UPDATE_RESPONSE_EXCERPT: usize = 120;
+
+/// Running and latest released version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpdateInfo {
+    pub current: String,
+    pub latest: String,
+    pub update_available: bool,
+}
+
+/// Ask the configured release endpoint for the latest version
+///
+/// Opt-in: without `update_url` in the config no request is made. The
+/// endpoint may return GitHub-style release JSON (`tag_name`), an object
+/// with a `version` field or the bare version as text.
+#[tauri::command]
+pub async fn check_for_update(config: State<'_, ConfigStore>) -> Result<UpdateInfo, String> {
+    let url = config.get().update_url.ok_or_else(|| {
+        ControlCentreError::SystemError(
+            "Update checks are off; set update_url in the config to enable them".to_string(),
+        )
+        .to_string()
+    })?;
+    check_for_update_internal(&url).await.map_err(|e| e.to_string())
+}
+
+async fn check_for_update_internal(url: &str) -> CCResult<UpdateInfo> {
+    if !url.starts_with("https://") {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "update_url must be an https:// URL, got {}",
+            url
+        )));
+    }
+    
+    // Redirects are followed, but never off https
+    let body = run_command(
+        "curl",
+        &[
+            "-fsSL",
+            "--proto",
+            "=https",
+            "--proto-redir",
+            "=https",
+            "--max-time",
+            UPDATE_CHECK_TIMEOUT_SECS,
+            url,
+        ],
+    )
+    .await?;
+    let latest = parse_latest_version(&body)?;
+    let current = env!("CARGO_PKG_VERSION");
+    
+    Ok(UpdateInfo {
+        update_available: is_newer_version(&latest, current),
+        current: current.to_string(),
+        latest,
+    })
+}
+
+/// Version from a release endpoint's response, without a leading `v`
+fn parse_latest_version(body: &str) -> CCResult<String> {
+    let version = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(release)) => ["tag_name", "version"]
+            .iter()
+            .find_map(|key| release.get(*key)?.as_str())
+            .map(str::to_string),
+        Ok(serde_json::Value::String(version)) => Some(version),
+        _ => body.lines().map(str::trim).find(|line| !line.is_empty()).map(str::to_string),
+    };
+    
+    version
+        .map(|v| v.trim().trim_start_matches('v').to_string())
+        .filter(|v| version_parts(v).is_some())
+        .ok_or_else(|| {
+            ControlCentreError::ParseError(format!(
+                "No version in update response: {}",
+                response_excerpt(body)
+            ))
+        })
+}
+
+/// Start of a response body, on one line, for error messages
+fn response_excerpt(body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if body.chars().count() <= UPDATE_RESPONSE_EXCERPT {
+        return body;
+    }
+    let excerpt: String = body.chars().take(UPDATE_RESPONSE_EXCERPT).collect();
+    format!("{}…", excerpt)
+}
+
+/// Numeric components of `1.2.3`, ignoring pre-release and build suffixes
+fn version_parts(version: &str) -> Option<Vec<u64>> {
+    version
+        .trim_start_matches('v')
+        .split(['-', '+'])
+        .next()?
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+/// Whether `latest` is a higher version than `current`
+fn is_newer_version(latest: &str, current: &str) -> bool {
+    let (Some(mut latest), Some(mut current)) = (version_parts(latest), version_parts(current))
+    else {
+        return false;
+    };
+    let len = latest.len().max(current.len());
+    latest.resize(len, 0);
+    current.resize(len, 0);
+    latest > current
+}
+
+// ============================================================================
+// Diagnostics Commands
+// ============================================================================
+
+/// Get the most recent backend command invocations, oldest first
+///
+/// Secrets in arguments are redacted and output is truncated.
+#[tauri::command]
+pub async fn get_command_log(state: State<'_, AppState>) -> Result<Vec<CommandLogEntry>, String> {
+    Ok(state.command_log().recent())
+}
+
+/// Get the latency of each backend tool run since startup
+///
+/// Usually shows which backend makes `get_all_states` slow (often
+/// bluetoothctl).
+#[tauri::command]
+pub async fn get_backend_timings(state: State<'_, AppState>) -> Result<Vec<BackendTiming>, String> {
+    Ok(state.command_log().timings())
+}
+
+/// Optional tools probed for the diagnostics report besides the card backends
+const DIAGNOSTIC_EXTRA_TOOLS: [&str; 4] = ["niri", "notify-send", "openrgb", "busctl"];
+
+/// Placeholder for values left out of a diagnostics report
+const REDACTED: &str = "<redacted>";
+
+/// Everything useful for a bug report, as written by `export_diagnostics`
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub version: &'static str,
+    pub compositor: Compositor,
+    pub radio_backends: RadioBackends,
+    pub night_light_backend: Option<NightLightBackend>,
+    /// Whether each probed tool was found
+    pub tools: BTreeMap<&'static str, bool>,
+    pub enabled_cards: Vec<Card>,
+    pub states: AllStates,
+    /// Most recent log lines, oldest first
+    pub log: Vec<String>,
+}
+
+/// Write a diagnostics report to a private file and return its path
+///
+/// Network and Bluetooth device names are redacted, in the states and the
+/// log, unless `include_sensitive` is set. The file is only readable by the
+/// user and goes to `$XDG_RUNTIME_DIR` when it is set.
+#[tauri::command]
+pub async fn export_diagnostics(
+    state: State<'_, AppState>,
+    config: State<'_, ConfigStore>,
+    include_sensitive: Option<bool>,
+) -> Result<String, String> {
+    let config = config.get();
+    
+    let tools = Card::ALL
+        .into_iter()
+        .flat_map(card_backend_tools)
+        .chain(DIAGNOSTIC_EXTRA_TOOLS.iter())
+        .map(|tool| (*tool, command_exists(tool)))
+        .collect();
+    
+    let mut report = DiagnosticsReport {
+        version: env!("CARGO_PKG_VERSION"),
+        compositor: state.compositor(),
+        radio_backends: state.radio_backends(),
+        night_light_backend: state.get_night_light_backend(),
+        tools,
+        enabled_cards: resolve_enabled_cards(&config),
+        states: get_all_states_internal(&state, &config).await,
+        log: crate::log_buffer::recent(),
+    };
+    if !include_sensitive.unwrap_or(false) {
+        redact_diagnostics(&mut report, &known_radio_names().await);
+    }
+    
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|e| format!("Failed to serialize diagnostics: {}", e))?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(std::env::temp_dir);
+    let path = dir.join(format!("control-centre-diagnostics-{}.json", timestamp));
+    write_private(&path, &json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    
+    info!("Diagnostics written to {}", path.display());
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Create `path` readable by the user only, refusing to follow an existing file
+fn write_private(path: &std::path::Path, contents: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents.as_bytes())
+}
+
+/// Tools whose arguments and errors name networks and devices
+const RADIO_TOOLS: [&str; 2] = ["nmcli", "bluetoothctl"];
+
+/// Names shorter than this are left alone, as they would mangle unrelated text
+const MIN_REDACTED_NAME_LEN: usize = 3;
+
+/// Saved connection names and Bluetooth device names and addresses
+///
+/// Backends that are missing or fail just contribute nothing.
+async fn known_radio_names() -> Vec<String> {
+    let (connections, devices) = tokio::join!(
+        run_command("nmcli", &["-t", "-f", "NAME,UUID", "connection", "show"]),
+        run_command("bluetoothctl", &["devices"]),
+    );
+    
+    let mut names: Vec<String> = connections
+        .map(|output| output.lines().map(|line| split_nmcli_terse(line).0).collect())
+        .unwrap_or_default();
+    let devices = devices.map(|output| parse_bluetooth_device_names(&output));
+    for (address, name) in devices.unwrap_or_default() {
+        names.push(address);
+        names.push(name);
+    }
+    names.extend(load_bluetooth_history().into_keys());
+    names
+}
+
+/// Strip network and device names from a report
+///
+/// Tool stdout is dropped entirely and radio tool lines are cut after the
+/// tool name. Every name in `names` and the current SSID are then replaced
+/// in the remaining lines, so the app's own messages about earlier
+/// connections are covered even when WiFi is off at export time.
+fn redact_diagnostics(report: &mut DiagnosticsReport, names: &[String]) {
+    report.log.retain(|line| !line.contains("Command output: "));
+    for line in &mut report.log {
+        let found = RADIO_TOOLS
+            .iter()
+            .find_map(|tool| line.find(&format!("{} ", tool)).map(|start| start + tool.len()));
+        if let Some(end) = found {
+            line.truncate(end);
+            line.push(' ');
+            line.push_str(REDACTED);
+        }
+    }
+    
+    let mut names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let ssid = report
+        .states
+        .network
+        .value
+        .as_mut()
+        .and_then(|network| network.wifi_ssid.as_mut())
+        .map(|ssid| std::mem::replace(ssid, REDACTED.to_string()));
+    names.extend(ssid.as_deref());
+    
+    // Longest first, so a name containing another is replaced whole
+    names.retain(|name| name.trim().len() >= MIN_REDACTED_NAME_LEN);
+    names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+    names.dedup();
+    for line in &mut report.log {
+        for name in &names {
+            if line.contains(name) {
+                *line = line.replace(name, REDACTED);
+            }
+        }
+    }
+}
+
+// Internal helpers
+async fn get_volume_internal() -> CCResult<u8> {
+    let output = run_command("pactl", &["get-sink-volume", "@DEFAULT_SINK@"]).await?;
+    let re = Regex::new(r"(\d+)%").map_err(|e| ControlCentreError::ParseError(e.to_string()))?;
+    re.captures(&output)
+        .and_then(|cap| cap.get(1))
+        .and_then(|m| m.as_str().parse::<u8>().ok())
+        .ok_or_else(|| ControlCentreError::ParseError(format!("No volume in pactl output: {}", output)))
+}
+
+async fn set_volume_internal(value: u8) -> CCResult<()> {
+    run_command_no_output("pactl", &["set-sink-volume", "@DEFAULT_SINK@", &format!("{}%", value)]).await
+}
+
+pub(crate) async fn set_brightness_internal(value: u8) -> CCResult<()> {
+    run_command_no_output("brightnessctl", &["set", &format!("{}%", value)]).await
+}
+
+async fn get_brightness_internal() -> CCResult<u8> {
+    let output = run_command("brightnessctl", &["-m", "info"]).await?;
+    parse_brightnessctl_machine(&output)
+        .ok_or_else(|| ControlCentreError::ParseError(format!("No brightness in brightnessctl output: {}", output)))
+}
+
+/// Brightness percentage from `brightnessctl -m info` (`device,class,current,percent,max`)
+///
+/// Computed from the raw current and max values, since the preformatted
+/// percentage has varied between versions; that field is only a fallback.
+fn parse_brightnessctl_machine(output: &str) -> Option<u8> {
+    let line = output.lines().find(|line| !line.trim().is_empty())?;
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let raw = |index: usize| fields.get(index)?.parse::<u64>().ok();
+    
+    if let (Some(current), Some(max)) = (raw(2), raw(4)) {
+        if let Some(percent) = (current * 100 + max / 2).checked_div(max) {
+            return Some(percent.min(100) as u8);
+        }
+    }
+    
+    fields
+        .iter()
+        .find_map(|field| field.strip_suffix('%')?.parse::<u8>().ok())
+        .filter(|percent| *percent <= 100)
+}
+
+async fn get_max_brightness_internal() -> CCResult<u32> {
+    let output = run_command("brightnessctl", &["max"]).await?;
+    output.trim().parse::<u32>()
+        .map_err(|e| ControlCentreError::ParseError(e.to_string()))
+}
+
+async fn get_network_state_internal(state: &AppState) -> CCResult<NetworkState> {
+    let backends = state.radio_backends();
+    let wifi_enabled = get_wifi_enabled_internal(backends.wifi).await?;
+    
+    let mut wifi_connected = false;
     let mut wifi_ssid = None;
     
-    if wifi_enabled {
+    // rfkill only knows about the switch, not the connection
+    if wifi_enabled && backends.wifi == RadioBackend::NetworkManager {
         if let Ok(conn_output) = run_command("nmcli", &["-t", "-f", "ACTIVE,SSID", "device", "wifi"]).await {
             for line in conn_output.lines() {
                 if line.starts_with("yes:") {
@@ -517,33 +7208,153 @@ async fn get_network_state_internal() -> NetworkState {
         }
     }
     
-    let bluetooth_enabled = get_bluetooth_status_internal().await.unwrap_or(false);
+    let bluetooth_enabled = get_bluetooth_status_internal(backends.bluetooth)
+        .await
+        .unwrap_or(false);
+    
+    // Device details need BlueZ; only look them up when the radio is on
+    let (bluetooth_connected, bluetooth_battery) =
+        if bluetooth_enabled && backends.bluetooth == RadioBackend::Bluetoothctl {
+            get_bluetooth_connection_internal(state).await
+        } else {
+            (false, None)
+        };
     
-    NetworkState {
+    Ok(NetworkState {
         wifi_enabled,
         wifi_connected,
         wifi_ssid,
         bluetooth_enabled,
-        bluetooth_connected: false,
+        bluetooth_connected,
+        bluetooth_battery,
+    })
+}
+
+async fn get_night_light_internal(state: &AppState) -> CCResult<bool> {
+    let Some(backend) = resolve_night_light_backend(state).await else {
+        return Ok(false);
+    };
+    
+    match backend {
+        NightLightBackend::Gnome => {
+            let output = run_command(
+                "gsettings",
+                &["get", "org.gnome.settings-daemon.plugins.color", "night-light-enabled"],
+            )
+            .await?;
+            Ok(output.trim() == "true")
+        }
+        NightLightBackend::Gammastep => Ok(run_command("pgrep", &["-x", "gammastep"]).await.is_ok()),
+        NightLightBackend::Wlsunset => {
+            Ok(global_wlsunset_running(&state.night_light_outputs()).await)
+        }
+        NightLightBackend::WlrGamma => {
+            let Ok(output) = run_command(
+                "busctl",
+                &["--user", "get-property", GAMMARELAY_SERVICE, "/", GAMMARELAY_INTERFACE, "Temperature"],
+            )
+            .await
+            else {
+                return Ok(false);
+            };
+            Ok(parse_busctl_uint(&output).is_some_and(|t| t < NEUTRAL_TEMPERATURE))
+        }
     }
 }
 
-async fn get_night_light_internal() -> CCResult<bool> {
-    let result = run_command(
-        "gsettings",
-        &["get", "org.gnome.settings-daemon.plugins.color", "night-light-enabled"],
-    ).await;
+/// Parse a `busctl get-property` unsigned reply such as `q 4500`
+fn parse_busctl_uint(output: &str) -> Option<u32> {
+    output.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Attempts to find the panel among Niri's windows right after it is shown
+const FLOATING_LOOKUP_ATTEMPTS: u32 = 3;
+
+/// Delay between lookups while Niri has not mapped the window yet
+const FLOATING_LOOKUP_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Make the panel float if Niri opened it tiled
+///
+/// Covers setups without the window rule from `niri/window-rules.kdl`.
+/// Returns whether the window had to be moved to the floating layout.
+#[tauri::command]
+pub async fn ensure_floating(state: State<'_, AppState>) -> Result<bool, String> {
+    ensure_floating_internal(&state).await.map_err(|e| e.to_string())
+}
+
+pub(crate) async fn ensure_floating_internal(state: &AppState) -> CCResult<bool> {
+    if !state.capabilities().niri_ipc {
+        return Err(ControlCentreError::SystemError(
+            "Floating the panel requires Niri's IPC".to_string(),
+        ));
+    }
     
-    match result {
-        Ok(output) => Ok(output.trim() == "true"),
-        Err(_) => {
-            if let Ok(output) = run_command("pgrep", &["-x", "gammastep"]).await {
-                Ok(!output.is_empty())
-            } else {
-                Ok(false)
+    let pid = std::process::id();
+    for attempt in 1..=FLOATING_LOOKUP_ATTEMPTS {
+        let output = run_command("niri", &["msg", "--json", "windows"]).await?;
+        let windows = niri::parse_windows(&output)?;
+        
+        if let Some(window) = own_niri_window(&windows, pid) {
+            if window.is_floating {
+                return Ok(false);
             }
+            let id = window.id.to_string();
+            let args = ["msg", "action", "move-window-to-floating", "--id", &id];
+            run_command_no_output("niri", &args).await?;
+            info!("Panel was tiled, moved window {} to floating", id);
+            return Ok(true);
         }
+        
+        if attempt < FLOATING_LOOKUP_ATTEMPTS {
+            tokio::time::sleep(FLOATING_LOOKUP_DELAY).await;
+        }
+    }
+    
+    debug!("Panel window not found among Niri's windows");
+    Ok(false)
+}
+
+/// The panel's window, found by process id
+fn own_niri_window(windows: &[NiriWindow], pid: u32) -> Option<&NiriWindow> {
+    windows.iter().find(|window| window.pid == Some(pid))
+}
+
+async fn get_niri_outputs_internal(state: &AppState) -> CCResult<Vec<NiriOutput>> {
+    let compositor = state.compositor();
+    if compositor != Compositor::Niri {
+        return Err(ControlCentreError::SystemError(format!(
+            "Display control requires Niri, running under {:?}",
+            compositor
+        )));
+    }
+    
+    let output = run_command("niri", &["msg", "--json", "outputs"]).await?;
+    niri::parse_outputs(&output)
+}
+
+/// Look up an output by connector name
+fn find_output<'a>(outputs: &'a [NiriOutput], name: &str) -> CCResult<&'a NiriOutput> {
+    outputs
+        .iter()
+        .find(|o| o.name == name)
+        .ok_or_else(|| ControlCentreError::InvalidArgument(format!("Unknown output: {}", name)))
+}
+
+/// Check that an output exists and that turning it off leaves a display on
+fn validate_output_toggle(outputs: &[NiriOutput], name: &str, enabled: bool) -> CCResult<()> {
+    let target = find_output(outputs, name)?;
+    
+    let others_enabled = outputs
+        .iter()
+        .any(|o| o.name != target.name && o.is_enabled());
+    if !enabled && target.is_enabled() && !others_enabled {
+        return Err(ControlCentreError::InvalidArgument(format!(
+            "Refusing to disable {}: it is the only enabled output",
+            name
+        )));
     }
+    
+    Ok(())
 }
 
 #[cfg(test)]
@@ -558,6 +7369,74 @@ mod tests {
         assert!(validate_percentage(101).is_err());
     }
     
+    #[test]
+    fn test_normalize_percentage_reject() {
+        assert_eq!(normalize_percentage(42, ValueMode::Reject).unwrap(), 42);
+        assert!(normalize_percentage(150, ValueMode::Reject).is_err());
+        assert!(normalize_percentage(-5, ValueMode::Reject).is_err());
+        assert!(normalize_percentage(300, ValueMode::Reject).is_err());
+    }
+    
+    #[test]
+    fn test_normalize_percentage_clamp() {
+        assert_eq!(normalize_percentage(42, ValueMode::Clamp).unwrap(), 42);
+        assert_eq!(normalize_percentage(150, ValueMode::Clamp).unwrap(), 100);
+        assert_eq!(normalize_percentage(-5, ValueMode::Clamp).unwrap(), 0);
+    }
+    
+    #[test]
+    fn test_apply_delta_saturates() {
+        assert_eq!(apply_delta(50, 10, VOLUME_FLOOR), 60);
+        assert_eq!(apply_delta(95, 10, VOLUME_FLOOR), 100);
+        assert_eq!(apply_delta(5, -10, VOLUME_FLOOR), 0);
+        assert_eq!(apply_delta(5, -10, BRIGHTNESS_FLOOR), 1);
+        assert_eq!(apply_delta(250, 127, VOLUME_FLOOR), 100);
+    }
+    
+    #[test]
+    fn test_parse_mako_history() {
+        let output = "Notification 12: Build finished\n  App name: notify-send\n  Body: All targets built\n  Urgency: normal\nNotification 11: Hello\n  App name: firefox\n";
+        let history = parse_mako_history(output);
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].summary, "Build finished");
+        assert_eq!(history[0].app, "notify-send");
+        assert_eq!(history[0].body, "All targets built");
+        assert_eq!(history[1].app, "firefox");
+        assert!(history[1].body.is_empty());
+        assert!(parse_mako_history("").is_empty());
+    }
+    
+    #[test]
+    fn test_parse_swaync_history() {
+        let output = r#"[{"app_name": "Slack", "summary": "New message", "body": "hi", "time": 1700000000}]"#;
+        let history = parse_swaync_history(output);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].app, "Slack");
+        assert_eq!(history[0].time.as_deref(), Some("1700000000"));
+        assert!(parse_swaync_history("not json").is_empty());
+    }
+    
+    #[test]
+    fn test_parse_busctl_uint() {
+        assert_eq!(parse_busctl_uint("q 4500"), Some(4500));
+        assert_eq!(parse_busctl_uint("q"), None);
+        assert_eq!(parse_busctl_uint(""), None);
+    }
+    
+    #[test]
+    fn test_domain_state_from_result() {
+        let ok: DomainState<u8> = Ok(42).into();
+        assert!(ok.available);
+        assert_eq!(ok.value, Some(42));
+        assert!(ok.error.is_none());
+        
+        let err: DomainState<u8> =
+            Err(ControlCentreError::CommandNotFound("pactl".to_string())).into();
+        assert!(!err.available);
+        assert!(err.value.is_none());
+        assert!(err.error.unwrap().contains("pactl"));
+    }
+    
     #[test]
     fn test_volume_parsing() {
         let test_output = "Volume: front-left: 65536 / 100% / 0.00 dB";
@@ -577,4 +7456,1006 @@ mod tests {
             .unwrap_or(0);
         assert_eq!(brightness, 39);
     }
+    
+    #[test]
+    fn test_validate_output_toggle() {
+        let output = |name: &str, enabled: bool| NiriOutput {
+            name: name.to_string(),
+            current_mode: enabled.then_some(0),
+            ..Default::default()
+        };
+        
+        let single = vec![output("eDP-1", true), output("HDMI-A-1", false)];
+        assert!(validate_output_toggle(&single, "eDP-1", false).is_err());
+        assert!(validate_output_toggle(&single, "HDMI-A-1", true).is_ok());
+        assert!(validate_output_toggle(&single, "DP-3", true).is_err());
+        
+        let dual = vec![output("eDP-1", true), output("HDMI-A-1", true)];
+        assert!(validate_output_toggle(&dual, "HDMI-A-1", false).is_ok());
+    }
+    
+    #[test]
+    fn test_parse_pactl_description() {
+        let list = "Sink #47\n\tState: RUNNING\n\tName: alsa_output.pci-0000_00_1f.3.analog-stereo\n\tDescription: Built-in Audio Analog Stereo\n\nSink #52\n\tName: bluez_output.AA_BB\n\tDescription: WH-1000XM4\n";
+        assert_eq!(
+            parse_pactl_description(list, "bluez_output.AA_BB"),
+            Some("WH-1000XM4".to_string())
+        );
+        assert_eq!(
+            parse_pactl_description(list, "alsa_output.pci-0000_00_1f.3.analog-stereo"),
+            Some("Built-in Audio Analog Stereo".to_string())
+        );
+        assert_eq!(parse_pactl_description(list, "missing"), None);
+    }
+    
+    #[test]
+    fn test_parse_rfkill_json() {
+        let json = r#"{"rfkilldevices": [
+            {"id": 0, "type": "bluetooth", "device": "hci0", "soft": "blocked", "hard": "unblocked"},
+            {"id": 1, "type": "wlan", "device": "phy0", "soft": "unblocked", "hard": "unblocked"}
+        ]}"#;
+        let devices = parse_rfkill_json(json).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert!(devices[0].blocked);
+        assert_eq!(devices[1].kind, "wlan");
+        assert!(!devices[1].blocked);
+        
+        assert_eq!(parse_rfkill_json(r#"{"": []}"#).unwrap().len(), 0);
+        assert!(parse_rfkill_json("[]").is_err());
+    }
+    
+    #[test]
+    fn test_parse_rfkill_list() {
+        let output = "0: phy0: Wireless LAN\n\tSoft blocked: no\n\tHard blocked: no\n1: hci0: Bluetooth\n\tSoft blocked: yes\n\tHard blocked: no\n";
+        assert_eq!(
+            parse_rfkill_list(output),
+            vec![
+                RfkillDevice { kind: "wlan".to_string(), blocked: false },
+                RfkillDevice { kind: "bluetooth".to_string(), blocked: true },
+            ]
+        );
+    }
+    
+    #[test]
+    fn test_compositor_from_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        
+        assert_eq!(Compositor::from_env(env(&[("NIRI_SOCKET", "/run/niri.sock")])), Compositor::Niri);
+        assert_eq!(Compositor::from_env(env(&[("SWAYSOCK", "/run/sway.sock")])), Compositor::Sway);
+        assert_eq!(Compositor::from_env(env(&[("XDG_CURRENT_DESKTOP", "Hyprland")])), Compositor::Hyprland);
+        assert_eq!(Compositor::from_env(env(&[("XDG_CURRENT_DESKTOP", "GNOME")])), Compositor::Other);
+        assert_eq!(Compositor::from_env(env(&[])), Compositor::Other);
+    }
+    
+    #[test]
+    fn test_parse_bluetooth_devices_and_battery() {
+        let devices = "Device AA:BB:CC:DD:EE:FF WH-1000XM4\nDevice 11:22:33:44:55:66 Keyboard\n";
+        assert_eq!(
+            parse_bluetooth_devices(devices),
+            vec!["AA:BB:CC:DD:EE:FF".to_string(), "11:22:33:44:55:66".to_string()]
+        );
+        
+        let info = "Device AA:BB:CC:DD:EE:FF (public)\n\tName: WH-1000XM4\n\tConnected: yes\n\tBattery Percentage: 0x4b (75)\n";
+        assert_eq!(parse_bluetooth_battery(info), Some(75));
+        assert_eq!(parse_bluetooth_battery("\tBattery Percentage: 0x32"), Some(50));
+        assert_eq!(parse_bluetooth_battery("\tConnected: yes\n"), None);
+        
+        assert_eq!(
+            parse_bluetooth_device_names("Device AA:BB:CC:DD:EE:FF WH-1000XM4 Case\nDevice 11:22:33:44:55:66\n"),
+            vec![
+                ("AA:BB:CC:DD:EE:FF".to_string(), "WH-1000XM4 Case".to_string()),
+                ("11:22:33:44:55:66".to_string(), "11:22:33:44:55:66".to_string()),
+            ]
+        );
+    }
+    
+    #[test]
+    fn test_next_sink_skips_unavailable() {
+        let list = "Sink #1\n\tState: RUNNING\n\tName: speakers\n\tDescription: Speakers\n\tPorts:\n\t\tanalog-output-speaker: Speakers (type: Speaker, priority: 10000, availability unknown)\n\tActive Port: analog-output-speaker\n\
+Sink #2\n\tState: SUSPENDED\n\tName: hdmi\n\tDescription: HDMI\n\tPorts:\n\t\thdmi-output-0: HDMI / DisplayPort (type: HDMI, priority: 5900, not available)\n\tActive Port: hdmi-output-0\n\
+Sink #3\n\tState: SUSPENDED\n\tName: headset\n\tDescription: Headset\n";
+        let sinks = parse_pactl_devices(list);
+        assert_eq!(sinks.len(), 3);
+        assert!(!sinks[1].available);
+        
+        assert_eq!(next_sink(&sinks, "speakers").map(|s| s.name.as_str()), Some("headset"));
+        assert_eq!(next_sink(&sinks, "headset").map(|s| s.name.as_str()), Some("speakers"));
+        assert_eq!(next_sink(&sinks, "missing").map(|s| s.name.as_str()), Some("speakers"));
+        assert!(next_sink(&sinks[..1], "speakers").is_none());
+    }
+    
+    #[test]
+    fn test_nmcli_metered_parsing() {
+        assert!(parse_nmcli_metered("connection.metered:yes").unwrap());
+        assert!(parse_nmcli_metered("connection.metered:yes (guessed)").unwrap());
+        assert!(!parse_nmcli_metered("connection.metered:no").unwrap());
+        assert!(!parse_nmcli_metered("unknown").unwrap());
+        assert!(parse_nmcli_metered("connection.metered:maybe").is_err());
+        
+        assert_eq!(
+            split_nmcli_terse(r"Cafe\:Guest:6f1c-44"),
+            ("Cafe:Guest".to_string(), "6f1c-44".to_string())
+        );
+    }
+    
+    #[test]
+    fn test_parse_portal_color_scheme() {
+        assert_eq!(parse_portal_color_scheme("v u 1\n"), Some(ColorScheme::Dark));
+        assert_eq!(parse_portal_color_scheme("v u 0"), Some(ColorScheme::Light));
+        assert_eq!(parse_portal_color_scheme("v u 2"), Some(ColorScheme::Light));
+        assert_eq!(parse_portal_color_scheme("v u 7"), None);
+        assert_eq!(parse_portal_color_scheme(""), None);
+    }
+    
+    #[test]
+    fn test_set_ini_value() {
+        assert_eq!(
+            set_ini_value("", "Settings", "gtk-application-prefer-dark-theme", "1"),
+            "[Settings]\ngtk-application-prefer-dark-theme=1\n"
+        );
+        
+        let existing = "[Settings]\ngtk-theme-name=Adwaita\ngtk-application-prefer-dark-theme=0\n[Other]\nkey=value\n";
+        assert_eq!(
+            set_ini_value(existing, "Settings", "gtk-application-prefer-dark-theme", "1"),
+            "[Settings]\ngtk-theme-name=Adwaita\ngtk-application-prefer-dark-theme=1\n[Other]\nkey=value\n"
+        );
+        
+        let without_key = "[Settings]\ngtk-theme-name=Adwaita\n[Other]\nkey=value\n";
+        assert_eq!(
+            set_ini_value(without_key, "Settings", "gtk-application-prefer-dark-theme", "1"),
+            "[Settings]\ngtk-theme-name=Adwaita\ngtk-application-prefer-dark-theme=1\n[Other]\nkey=value\n"
+        );
+    }
+    
+    #[test]
+    fn test_parse_hhmm() {
+        assert_eq!(parse_hhmm("00:00").unwrap(), 0.0);
+        assert_eq!(parse_hhmm("20:30").unwrap(), 20.5);
+        assert_eq!(parse_hhmm("06:45").unwrap(), 6.75);
+        assert!(parse_hhmm("24:00").is_err());
+        assert!(parse_hhmm("7:00").is_err());
+        assert!(parse_hhmm("07:60").is_err());
+        assert!(parse_hhmm("0700").is_err());
+    }
+    
+    #[test]
+    fn test_night_light_daemon_to_keep() {
+        let running = ["gammastep", "wlsunset"];
+        assert_eq!(
+            night_light_daemon_to_keep(&running, Some(NightLightBackend::Wlsunset)),
+            Some("wlsunset")
+        );
+        assert_eq!(
+            night_light_daemon_to_keep(&running, Some(NightLightBackend::Gnome)),
+            Some("gammastep")
+        );
+        assert_eq!(
+            night_light_daemon_to_keep(&["redshift"], Some(NightLightBackend::Gammastep)),
+            Some("redshift")
+        );
+        assert_eq!(night_light_daemon_to_keep(&[], None), None);
+        
+        assert_eq!(parse_pgrep_daemons("812 wlsunset\n907 gammastep\n"), ["gammastep", "wlsunset"]);
+    }
+    
+    #[test]
+    fn test_has_global_wlsunset() {
+        let per_output = BTreeMap::from([("eDP-1".to_string(), 4000)]);
+        assert!(has_global_wlsunset("812 wlsunset -T 4501 -t 4500\n", &per_output));
+        assert!(has_global_wlsunset(
+            "812 wlsunset -o eDP-1 -T 4001 -t 4000\n907 wlsunset -T 4501 -t 4500 -o DP-1\n",
+            &per_output
+        ));
+        assert!(!has_global_wlsunset("812 wlsunset -o eDP-1 -T 4001 -t 4000\n", &per_output));
+        assert!(!has_global_wlsunset("", &per_output));
+        assert!(parse_pgrep_daemons("").is_empty());
+    }
+    
+    #[test]
+    fn test_validate_keyboard_repeat() {
+        assert!(validate_keyboard_repeat(25, 600).is_ok());
+        assert!(validate_keyboard_repeat(0, 600).is_err());
+        assert!(validate_keyboard_repeat(101, 600).is_err());
+        assert!(validate_keyboard_repeat(25, 99).is_err());
+        assert!(validate_keyboard_repeat(25, 2001).is_err());
+    }
+    
+    #[test]
+    fn test_parse_gsettings_uint() {
+        assert_eq!(parse_gsettings_uint("uint32 30\n"), Some(30));
+        assert_eq!(parse_gsettings_uint("500"), Some(500));
+        assert_eq!(parse_gsettings_uint("'dark'"), None);
+    }
+    
+    #[test]
+    fn test_parse_pointer_devices() {
+        let devices = "\
+I: Bus=0011 Vendor=0001 Product=0001 Version=ab83
+N: Name=\"AT Translated Set 2 keyboard\"
+H: Handlers=sysrq kbd event3 leds
+
+I: Bus=0018 Vendor=06cb Product=ce26 Version=0100
+N: Name=\"SYNA2BA6:00 06CB:CE26 Touchpad\"
+H: Handlers=event8 mouse1
+";
+        assert_eq!(parse_pointer_devices(devices), vec![PointerDevice::Touchpad]);
+        
+        let with_mouse = format!(
+            "{}\nN: Name=\"Logitech USB Receiver\"\nH: Handlers=event12 mouse2\n",
+            devices
+        );
+        let found = parse_pointer_devices(&with_mouse);
+        assert!(found.contains(&PointerDevice::Mouse));
+        assert!(found.contains(&PointerDevice::Touchpad));
+        
+        assert!(parse_pointer_devices("").is_empty());
+    }
+    
+    #[test]
+    fn test_parse_proc_uptime_and_loadavg() {
+        assert_eq!(parse_uptime("35127.45 130521.12\n").unwrap(), 35127);
+        assert!(parse_uptime("").is_err());
+        
+        assert_eq!(parse_loadavg("0.52 0.61 0.70 2/1234 56789\n").unwrap(), [0.52, 0.61, 0.70]);
+        assert!(parse_loadavg("0.52 0.61").is_err());
+        assert!(parse_loadavg("a b c").is_err());
+    }
+    
+    #[test]
+    fn test_augment_path() {
+        let path = augment_path(
+            Some(OsStr::new("/usr/bin:/opt/tools")),
+            Some(OsStr::new("/home/u")),
+        );
+        let dirs: Vec<PathBuf> = std::env::split_paths(&path).collect();
+        assert_eq!(dirs[..2], [PathBuf::from("/usr/bin"), PathBuf::from("/opt/tools")]);
+        assert!(dirs.contains(&PathBuf::from("/home/u/.nix-profile/bin")));
+        assert!(dirs.contains(&PathBuf::from("/bin")));
+        assert_eq!(dirs.iter().filter(|d| d.as_os_str() == "/usr/bin").count(), 1);
+        
+        let path = augment_path(None, None);
+        assert_eq!(std::env::split_paths(&path).count(), FALLBACK_PATH_DIRS.len());
+    }
+    
+    #[test]
+    fn test_parse_volume_db() {
+        let output = "Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: 32768 /  50% / -18.06 dB\n        balance 0.00";
+        assert_eq!(parse_volume_db(output).unwrap(), Some(-18.06));
+        assert_eq!(parse_volume_percent(output), Some(50));
+        
+        let silent = "Volume: front-left: 0 /   0% / -inf dB,   front-right: 0 /   0% / -inf dB";
+        assert_eq!(parse_volume_db(silent).unwrap(), None);
+        
+        let unity = "Volume: mono: 65536 / 100% / 0.00 dB";
+        assert_eq!(parse_volume_db(unity).unwrap(), Some(0.0));
+        assert!(parse_volume_db("Volume: front-left: 65536 / 100%").is_err());
+    }
+    
+    #[test]
+    fn test_audio_snapshot_path() {
+        assert!(audio_snapshot_path("meeting").unwrap().ends_with("audio-snapshots/meeting.json"));
+        assert!(audio_snapshot_path("work_2-b").is_ok());
+        assert!(audio_snapshot_path("").is_err());
+        assert!(audio_snapshot_path("../config").is_err());
+        assert!(audio_snapshot_path("a/b").is_err());
+    }
+    
+    #[test]
+    fn test_audio_snapshot_tolerates_missing_fields() {
+        let snapshot: AudioSnapshot = serde_json::from_str(r#"{"sink_volume": 40}"#).unwrap();
+        assert_eq!(snapshot.sink_volume, Some(40));
+        assert!(snapshot.default_sink.is_none());
+        assert!(snapshot.app_volumes.is_empty());
+    }
+    
+    #[test]
+    fn test_pick_primary_connection() {
+        let devices = "wifi:connected:Home\\:5G\nethernet:unavailable:\nloopback:connected (externally):lo\n";
+        assert_eq!(
+            pick_primary_connection(devices, ""),
+            (ConnectionKind::Wifi, Some("Home:5G".to_string()))
+        );
+        
+        let wired = "ethernet:connected:Wired connection 1\nwifi:connected:Home\n";
+        assert_eq!(
+            pick_primary_connection(wired, ""),
+            (ConnectionKind::Ethernet, Some("Wired connection 1".to_string()))
+        );
+        
+        let active = "802-3-ethernet:Wired connection 1\nvpn:Office\n";
+        assert_eq!(
+            pick_primary_connection(wired, active),
+            (ConnectionKind::Vpn, Some("Office".to_string()))
+        );
+        
+        assert_eq!(
+            pick_primary_connection("wifi:disconnected:\n", ""),
+            (ConnectionKind::None, None)
+        );
+    }
+    
+    #[test]
+    fn test_parse_brightnessctl_machine() {
+        assert_eq!(parse_brightnessctl_machine("intel_backlight,backlight,7500,50%,15000"), Some(50));
+        // Percentage missing its sign or rounded differently by the tool
+        assert_eq!(parse_brightnessctl_machine("amdgpu_bl0,backlight,85,33,255"), Some(33));
+        assert_eq!(parse_brightnessctl_machine("acpi_video0,backlight,1,10%,7\n"), Some(14));
+        // Raw values unusable, fall back to the formatted field
+        assert_eq!(parse_brightnessctl_machine("dev,backlight,?,42%,0"), Some(42));
+        assert_eq!(parse_brightnessctl_machine("\nintel_backlight,backlight,96000,100%,96000\n"), Some(100));
+        assert_eq!(parse_brightnessctl_machine("Device 'foo' not found"), None);
+        assert_eq!(parse_brightnessctl_machine(""), None);
+    }
+    
+    #[test]
+    fn test_parse_inhibitors() {
+        let output = "\
+WHO            UID  USER PID  COMM           WHAT                          WHY                                       MODE 
+NetworkManager 0    root 1042 NetworkManager sleep                         NetworkManager needs to turn off networks delay
+UPower         0    root 1290 upowerd        handle-lid-switch             Lid switch handled by UPower              block
+Firefox        1000 me   5678 firefox        sleep:idle                    video-playing                             block
+
+3 inhibitors listed.
+";
+        let inhibitors = parse_inhibitors(output);
+        assert_eq!(inhibitors.len(), 3);
+        assert_eq!(inhibitors[0].who, "NetworkManager");
+        assert_eq!(inhibitors[0].why, "NetworkManager needs to turn off networks");
+        assert_eq!(inhibitors[0].mode, "delay");
+        assert_eq!(inhibitors[1].what, "handle-lid-switch");
+        assert_eq!(inhibitors[2].what, "sleep:idle");
+        assert_eq!(inhibitors[2].mode, "block");
+        
+        assert!(parse_inhibitors("No inhibitors.\n").is_empty());
+    }
+    
+    #[test]
+    fn test_new_power_token() {
+        let token = new_power_token();
+        assert_eq!(token.len(), 16);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, new_power_token());
+    }
+    
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#ff8800").unwrap(), "FF8800");
+        assert_eq!(parse_hex_color(" 00aAfF ").unwrap(), "00AAFF");
+        assert!(parse_hex_color("#fff").is_err());
+        assert!(parse_hex_color("#gg0000").is_err());
+        assert!(parse_hex_color("##ff8800").is_err());
+        assert!(parse_hex_color("").is_err());
+    }
+    
+    #[test]
+    fn test_parse_bluetooth_flag() {
+        let output = "Controller 00:1A:7D:DA:71:13 (public)
+	Name: laptop
+	Powered: yes
+	Discoverable: no
+	DiscoverableTimeout: 0x000000b4
+	Pairable: yes";
+        assert_eq!(parse_bluetooth_flag(output, "Discoverable"), Some(false));
+        assert_eq!(parse_bluetooth_flag(output, "Pairable"), Some(true));
+        assert_eq!(parse_bluetooth_flag(output, "Discovering"), None);
+        assert_eq!(parse_bluetooth_flag("No default controller available", "Pairable"), None);
+    }
+    
+    #[test]
+    fn test_nudge_queue() {
+        let queue = NudgeQueue::new();
+        assert_eq!(queue.pending(), None);
+        
+        assert!(queue.push(52));
+        assert!(!queue.push(54));
+        assert_eq!(queue.pending(), Some(54));
+        
+        assert_eq!(queue.take(), Some(54));
+        assert_eq!(queue.take(), None);
+        assert!(queue.push(56));
+    }
+    
+    #[test]
+    fn test_nudge_delta() {
+        let mut config = Config::default();
+        assert_eq!(nudge_delta(&config, true), 2);
+        assert_eq!(nudge_delta(&config, false), -2);
+        
+        config.nudge_step = Some(0);
+        assert_eq!(nudge_delta(&config, true), 1);
+        config.nudge_step = Some(200);
+        assert_eq!(nudge_delta(&config, false), -100);
+    }
+    
+    #[test]
+    fn test_parse_nmcli_autoconnect() {
+        assert!(parse_nmcli_autoconnect("connection.autoconnect:yes\n").unwrap());
+        assert!(!parse_nmcli_autoconnect("connection.autoconnect:no").unwrap());
+        assert!(parse_nmcli_autoconnect("no").is_ok());
+        assert!(parse_nmcli_autoconnect("connection.autoconnect:maybe").is_err());
+    }
+    
+    #[test]
+    fn test_redact_diagnostics() {
+        let network = NetworkState {
+            wifi_enabled: true,
+            wifi_connected: true,
+            wifi_ssid: Some("HomeNet".to_string()),
+            bluetooth_enabled: false,
+            bluetooth_connected: false,
+            bluetooth_battery: None,
+        };
+        let states = AllStates {
+            volume: DomainState::disabled(),
+            mic: DomainState::disabled(),
+            brightness: DomainState::disabled(),
+            network: Ok(network).into(),
+            display: DomainState::disabled(),
+        };
+        let mut report = DiagnosticsReport {
+            version: "1.0.0",
+            compositor: Compositor::Niri,
+            radio_backends: RadioBackends {
+                wifi: RadioBackend::NetworkManager,
+                bluetooth: RadioBackend::Bluetoothctl,
+            },
+            night_light_backend: None,
+            tools: BTreeMap::new(),
+            enabled_cards: Vec::new(),
+            states,
+            log: vec!["[+1.000s INFO control_centre] Connected to HomeNet".to_string()],
+        };
+        
+        redact_diagnostics(&mut report, &[]);
+        let network = report.states.network.value.as_ref().unwrap();
+        assert_eq!(network.wifi_ssid.as_deref(), Some(REDACTED));
+        assert_eq!(report.log[0], "[+1.000s INFO control_centre] Connected to <redacted>");
+        
+        // Names from earlier connections are gone with WiFi off
+        let network = report.states.network.value.as_mut().unwrap();
+        network.wifi_enabled = false;
+        network.wifi_connected = false;
+        network.wifi_ssid = None;
+        report.log = vec![
+            "[+2.000s DEBUG control_centre] Running command: nmcli [\"con\", \"up\", \"Cafe\"]"
+                .to_string(),
+            "[+2.100s DEBUG control_centre] Command output: Cafe:wlan0".to_string(),
+            "[+2.200s ERROR control_centre] Command bluetoothctl failed: Buds not available"
+                .to_string(),
+            "[+2.300s INFO control_centre] Volume set to 40%".to_string(),
+            "[+2.400s INFO control_centre] Reconnected WiFi Cafe Guest".to_string(),
+            "[+2.500s WARN control_centre] Buds (AA:BB:CC:DD:EE:FF) did not connect".to_string(),
+        ];
+        
+        let names = ["Cafe", "Cafe Guest", "Buds", "AA:BB:CC:DD:EE:FF", "lo"].map(String::from);
+        redact_diagnostics(&mut report, &names);
+        assert_eq!(report.states.network.value.as_ref().unwrap().wifi_ssid, None);
+        assert_eq!(
+            report.log,
+            vec![
+                "[+2.000s DEBUG control_centre] Running command: nmcli <redacted>",
+                "[+2.200s ERROR control_centre] Command bluetoothctl <redacted>",
+                "[+2.300s INFO control_centre] Volume set to 40%",
+                "[+2.400s INFO control_centre] Reconnected WiFi <redacted>",
+                "[+2.500s WARN control_centre] <redacted> (<redacted>) did not connect",
+            ]
+        );
+    }
+    
+    #[test]
+    fn test_missing_source_error() {
+        let stderr = "Failed to get source information: No such entity";
+        assert!(is_missing_entity_error(stderr));
+        assert!(!is_missing_entity_error("Connection failure: Connection refused"));
+        assert!(matches!(
+            missing_source_error(ControlCentreError::CommandFailed(stderr.to_string())),
+            ControlCentreError::DeviceNotFound(_)
+        ));
+        assert!(matches!(
+            missing_source_error(ControlCentreError::Timeout("pactl".to_string())),
+            ControlCentreError::Timeout(_)
+        ));
+        
+        let state: DomainState<MicState> =
+            Err(ControlCentreError::DeviceNotFound("No default audio source".to_string())).into();
+        assert!(!state.available);
+        assert!(state.missing_device);
+        
+        let state: DomainState<MicState> =
+            Err(ControlCentreError::CommandFailed(stderr.to_string())).into();
+        assert!(!state.missing_device);
+    }
+    
+    #[test]
+    fn test_toggle_output_target() {
+        let sink = |name: &str, port: &str| SinkInfo {
+            index: 0,
+            name: name.to_string(),
+            description: String::new(),
+            volume: 50,
+            muted: false,
+            base_volume: 100,
+            channels: Vec::new(),
+            ports: vec![pactl::Port {
+                name: port.to_string(),
+                description: String::new(),
+                available: port != "unplugged",
+            }],
+            active_port: Some(port.to_string()),
+            sample_spec: None,
+        };
+        let sinks = [
+            sink("builtin", "analog-output-speaker"),
+            sink("hdmi", "hdmi-output-0"),
+            sink("bluez", "headset-output"),
+            sink("jack", "unplugged"),
+        ];
+        let target = |current, remembered| {
+            toggle_output_target(&sinks, current, remembered).map(|s| s.name.as_str())
+        };
+        
+        assert_eq!(target("builtin", None), Some("hdmi"));
+        assert_eq!(target("builtin", Some("bluez")), Some("bluez"));
+        assert_eq!(target("builtin", Some("jack")), Some("hdmi"));
+        assert_eq!(target("bluez", Some("bluez")), Some("builtin"));
+        
+        // Without speakers the other sink is used
+        let without_speakers = toggle_output_target(&sinks[1..3], "hdmi", None);
+        assert_eq!(without_speakers.map(|s| s.name.as_str()), Some("bluez"));
+        assert!(toggle_output_target(&sinks[..1], "builtin", None).is_none());
+    }
+    
+    #[test]
+    fn test_stream_routes() {
+        let json = r#"[{"index": 56, "name": "speakers"}, {"index": 57, "name": "hdmi"}]"#;
+        let sinks = pactl::parse_sinks(json).unwrap();
+        let input = |index, sink| SinkInput {
+            index,
+            sink,
+            app_name: "mpv".to_string(),
+            volume: 100,
+            muted: false,
+        };
+        
+        let routes = stream_routes(&[input(112, 57), input(113, 99)], &sinks);
+        assert_eq!(routes[0].input, 112);
+        assert_eq!(routes[0].sink.as_deref(), Some("hdmi"));
+        assert!(routes[1].sink.is_none());
+    }
+    
+    #[test]
+    fn test_refusal_cooldown() {
+        let cooldown = RefusalCooldown::new();
+        let period = std::time::Duration::from_secs(10);
+        assert!(!cooldown.active(period));
+        
+        // A single refused attempt does not start it, exhausted retries do
+        cooldown.record(true, false);
+        assert!(!cooldown.active(period));
+        cooldown.record(true, true);
+        assert!(cooldown.active(period));
+        assert!(!cooldown.active(std::time::Duration::ZERO));
+        
+        // Refusals during the cooldown keep it, an answer ends it
+        cooldown.record(true, false);
+        assert!(cooldown.active(period));
+        cooldown.record(false, false);
+        assert!(!cooldown.active(period));
+        
+        assert!(is_connection_refused::<()>(&Err(ControlCentreError::CommandFailed(
+            "Connection failure: Connection refused".to_string()
+        ))));
+        assert!(!is_connection_refused(&Ok(())));
+    }
+    
+    #[tokio::test]
+    async fn test_retry_on_connection_refused() {
+        let refused = || {
+            ControlCentreError::CommandFailed("Connection failure: Connection refused".to_string())
+        };
+        let delay = std::time::Duration::ZERO;
+        
+        // Refused twice while the server restarts, then answers
+        let mut calls = 0;
+        let result = retry_on_connection_refused(5, delay, || {
+            calls += 1;
+            let result = if calls < 3 { Err(refused()) } else { Ok("Volume: 50%") };
+            async move { result }
+        })
+        .await;
+        assert_eq!(result.unwrap(), "Volume: 50%");
+        assert_eq!(calls, 3);
+        
+        // Server gone for good
+        let mut calls = 0;
+        let result: CCResult<()> = retry_on_connection_refused(3, delay, || {
+            calls += 1;
+            async { Err(refused()) }
+        })
+        .await;
+        assert!(matches!(result, Err(ControlCentreError::CommandFailed(_))));
+        assert_eq!(calls, 3);
+        
+        // Other failures are not retried
+        let mut calls = 0;
+        let result: CCResult<()> = retry_on_connection_refused(3, delay, || {
+            calls += 1;
+            async { Err(ControlCentreError::CommandFailed("No such entity".to_string())) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+    
+    #[test]
+    fn test_parse_nmcli_radio() {
+        assert!(parse_nmcli_radio("enabled\n").unwrap());
+        assert!(parse_nmcli_radio("Enabled").unwrap());
+        assert!(!parse_nmcli_radio("disabled").unwrap());
+        assert!(parse_nmcli_radio("aktiviert").is_err());
+        assert!(parse_nmcli_radio("").is_err());
+    }
+    
+    #[test]
+    fn test_parse_nmcli_powersave() {
+        let parse = |value: &str| parse_nmcli_powersave(&format!("802-11-wireless.powersave:{}", value));
+        assert_eq!(parse("3 (enable)").unwrap(), Some(true));
+        assert_eq!(parse("2").unwrap(), Some(false));
+        assert_eq!(parse("0 (default)").unwrap(), None);
+        assert_eq!(parse_nmcli_powersave("disable").unwrap(), Some(false));
+        assert!(parse("").is_err());
+    }
+    
+    #[test]
+    fn test_parse_wallpaper_outputs() {
+        let swww = "eDP-1: 1920x1080, scale: 1, currently displaying: image: /home/u/a.png\n\
+                    HDMI-A-1: 2560x1440, scale: 1, currently displaying: color: 000000";
+        assert_eq!(parse_swww_query(swww).as_deref(), Some("/home/u/a.png"));
+        assert_eq!(parse_swww_query("HDMI-A-1: 2560x1440, displaying: color: 000000"), None);
+        
+        let hyprpaper = "eDP-1 = /home/u/my wall.jpg\nHDMI-A-1 = /home/u/b.jpg";
+        assert_eq!(parse_hyprpaper_active(hyprpaper).as_deref(), Some("/home/u/my wall.jpg"));
+        assert_eq!(parse_hyprpaper_active("no wallpapers active"), None);
+        
+        assert_eq!(
+            parse_swaybg_cmdline(b"swaybg\0-m\0fill\0-i\0/home/u/a b.png\0").as_deref(),
+            Some("/home/u/a b.png")
+        );
+        assert_eq!(parse_swaybg_cmdline(b"swaybg\0--image=/a.png\0").as_deref(), Some("/a.png"));
+        assert_eq!(parse_swaybg_cmdline(b"swaybg\0-c\0#000000\0"), None);
+    }
+    
+    #[test]
+    fn test_validate_wallpaper_path() {
+        assert!(validate_wallpaper_path("/etc/hostname").is_err());
+        assert!(validate_wallpaper_path("/nonexistent/wall.png").is_err());
+        
+        let path = std::env::temp_dir().join("control-centre-test-wall.PNG");
+        std::fs::write(&path, b"").unwrap();
+        assert!(validate_wallpaper_path(&path.to_string_lossy()).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn test_gammarelay_output_path() {
+        assert_eq!(gammarelay_output_path("eDP-1"), "/outputs/eDP_1");
+        assert_eq!(gammarelay_output_path("HDMI-A-1"), "/outputs/HDMI_A_1");
+    }
+    
+    #[test]
+    fn test_parse_service_status() {
+        assert_eq!(parse_service_status("active\n"), Some(ServiceStatus::Active));
+        assert_eq!(parse_service_status("activating\n"), Some(ServiceStatus::Active));
+        assert_eq!(parse_service_status("inactive\n"), Some(ServiceStatus::Inactive));
+        assert_eq!(parse_service_status("deactivating"), Some(ServiceStatus::Inactive));
+        assert_eq!(parse_service_status("failed\n"), Some(ServiceStatus::Failed));
+        assert_eq!(parse_service_status(""), None);
+        assert_eq!(parse_service_status("Failed to connect to bus"), None);
+    }
+    
+    #[test]
+    fn test_ensure_service_allowed() {
+        let config = Config { services: vec!["mako".to_string()], ..Config::default() };
+        assert!(ensure_service_allowed(&config, "mako").is_ok());
+        assert!(matches!(
+            ensure_service_allowed(&config, "sshd"),
+            Err(ControlCentreError::PermissionDenied(_))
+        ));
+        assert!(ensure_service_allowed(&Config::default(), "mako").is_err());
+    }
+    
+    #[test]
+    fn test_night_light_temperature_parsing() {
+        assert_eq!(parse_gvariant_uint("uint32 4000"), Some(4000));
+        assert_eq!(
+            parse_gammastep_print("Period: Night\nColor temperature: 4500K\nBrightness: 1.00"),
+            Some(4500)
+        );
+        assert_eq!(parse_gammastep_print("Period: Daytime"), None);
+        
+        let running = split_cmdline(
+            b"gammastep\0-m\0wayland\0-l\x0055.7:12.6\0-t\x006500:4500\0-c/tmp/g.conf\0-P\0",
+        );
+        assert_eq!(
+            gammastep_print_args(&running),
+            ["-l", "55.7:12.6", "-t", "6500:4500", "-c/tmp/g.conf"]
+        );
+        
+        let pinned = split_cmdline(b"wlsunset\0-T\x004501\0-t\x004500\0");
+        assert_eq!(parse_wlsunset_pinned(&pinned), Some(4500));
+        let scheduled = split_cmdline(b"wlsunset\0-T\x006500\0-t\x004500\0-S\x0007:00\0");
+        assert_eq!(parse_wlsunset_pinned(&scheduled), None);
+        
+        assert_eq!(applied_temperature(false, None), Some(NEUTRAL_TEMPERATURE));
+        assert_eq!(applied_temperature(true, None), Some(NIGHT_LIGHT_TEMPERATURE));
+    }
+    
+    #[test]
+    fn test_brightness_permission_handling() {
+        assert!(is_permission_error(&ControlCentreError::CommandFailed(
+            "Can't modify brightness: Permission denied".to_string()
+        )));
+        assert!(!is_permission_error(&ControlCentreError::CommandFailed(
+            "Device 'foo' not found".to_string()
+        )));
+        
+        assert_eq!(
+            escalation_args(Escalation::Sudo, &["brightnessctl", "set", "50%"]),
+            vec!["-n", "brightnessctl", "set", "50%"]
+        );
+        assert_eq!(escalation_args(Escalation::Pkexec, &["brightnessctl"]), vec!["brightnessctl"]);
+        
+        assert!(brightness_permission_hint(Some(Escalation::Pkexec)).contains("pkexec"));
+        assert!(!brightness_permission_hint(None).contains("allow_privileged_brightness"));
+    }
+    
+    #[test]
+    fn test_effective_temperature() {
+        assert_eq!(effective_temperature(false, Some(4500)), None);
+        assert_eq!(effective_temperature(true, Some(3800)), Some(3800));
+        assert_eq!(effective_temperature(true, None), Some(NIGHT_LIGHT_TEMPERATURE));
+        assert_eq!(
+            effective_temperature(true, Some(NEUTRAL_TEMPERATURE)),
+            Some(NIGHT_LIGHT_TEMPERATURE)
+        );
+    }
+    
+    #[test]
+    fn test_parse_time_sync_status() {
+        assert_eq!(
+            parse_time_sync_status("NTP=yes\nNTPSynchronized=no\n").unwrap(),
+            TimeSyncStatus {
+                ntp_enabled: true,
+                synchronized: false
+            }
+        );
+        assert_eq!(
+            parse_time_sync_status("NTPSynchronized=yes\nNTP=no").unwrap(),
+            TimeSyncStatus {
+                ntp_enabled: false,
+                synchronized: true
+            }
+        );
+        assert!(parse_time_sync_status("NTP=yes").is_err());
+        assert!(parse_time_sync_status("NTP=maybe\nNTPSynchronized=yes").is_err());
+    }
+    
+    #[test]
+    fn test_parse_ddc_brightness() {
+        assert_eq!(
+            parse_ddc_brightness("VCP 10 C 40 100").unwrap(),
+            ExternalBrightness { current: 40, max: 100 }
+        );
+        assert!(parse_ddc_brightness("VCP 10 ERR").is_err());
+        assert!(parse_ddc_brightness("Display not found").is_err());
+        
+        let detect = "Display 1\n   I2C bus:  /dev/i2c-4\n\n\
+                      Invalid display\n   I2C bus:  /dev/i2c-5\n\n\
+                      Display 2\n";
+        assert_eq!(parse_ddc_displays(detect), vec![1, 2]);
+    }
+    
+    #[test]
+    fn test_ddc_permission_error() {
+        let denied = ControlCentreError::CommandFailed(
+            "Open failed for /dev/i2c-4: errno=EACCES(13): Permission denied".to_string(),
+        );
+        match ddc_error(denied) {
+            ControlCentreError::PermissionDenied(message) => assert!(message.contains("i2c group")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(matches!(
+            ddc_error(ControlCentreError::CommandFailed("Display not found".to_string())),
+            ControlCentreError::CommandFailed(_)
+        ));
+    }
+    
+    #[test]
+    fn test_mako_notification_mode() {
+        let config = "font=monospace 10\n\n[mode=do-not-disturb]\ninvisible=1\n\n\
+                      [app-name=Slack mode=calls-only]\ninvisible=0\n";
+        
+        let mode = mako_notification_mode("default\n", config);
+        assert_eq!(mode.mode, "default");
+        assert_eq!(mode.available, vec!["default", "do-not-disturb", "calls-only"]);
+        
+        // An active mode missing from the config is still offered
+        let mode = mako_notification_mode("default\nfocus\n", "");
+        assert_eq!(mode.mode, "focus");
+        assert_eq!(mode.available, vec!["default", "focus"]);
+        
+        assert_eq!(mako_notification_mode("", "").mode, "default");
+    }
+    
+    #[test]
+    fn test_validate_mako_mode() {
+        assert!(validate_mako_mode("do-not-disturb").is_ok());
+        assert!(validate_mako_mode("calls_only.2").is_ok());
+        assert!(validate_mako_mode("").is_err());
+        assert!(validate_mako_mode("-s").is_err());
+        assert!(validate_mako_mode("a b").is_err());
+    }
+    
+    #[test]
+    fn test_own_niri_window() {
+        let windows = [
+            NiriWindow { id: 1, pid: Some(10), ..Default::default() },
+            NiriWindow { id: 2, pid: None, ..Default::default() },
+            NiriWindow { id: 3, pid: Some(42), ..Default::default() },
+        ];
+        assert_eq!(own_niri_window(&windows, 42).map(|w| w.id), Some(3));
+        assert!(own_niri_window(&windows, 7).is_none());
+    }
+    
+    #[test]
+    fn test_validate_opacity() {
+        assert_eq!(validate_opacity(0.0).unwrap(), 0.0);
+        assert_eq!(validate_opacity(1.0).unwrap(), 1.0);
+        assert!(validate_opacity(1.01).is_err());
+        assert!(validate_opacity(-0.1).is_err());
+        assert!(validate_opacity(f32::NAN).is_err());
+    }
+    
+    #[test]
+    fn test_parse_latest_version() {
+        let github = r#"{"tag_name": "v1.2.0", "name": "Control Centre 1.2"}"#;
+        assert_eq!(parse_latest_version(github).unwrap(), "1.2.0");
+        assert_eq!(parse_latest_version(r#"{"version": "1.1.3"}"#).unwrap(), "1.1.3");
+        assert_eq!(parse_latest_version("1.0.1\n").unwrap(), "1.0.1");
+        assert!(parse_latest_version("<html>Not found</html>").is_err());
+        assert!(parse_latest_version(r#"{"message": "rate limited"}"#).is_err());
+        
+        let page = format!("<html>\n{}</html>", "é".repeat(10_000));
+        let error = parse_latest_version(&page).unwrap_err().to_string();
+        assert!(error.chars().count() < 2 * UPDATE_RESPONSE_EXCERPT);
+        assert!(error.ends_with('…'));
+        assert!(!error.contains('\n'));
+    }
+    
+    #[test]
+    fn test_is_newer_version() {
+        assert!(is_newer_version("1.0.1", "1.0.0"));
+        assert!(is_newer_version("1.1", "1.0.9"));
+        assert!(is_newer_version("2.0.0-rc1", "1.9.9"));
+        assert!(!is_newer_version("1.0", "1.0.0"));
+        assert!(!is_newer_version("0.9.9", "1.0.0"));
+        assert!(!is_newer_version("garbage", "1.0.0"));
+    }
+    
+    #[test]
+    fn test_sink_ceiling() {
+        let limit = |name: &str, max| SinkLimit {
+            name: name.to_string(),
+            max,
+        };
+        let limits = [limit("headphones", 80), limit("hdmi", 120), limit("headphones", 60)];
+        
+        assert_eq!(sink_ceiling(&limits, "headphones"), Some(60));
+        assert_eq!(sink_ceiling(&limits, "hdmi"), Some(100));
+        assert_eq!(sink_ceiling(&limits, "speakers"), None);
+        assert_eq!(sink_ceiling(&[], "headphones"), None);
+        
+        assert_eq!(cap_sink_volume(&limits, "headphones", 90), 60);
+        assert_eq!(cap_sink_volume(&limits, "headphones", 40), 40);
+        assert_eq!(cap_sink_volume(&limits, "speakers", 90), 90);
+        
+        // +6 dB on a sink limited to 50% is written as 50%, never above it
+        let (factor, capped) = cap_linear_volume(10f32.powf(6.0 / 20.0), 50);
+        assert!(capped);
+        assert!((factor - 0.125).abs() < 1e-6);
+        assert_eq!(cap_linear_volume(0.1, 80), (0.1, false));
+    }
+    
+    #[test]
+    fn test_parse_active_wifi() {
+        let active = "802-3-ethernet:Wired connection 1\nvpn:Work\n802-11-wireless:Home\\:5G\n";
+        assert_eq!(parse_active_wifi(active), Some("Home:5G".to_string()));
+        assert_eq!(parse_active_wifi("wifi:Cafe\n"), Some("Cafe".to_string()));
+        assert_eq!(parse_active_wifi("802-3-ethernet:Wired connection 1\n"), None);
+        assert_eq!(parse_active_wifi(""), None);
+    }
+    
+    #[test]
+    fn test_peak_level() {
+        let samples: Vec<u8> = [0i16, 1000, -16384, 200]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        assert_eq!(peak_level(&samples), 0.5);
+        assert_eq!(peak_level(&i16::MIN.to_le_bytes()), 1.0);
+        assert_eq!(peak_level(&[0, 0, 0]), 0.0);
+        assert_eq!(peak_level(&[]), 0.0);
+    }
+    
+    #[test]
+    fn test_dummy_sink() {
+        assert!(is_dummy_sink("auto_null"));
+        assert!(is_dummy_sink("Dummy Output"));
+        assert!(!is_dummy_sink("alsa_output.pci-0000_00_1f.3.analog-stereo"));
+        assert!(!is_dummy_sink("Built-in Audio Analog Stereo"));
+        assert!(!is_dummy_sink("alsa_output.usb-Dummy_Audio_Labs-00.analog-stereo"));
+        assert!(!is_dummy_sink("auto_null.monitor"));
+        
+        let state: DomainState<VolumeState> = ensure_real_sink("auto_null")
+            .map(|()| VolumeState { volume: 50, muted: false })
+            .into();
+        assert!(!state.available);
+        assert!(state.audio_unavailable);
+        assert!(!state.missing_device);
+    }
+    
+    #[test]
+    fn test_recent_bluetooth_devices() {
+        let paired = vec![
+            ("AA".to_string(), "Speaker".to_string()),
+            ("BB".to_string(), "AirPods".to_string()),
+            ("CC".to_string(), "Keyboard".to_string()),
+            ("DD".to_string(), "Controller".to_string()),
+            ("EE".to_string(), "Mouse".to_string()),
+        ];
+        let history = BTreeMap::from([("AA".to_string(), 100), ("BB".to_string(), 300)]);
+        let connected = ["CC".to_string()];
+        
+        let order: Vec<String> = recent_bluetooth_devices(paired, &connected, &history)
+            .into_iter()
+            .map(|device| device.name)
+            .collect();
+        assert_eq!(order, ["Keyboard", "AirPods", "Speaker", "Controller", "Mouse"]);
+    }
+    
+    #[tokio::test]
+    async fn test_duck_failures_and_cancel() {
+        let state = AppState::new();
+        let failing = |_| async {
+            Err::<(), _>(ControlCentreError::CommandFailed("pactl failed".to_string()))
+        };
+        
+        // A failed first duck records nothing
+        let result = begin_duck(&state, 20, async { Ok(70) }, failing).await;
+        assert!(result.is_err());
+        assert_eq!(state.duck_origin(), None);
+        
+        let (value, generation) = begin_duck(&state, 20, async { Ok(70) }, |_| async { Ok(()) })
+            .await
+            .unwrap();
+        assert_eq!(value, 20);
+        assert_eq!(state.duck_origin(), Some(70));
+        
+        // A failed re-duck keeps the running duck and its pending restore
+        let result = begin_duck(&state, 10, async { Ok(20) }, failing).await;
+        assert!(result.is_err());
+        assert_eq!(state.duck_origin(), Some(70));
+        assert_eq!(DUCK_GENERATION.load(Ordering::SeqCst), generation);
+        
+        // An explicit volume change ends the duck and its restore
+        cancel_duck(&state);
+        assert_eq!(state.duck_origin(), None);
+        assert_ne!(DUCK_GENERATION.load(Ordering::SeqCst), generation);
+    }
 }
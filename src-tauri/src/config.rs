@@ -0,0 +1,527 @@
+//! User configuration for Control Centre
+//!
+//! Loads optional settings from `$XDG_CONFIG_HOME/control-centre/config.json`
+//! (falling back to `~/.config/control-centre/config.json`). Every field is
+//! optional; a missing or malformed file yields the defaults.
+
+use crate::error::{CCResult, ControlCentreError};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// Config file name inside the config directory
+const CONFIG_FILE: &str = "config.json";
+
+/// User-configurable settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Override for the Wayland app-id (and X11 WM_CLASS) of the window.
+    /// Must be a valid GApplication id such as `com.example.ControlCentre`.
+    pub app_id: Option<String>,
+
+    /// Create the window hidden on startup so the first toggle only has to
+    /// show an already rendered webview (same as passing `--preload`)
+    pub start_hidden: bool,
+
+    /// Hide the panel when focus moves to another application
+    pub hide_on_focus_loss: bool,
+
+    /// Default handling of out-of-range volume/brightness values when a
+    /// command call does not specify a mode
+    pub value_mode: ValueMode,
+
+    /// Fade the volume out before muting and back in after unmuting
+    pub smooth_mute: bool,
+
+    /// Percentage applied by one scroll nudge of volume or brightness
+    /// (built-in fallback 2%)
+    pub nudge_step: Option<u8>,
+
+    /// Volume restored by `reset_defaults` (built-in fallback 50%)
+    pub default_volume: Option<u8>,
+
+    /// Brightness restored by `reset_defaults` (built-in fallback 70%)
+    pub default_brightness: Option<u8>,
+
+    /// Highest volume allowed on specific sinks, e.g. to protect hearing on
+    /// headphones
+    pub sink_limits: Vec<SinkLimit>,
+
+    /// How long after a brightness change a read within 1% of the set value
+    /// reports the set value, hiding brightnessctl's rounding from the slider
+    /// (built-in fallback 1000ms; 0 turns it off)
+    pub brightness_hysteresis_ms: Option<u64>,
+
+    /// Retry `set_brightness` as root (via `sudo -n` or `pkexec`) when the
+    /// backlight is not writable by the user; off by default
+    pub allow_privileged_brightness: bool,
+
+    /// Reconnect paired Bluetooth devices when Bluetooth is turned on
+    pub bt_auto_reconnect: bool,
+
+    /// Skip querying every backend shortly after startup; saves a burst
+    /// of work on minimal setups at the cost of a slower first open
+    pub skip_warm_up: bool,
+
+    /// Ambient-light-sensor driven brightness
+    pub auto_brightness: AutoBrightnessConfig,
+
+    /// Notifications when the battery runs low
+    pub battery_warning: BatteryWarningConfig,
+
+    /// Fixed Night Light on/off times; when unset Night Light stays on
+    /// while enabled
+    pub night_light_schedule: Option<NightLightSchedule>,
+
+    /// Last display arrangement chosen from the panel, re-applied at startup
+    /// and when monitors are plugged in or removed
+    pub display_arrangement: Option<DisplayArrangement>,
+
+    /// Cards shown in the panel; when unset, every card whose backend tool
+    /// is installed is shown
+    pub enabled_cards: Option<Vec<Card>>,
+
+    /// systemd user services (e.g. `mako`, `waybar`) the panel may start
+    /// and stop; any other unit is refused
+    pub services: Vec<String>,
+
+    /// Panel mode last chosen with `set_panel_size`, restored on startup
+    pub panel_size: PanelSize,
+
+    /// Panel background opacity from 0.0 to 1.0, set with `set_window_opacity`
+    /// (built-in fallback 0.85)
+    pub window_opacity: Option<f32>,
+
+    /// Window size of the compact and expanded panel
+    pub panel_dimensions: PanelDimensions,
+
+    /// Top-right window corners saved by `position_window`, keyed by a
+    /// fingerprint of the monitor layout they were saved on
+    pub window_positions: BTreeMap<String, (f64, f64)>,
+
+    /// Logical screen width used to place the window when the compositor
+    /// reports no monitor (defaults to 1920)
+    pub fallback_screen_width: Option<f64>,
+
+    /// Backend commands taking at least this long are reported with a
+    /// `slow-command` event (built-in fallback 2000ms; 0 turns it off)
+    pub slow_command_ms: Option<u64>,
+
+    /// Release endpoint asked for the latest version by `check_for_update`;
+    /// update checks make no network request unless this is set
+    pub update_url: Option<String>,
+
+    /// Log filter in `RUST_LOG` syntax (e.g. `control_centre::commands=debug,info`)
+    /// to turn up single modules; `RUST_LOG` overrides it
+    pub log: Option<String>,
+
+    /// Absolute paths of tools (e.g. `"pactl": "/run/current-system/sw/bin/pactl"`)
+    /// for setups where they are not on the GUI session's `PATH`
+    pub binaries: BTreeMap<String, PathBuf>,
+}
+
+/// Settings for brightness following the ambient light sensor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoBrightnessConfig {
+    /// Whether auto brightness is on
+    pub enabled: bool,
+
+    /// How often the sensor is sampled, in milliseconds
+    pub interval_ms: u64,
+
+    /// How long a manual brightness change suspends auto brightness, in seconds
+    pub manual_override_secs: u64,
+
+    /// `[lux, percent]` points, interpolated linearly and sorted by lux
+    pub curve: Vec<(f64, u8)>,
+}
+
+impl Default for AutoBrightnessConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_ms: 2000,
+            manual_override_secs: 300,
+            curve: vec![
+                (0.0, 5),
+                (10.0, 20),
+                (100.0, 40),
+                (500.0, 70),
+                (1000.0, 100),
+            ],
+        }
+    }
+}
+
+/// Settings for the low battery warning
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryWarningConfig {
+    /// Whether the battery is watched at all
+    pub enabled: bool,
+
+    /// How often the battery is read, in seconds
+    pub interval_secs: u64,
+
+    /// Percentages that trigger a warning when the charge falls to them
+    pub thresholds: Vec<u8>,
+}
+
+impl Default for BatteryWarningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 60,
+            thresholds: vec![15, 5],
+        }
+    }
+}
+
+/// How out-of-range percentage values are handled by setter commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueMode {
+    /// Return an error for values outside 0-100
+    #[default]
+    Reject,
+    /// Clamp into 0-100 and apply, returning the applied value
+    Clamp,
+}
+
+/// Compact or expanded panel, like the sections of macOS Control Centre
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PanelSize {
+    #[default]
+    Compact,
+    Expanded,
+}
+
+/// `[width, height]` of the window in each panel mode, in logical pixels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PanelDimensions {
+    pub compact: (f64, f64),
+    pub expanded: (f64, f64),
+}
+
+impl Default for PanelDimensions {
+    fn default() -> Self {
+        Self {
+            // Matches the window size in tauri.conf.json
+            compact: (420.0, 400.0),
+            expanded: (420.0, 720.0),
+        }
+    }
+}
+
+impl PanelDimensions {
+    pub fn get(&self, size: PanelSize) -> (f64, f64) {
+        match size {
+            PanelSize::Compact => self.compact,
+            PanelSize::Expanded => self.expanded,
+        }
+    }
+}
+
+/// Volume ceiling for one sink
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SinkLimit {
+    /// Sink name as shown by `pactl list short sinks`
+    pub name: String,
+    /// Highest volume percentage the panel sets on this sink
+    pub max: u8,
+}
+
+/// Night Light start and end times as `HH:MM`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NightLightSchedule {
+    pub from: String,
+    pub to: String,
+}
+
+/// A control card in the panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Card {
+    Audio,
+    Brightness,
+    Network,
+    Bluetooth,
+    NightLight,
+    Power,
+    Media,
+}
+
+impl Card {
+    /// Every card, in display order
+    pub const ALL: [Card; 7] = [
+        Card::Audio,
+        Card::Brightness,
+        Card::Network,
+        Card::Bluetooth,
+        Card::NightLight,
+        Card::Power,
+        Card::Media,
+    ];
+}
+
+/// How multiple outputs are laid out relative to each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisplayArrangement {
+    /// All outputs share the same origin and show the same area
+    Mirror,
+    /// Outputs are placed side by side from left to right
+    Extend,
+}
+
+impl Config {
+    /// User config base directory (`$XDG_CONFIG_HOME` or `~/.config`)
+    pub fn base_dir() -> PathBuf {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .unwrap_or_else(|| {
+                let home = std::env::var_os("HOME").unwrap_or_default();
+                PathBuf::from(home).join(".config")
+            })
+    }
+
+    /// Directory holding the config file
+    pub fn dir() -> PathBuf {
+        Self::base_dir().join("control-centre")
+    }
+
+    /// Full path of the config file
+    pub fn path() -> PathBuf {
+        Self::dir().join(CONFIG_FILE)
+    }
+
+    /// Load config from disk, falling back to defaults
+    pub fn load() -> Self {
+        let path = Self::path();
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                info!("No config file at {}, using defaults", path.display());
+                return Self::default();
+            }
+        };
+
+        match serde_json::from_str(&contents) {
+            Ok(config) => {
+                info!("Loaded config from {}", path.display());
+                config
+            }
+            Err(e) => {
+                warn!(
+                    "Invalid config file {}: {}, using defaults",
+                    path.display(),
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Read the config file, failing instead of falling back to defaults
+    pub fn read() -> CCResult<Self> {
+        let contents = std::fs::read_to_string(Self::path())?;
+        serde_json::from_str(&contents)
+            .map_err(|e| ControlCentreError::ParseError(format!("Invalid config file: {}", e)))
+    }
+
+    /// The `log` filter from the config file
+    ///
+    /// Read separately from `load` because logging has to be set up
+    /// before `load` reports what it found.
+    pub fn log_filter() -> Option<String> {
+        let contents = std::fs::read_to_string(Self::path()).ok()?;
+        serde_json::from_str::<Self>(&contents).ok()?.log
+    }
+
+    /// Write config to disk, creating the config directory if needed
+    pub fn save(&self) -> CCResult<()> {
+        let path = Self::path();
+        std::fs::create_dir_all(Self::dir())?;
+
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| ControlCentreError::ParseError(e.to_string()))?;
+        std::fs::write(&path, contents)?;
+
+        info!("Saved config to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Shared config managed by Tauri, updatable at runtime
+pub struct ConfigStore {
+    config: RwLock<Config>,
+}
+
+impl ConfigStore {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config: RwLock::new(config),
+        }
+    }
+
+    /// Snapshot of the current config
+    pub fn get(&self) -> Config {
+        self.config.read().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// Swap in a config read from disk, without writing it back
+    pub fn replace(&self, config: Config) {
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+    }
+
+    /// Modify the config and persist it to disk
+    pub fn update(&self, f: impl FnOnce(&mut Config)) -> CCResult<()> {
+        let snapshot = {
+            let mut config = self
+                .config
+                .write()
+                .map_err(|e| ControlCentreError::SystemError(e.to_string()))?;
+            f(&mut config);
+            config.clone()
+        };
+        snapshot.save()
+    }
+}
+
+/// Check whether a string is a valid GApplication id
+///
+/// GTK refuses to start with an invalid id, so overrides are validated
+/// before being handed to the windowing backend.
+pub fn is_valid_app_id(id: &str) -> bool {
+    if id.is_empty() || id.len() > 255 || !id.contains('.') {
+        return false;
+    }
+
+    id.split('.').all(|element| {
+        !element.is_empty()
+            && !element.starts_with(|c: char| c.is_ascii_digit())
+            && element
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_defaults_on_empty_json() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert!(config.app_id.is_none());
+        assert!(!config.start_hidden);
+        assert!(!config.hide_on_focus_loss);
+        assert_eq!(config.value_mode, ValueMode::Reject);
+        assert!(!config.auto_brightness.enabled);
+        assert!(!config.auto_brightness.curve.is_empty());
+        assert!(config.binaries.is_empty());
+    }
+
+    #[test]
+    fn test_binaries_parsing() {
+        let config: Config =
+            serde_json::from_str(r#"{"binaries": {"pactl": "/run/current-system/sw/bin/pactl"}}"#)
+                .unwrap();
+        assert_eq!(
+            config.binaries.get("pactl"),
+            Some(&PathBuf::from("/run/current-system/sw/bin/pactl"))
+        );
+    }
+
+    #[test]
+    fn test_value_mode_parsing() {
+        let config: Config = serde_json::from_str(r#"{"value_mode": "clamp"}"#).unwrap();
+        assert_eq!(config.value_mode, ValueMode::Clamp);
+    }
+
+    #[test]
+    fn test_enabled_cards_parsing() {
+        let config: Config =
+            serde_json::from_str(r#"{"enabled_cards": ["audio", "night_light"]}"#).unwrap();
+        assert_eq!(
+            config.enabled_cards,
+            Some(vec![Card::Audio, Card::NightLight])
+        );
+    }
+
+    #[test]
+    fn test_is_valid_app_id() {
+        assert!(is_valid_app_id("com.niri.control-centre"));
+        assert!(is_valid_app_id("org.example.ControlCentre"));
+        assert!(!is_valid_app_id("control-centre"));
+        assert!(!is_valid_app_id("com..control"));
+        assert!(!is_valid_app_id("com.1control"));
+        assert!(!is_valid_app_id("com.control centre"));
+    }
+
+    #[test]
+    fn test_services_parsing() {
+        let config: Config = serde_json::from_str(r#"{"services": ["mako", "waybar"]}"#).unwrap();
+        assert_eq!(config.services, vec!["mako", "waybar"]);
+        assert!(Config::default().services.is_empty());
+    }
+
+    #[test]
+    fn test_log_filter_parsing() {
+        let config: Config =
+            serde_json::from_str(r#"{"log": "control_centre::commands=debug,info"}"#).unwrap();
+        assert_eq!(config.log.as_deref(), Some("control_centre::commands=debug,info"));
+        assert!(Config::default().log.is_none());
+    }
+
+    #[test]
+    fn test_panel_size_parsing() {
+        let config: Config = serde_json::from_str(
+            r#"{"panel_size": "expanded", "panel_dimensions": {"expanded": [600, 800]}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.panel_size, PanelSize::Expanded);
+        assert_eq!(config.panel_dimensions.get(PanelSize::Expanded), (600.0, 800.0));
+        assert_eq!(config.panel_dimensions.get(PanelSize::Compact), (420.0, 400.0));
+    }
+
+    #[test]
+    fn test_window_positions_parsing() {
+        let config: Config =
+            serde_json::from_str(r#"{"window_positions": {"3f2a": [1490, 58]}}"#).unwrap();
+        assert_eq!(config.window_positions.get("3f2a"), Some(&(1490.0, 58.0)));
+        assert!(Config::default().window_positions.is_empty());
+    }
+
+    #[test]
+    fn test_brightness_hysteresis_parsing() {
+        let config: Config = serde_json::from_str(r#"{"brightness_hysteresis_ms": 0}"#).unwrap();
+        assert_eq!(config.brightness_hysteresis_ms, Some(0));
+        assert!(Config::default().brightness_hysteresis_ms.is_none());
+    }
+
+    #[test]
+    fn test_sink_limits_parsing() {
+        let config: Config =
+            serde_json::from_str(r#"{"sink_limits": [{"name": "headphones", "max": 80}]}"#)
+                .unwrap();
+        assert_eq!(
+            config.sink_limits,
+            vec![SinkLimit {
+                name: "headphones".to_string(),
+                max: 80
+            }]
+        );
+        assert!(Config::default().sink_limits.is_empty());
+    }
+}
@@ -0,0 +1,301 @@
+//! Config file hot-reload
+//!
+//! The config directory is watched with inotify, so the file is followed
+//! across editors that save by rename. Once events for the file have
+//! settled (none for `DEBOUNCE`, so editors saving in several steps trigger
+//! a single reload) the file is parsed and swapped into the `ConfigStore`,
+//! settings that apply live are re-applied and `config-reloaded` is
+//! emitted. A file that fails to parse is logged and the previous config
+//! kept. Writes made by the app itself through `ConfigStore::update`
+//! already match the config in use and are skipped. Where inotify is
+//! unavailable the modification time is polled instead.
+//!
+//! Auto brightness is started or stopped when `auto_brightness.enabled`
+//! changes. Settings only read at startup (`app_id`, `start_hidden`,
+//! `skip_warm_up`, the saved panel size) still need a restart.
+
+use crate::auto_brightness;
+use crate::command_log;
+use crate::commands::set_binary_paths;
+use crate::config::{Config, ConfigStore};
+use crate::log_buffer;
+use log::{debug, info, warn};
+use std::ffi::CString;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::unix::AsyncFd;
+
+/// Quiet time after the last event for the file before it is reloaded
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// How often the config file is checked for changes without inotify
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Events that can mean the config file was written, replaced or removed
+const WATCH_MASK: u32 = libc::IN_CLOSE_WRITE
+    | libc::IN_MOVED_TO
+    | libc::IN_MOVED_FROM
+    | libc::IN_CREATE
+    | libc::IN_DELETE;
+
+/// Reports a modification time change once it has stopped changing
+#[derive(Debug)]
+struct ChangeDebouncer {
+    /// Modification time of the version last loaded
+    seen: Option<SystemTime>,
+    /// Changed modification time waiting for one more poll
+    pending: Option<Option<SystemTime>>,
+}
+
+impl ChangeDebouncer {
+    fn new(seen: Option<SystemTime>) -> Self {
+        Self {
+            seen,
+            pending: None,
+        }
+    }
+
+    /// Feed the latest modification time; `true` when it is time to reload
+    fn update(&mut self, modified: Option<SystemTime>) -> bool {
+        if modified == self.seen {
+            self.pending = None;
+            return false;
+        }
+        if self.pending == Some(modified) {
+            self.seen = modified;
+            self.pending = None;
+            return true;
+        }
+        self.pending = Some(modified);
+        false
+    }
+}
+
+/// Non-blocking inotify instance watching one directory
+struct DirWatch {
+    fd: AsyncFd<OwnedFd>,
+}
+
+impl DirWatch {
+    fn new(dir: &Path) -> io::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let dir = CString::new(dir.as_os_str().as_bytes())?;
+
+        // SAFETY: a plain syscall; the descriptor is owned from here on
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` was just returned by inotify_init1 and nothing else owns it
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        // SAFETY: `dir` is a valid NUL-terminated path for the duration of the call
+        if unsafe { libc::inotify_add_watch(fd.as_raw_fd(), dir.as_ptr(), WATCH_MASK) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            fd: AsyncFd::new(fd)?,
+        })
+    }
+
+    /// Wait for the next batch of events; `true` when one may concern `file`
+    async fn next(&self, file: &[u8]) -> io::Result<bool> {
+        let mut buf = [0u8; 4096];
+        loop {
+            let mut guard = self.fd.readable().await?;
+            let read = guard.try_io(|fd| {
+                // SAFETY: `buf` is valid for writes of `buf.len()` bytes
+                let n = unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            });
+            if let Ok(read) = read {
+                let n = read?;
+                return Ok(event_names(&buf[..n])
+                    .iter()
+                    .any(|name| name.is_empty() || *name == file));
+            }
+        }
+    }
+}
+
+/// File names of the `inotify_event` records in `buf`
+///
+/// A record without a name (a queue overflow) yields an empty name.
+fn event_names(buf: &[u8]) -> Vec<&[u8]> {
+    const HEADER: usize = std::mem::size_of::<libc::inotify_event>();
+    let mut names = Vec::new();
+    let mut offset = 0;
+    while offset + HEADER <= buf.len() {
+        // `len` is the last field of the header
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&buf[offset + HEADER - 4..offset + HEADER]);
+        let start = offset + HEADER;
+        let Some(name) = buf.get(start..start + u32::from_ne_bytes(len) as usize) else {
+            break;
+        };
+        let end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        names.push(&name[..end]);
+        offset = start + name.len();
+    }
+    names
+}
+
+/// Start watching the config file
+pub fn start(app: AppHandle) {
+    let path = Config::path();
+    let watch = path
+        .parent()
+        .ok_or_else(|| io::Error::other("config path has no directory"))
+        .and_then(DirWatch::new);
+    debug!("Watching {} for changes", path.display());
+
+    tauri::async_runtime::spawn(async move {
+        let error = match watch {
+            Ok(watch) => watch_events(&app, &watch, &path).await.unwrap_err(),
+            Err(e) => e,
+        };
+        warn!("Cannot watch the config ({}), polling it instead", error);
+        poll(&app, &path).await;
+    });
+}
+
+/// Reload the config each time events for it have settled; only returns on error
+async fn watch_events(app: &AppHandle, watch: &DirWatch, path: &Path) -> io::Result<()> {
+    let file = path.file_name().map(OsStrExt::as_bytes).unwrap_or_default();
+    loop {
+        if !watch.next(file).await? {
+            continue;
+        }
+        let mut deadline = tokio::time::Instant::now() + DEBOUNCE;
+        loop {
+            match tokio::time::timeout_at(deadline, watch.next(file)).await {
+                Err(_) => break,
+                Ok(Ok(true)) => deadline = tokio::time::Instant::now() + DEBOUNCE,
+                Ok(Ok(false)) => {}
+                Ok(Err(e)) => return Err(e),
+            }
+        }
+        reload(app);
+    }
+}
+
+async fn poll(app: &AppHandle, path: &Path) {
+    let mut debouncer = ChangeDebouncer::new(modified(path));
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if debouncer.update(modified(path)) {
+            reload(app);
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// Load the changed config file, keeping the current config if it is invalid
+fn reload(app: &AppHandle) {
+    let store = app.state::<ConfigStore>();
+    let config = match Config::read() {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Not reloading config, keeping the previous one: {}", e);
+            return;
+        }
+    };
+    let previous = store.get();
+    if same_config(&config, &previous) {
+        debug!("Config file changed but matches the config in use");
+        return;
+    }
+
+    set_binary_paths(&config.binaries);
+    command_log::set_slow_threshold(config.slow_command_ms);
+    log_buffer::set_filter(config.log.as_deref());
+    store.replace(config.clone());
+
+    // Started after the swap so the task reads the new curve and interval
+    let was_enabled = previous.auto_brightness.enabled;
+    match (was_enabled, config.auto_brightness.enabled) {
+        (false, true) => {
+            if let Err(e) = auto_brightness::start(app.clone()) {
+                warn!("Auto brightness enabled but unavailable: {}", e);
+            }
+        }
+        (true, false) => auto_brightness::stop(),
+        _ => {}
+    }
+
+    info!("Reloaded config from {}", Config::path().display());
+    let _ = app.emit("config-reloaded", &config);
+}
+
+fn same_config(a: &Config, b: &Config) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_debouncer_waits_for_settled_change() {
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + Duration::from_secs(1);
+        let t2 = t0 + Duration::from_secs(2);
+        let mut debouncer = ChangeDebouncer::new(Some(t0));
+
+        assert!(!debouncer.update(Some(t0)));
+        assert!(!debouncer.update(Some(t1)));
+        // Saved again before settling
+        assert!(!debouncer.update(Some(t2)));
+        assert!(debouncer.update(Some(t2)));
+        assert!(!debouncer.update(Some(t2)));
+
+        // Deleting the file is a change too
+        assert!(!debouncer.update(None));
+        assert!(debouncer.update(None));
+    }
+
+    #[test]
+    fn test_event_names() {
+        fn record(name: &[u8], padded: usize) -> Vec<u8> {
+            let mut record = vec![0u8; 12];
+            record.extend((padded as u32).to_ne_bytes());
+            record.extend(name);
+            record.resize(16 + padded, 0);
+            record
+        }
+        let mut buf = record(b"config.json", 16);
+        buf.extend(record(b"", 0));
+        buf.extend(record(b".config.json.swp", 32));
+
+        let names = event_names(&buf);
+        assert_eq!(names, [&b"config.json"[..], b"", b".config.json.swp"]);
+        // A truncated record is ignored
+        assert_eq!(event_names(&buf[..20]), Vec::<&[u8]>::new());
+    }
+
+    #[test]
+    fn test_same_config() {
+        let config = Config::default();
+        let changed = Config {
+            smooth_mute: true,
+            ..Config::default()
+        };
+        assert!(same_config(&config, &Config::default()));
+        assert!(!same_config(&config, &changed));
+    }
+}
@@ -28,6 +28,12 @@ pub enum ControlCentreError {
     
     #[error("Timeout: {0}")]
     Timeout(String),
+    
+    #[error("Device not found: {0}")]
+    DeviceNotFound(String),
+    
+    #[error("Audio unavailable: {0}")]
+    AudioUnavailable(String),
 }
 
 impl From<std::io::Error> for ControlCentreError {
@@ -0,0 +1,132 @@
+//! Logging with a copy of the most recent lines kept in memory
+//!
+//! Records go to env_logger as usual; the last `CAPACITY` that pass its
+//! filter are also kept so `export_diagnostics` can include them without
+//! the app writing a log file. The filter can be replaced at runtime when
+//! the config file is reloaded.
+
+use log::{Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::Instant;
+
+/// Number of log lines kept
+const CAPACITY: usize = 200;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// The installed logger, kept to swap its filter in `set_filter`
+static LOGGER: OnceLock<&'static BufferedLogger> = OnceLock::new();
+
+struct BufferedLogger {
+    inner: RwLock<env_logger::Logger>,
+    started: Instant,
+}
+
+impl Log for BufferedLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner
+            .read()
+            .map(|inner| inner.enabled(metadata))
+            .unwrap_or(false)
+    }
+
+    fn log(&self, record: &Record) {
+        let Ok(inner) = self.inner.read() else {
+            return;
+        };
+        if !inner.matches(record) {
+            return;
+        }
+
+        push_line(format!(
+            "[+{:.3}s {} {}] {}",
+            self.started.elapsed().as_secs_f64(),
+            record.level(),
+            record.target(),
+            record.args()
+        ));
+        inner.log(record);
+    }
+
+    fn flush(&self) {
+        if let Ok(inner) = self.inner.read() {
+            inner.flush();
+        }
+    }
+}
+
+/// Filter used when neither `RUST_LOG` nor the config sets one
+const DEFAULT_FILTER: &str = "info";
+
+/// Install the logger
+///
+/// `filter` uses the `RUST_LOG` syntax, e.g.
+/// `control_centre::commands=debug,info`; `RUST_LOG` itself still takes
+/// precedence, and without either only `info` and above is logged.
+pub fn init(filter: Option<&str>) {
+    let inner = build(filter);
+    let max_level = inner.filter();
+    let logger: &'static BufferedLogger = Box::leak(Box::new(BufferedLogger {
+        inner: RwLock::new(inner),
+        started: Instant::now(),
+    }));
+
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(max_level);
+        let _ = LOGGER.set(logger);
+    }
+}
+
+/// Replace the filter given to `init`, with the same precedence rules
+pub fn set_filter(filter: Option<&str>) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    let inner = build(filter);
+    let max_level = inner.filter();
+
+    if let Ok(mut current) = logger.inner.write() {
+        *current = inner;
+        log::set_max_level(max_level);
+    }
+}
+
+fn build(filter: Option<&str>) -> env_logger::Logger {
+    let env = env_logger::Env::default().default_filter_or(filter.unwrap_or(DEFAULT_FILTER));
+    env_logger::Builder::from_env(env).build()
+}
+
+fn push_line(line: String) {
+    if let Ok(mut recent) = RECENT.lock() {
+        if recent.len() == CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    }
+}
+
+/// The most recent log lines, oldest first
+pub fn recent() -> Vec<String> {
+    RECENT
+        .lock()
+        .map(|recent| recent.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_line_keeps_latest() {
+        for i in 0..CAPACITY + 5 {
+            push_line(format!("line {}", i));
+        }
+
+        let recent = recent();
+        assert_eq!(recent.len(), CAPACITY);
+        assert_eq!(recent.first().map(String::as_str), Some("line 5"));
+        assert_eq!(recent.last(), Some(&format!("line {}", CAPACITY + 4)));
+    }
+}
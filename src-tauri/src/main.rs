@@ -19,110 +19,394 @@
     windows_subsystem = "windows"
 )]
 
+mod audio_monitor;
+mod auto_brightness;
+mod battery_monitor;
+mod command_log;
 mod commands;
+mod config;
+mod config_watcher;
 mod error;
+mod log_buffer;
+mod niri;
+mod pactl;
 mod state;
 
 use log::{debug, error, info, warn};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
 use tauri::{Emitter, Manager, RunEvent, WebviewWindow, WindowEvent};
 
 use commands::*;
+use config::{Config, ConfigStore, PanelSize};
 use state::AppState;
 
 /// Global flag for window visibility state
 static WINDOW_VISIBLE: AtomicBool = AtomicBool::new(false);
 
+/// App-id the compositor sees for the window, resolved at startup
+static APP_ID: OnceLock<String> = OnceLock::new();
+
+/// Whether the IPC socket is bound and accepting toggle signals
+static SOCKET_LISTENING: AtomicBool = AtomicBool::new(false);
+
+/// Set on exit to make the socket listener loop return
+static SOCKET_SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+/// Socket listener thread, joined on exit
+static SOCKET_THREAD: Mutex<Option<std::thread::JoinHandle<()>>> = Mutex::new(None);
+
+/// Socket the listener actually bound, `SOCKET_PATH` or the per-user fallback
+static SOCKET_BOUND: Mutex<Option<PathBuf>> = Mutex::new(None);
+
 /// Socket path for IPC between instances
 const SOCKET_PATH: &str = "/tmp/control-centre.sock";
 
+/// Command-line flag to start with the window loaded but hidden
+const PRELOAD_FLAG: &str = "--preload";
+
+/// Grace period before hiding on focus loss
+const FOCUS_LOSS_HIDE_DELAY: Duration = Duration::from_millis(100);
+
+/// Number of attempts to bind the IPC socket before giving up
+const SOCKET_BIND_ATTEMPTS: u32 = 5;
+
+/// Initial delay between socket bind attempts (doubles after each failure)
+const SOCKET_BIND_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Commands accepted over the IPC socket, one per line
+const SOCKET_COMMANDS: [&str; 2] = ["toggle", "panic-mute"];
+
+/// Longest socket message line accepted; longer lines are dropped
+const SOCKET_MAX_LINE: usize = 256;
+
+/// Bytes read from one socket connection at most
+const SOCKET_MAX_READ: u64 = 4096;
+
+/// How long a client may take to send its messages before being dropped
+const SOCKET_READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long startup waits for `frontend_ready` before showing the window anyway
+const FRONTEND_READY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Number of attempts to read monitor info before settling for the fallback width
+const MONITOR_LOOKUP_ATTEMPTS: u32 = 3;
+
+/// Delay between monitor info attempts
+const MONITOR_LOOKUP_DELAY: Duration = Duration::from_millis(50);
+
+/// How often the connected monitors are checked for a hot-plug
+const ARRANGEMENT_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Logical screen width assumed when no monitor info is available and the
+/// config does not set one
+const DEFAULT_FALLBACK_SCREEN_WIDTH: f64 = 1920.0;
+
+/// Gap above the window: waybar height (~48px) plus padding (~10px)
+const WINDOW_MARGIN_TOP: f64 = 58.0;
+
+/// Gap between the window and the right screen edge
+const WINDOW_MARGIN_RIGHT: f64 = 10.0;
+
 fn main() {
-    // Initialize logging
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    // Initialize logging, with the per-module filter from the config file
+    log_buffer::init(Config::log_filter().as_deref());
+
+    // Headless subcommands run without the GUI
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("get-state") {
+        let json = args.iter().any(|arg| arg == "--json");
+        std::process::exit(run_get_state(json));
+    }
 
     info!("Starting Control Centre for Niri Wayland");
 
+    let preload = std::env::args().any(|arg| arg == PRELOAD_FLAG);
+
     // Check for existing instance via socket
     if is_instance_running() {
-        info!("Another instance is running, sending toggle signal");
-        send_toggle_signal();
+        if preload {
+            info!("Another instance is running, nothing to preload");
+        } else {
+            info!("Another instance is running, sending toggle signal");
+            send_toggle_signal();
+        }
         return;
     }
 
+    let config = Config::load();
+    set_binary_paths(&config.binaries);
+    command_log::set_slow_threshold(config.slow_command_ms);
+    let start_hidden = preload || config.start_hidden;
+    let auto_brightness_enabled = config.auto_brightness.enabled;
+    let warm_up = !config.skip_warm_up;
+
+    // Resolve the app-id before GTK starts so window rules can match it
+    let mut context = tauri::generate_context!();
+    let app_id = resolve_app_id(&mut context, &config);
+    info!(
+        "Window app-id is \"{}\" (use `match app-id=\"{}\"` in Niri window rules)",
+        app_id, app_id
+    );
+    let _ = APP_ID.set(app_id);
+
     // Build Tauri application
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .manage(AppState::new())
+        .manage(ConfigStore::new(config))
         .invoke_handler(tauri::generate_handler![
             // Audio commands
             get_volume,
             set_volume,
+            adjust_volume,
+            nudge_volume,
+            duck_volume,
+            unduck_volume,
+            get_volume_db,
+            set_volume_db,
             toggle_mute,
+            set_mute,
             get_mute_status,
+            get_default_sink_name,
+            get_default_source_name,
+            restart_audio_service,
+            cycle_default_sink,
+            toggle_audio_output,
+            list_stream_routes,
+            move_stream,
+            list_audio_clients,
+            kill_audio_client,
+            get_sink_info,
+            get_sink_format,
+            get_sink_limits,
+            set_sink_port,
+            snapshot_audio,
+            restore_audio,
+            save_audio_snapshot,
+            load_audio_snapshot,
+            panic_mute,
+            panic_unmute,
+            get_mic_level,
+            start_mic_monitor,
+            stop_mic_monitor,
+            toggle_mic_mute_with_led,
+            set_source_mute,
             // Brightness commands
             get_brightness,
             set_brightness,
+            adjust_brightness,
+            nudge_brightness,
+            set_auto_brightness,
+            get_auto_brightness,
             get_max_brightness,
+            get_external_brightness,
+            toggle_flashlight,
+            get_kbd_rgb,
+            set_kbd_rgb,
             // Network commands
             get_wifi_status,
             set_wifi_enabled,
+            get_metered,
+            set_metered,
+            get_autoconnect,
+            set_autoconnect,
+            get_wifi_powersave,
+            set_wifi_powersave,
+            get_primary_connection,
             get_bluetooth_status,
             set_bluetooth_enabled,
+            set_bluetooth_discoverable,
+            set_bluetooth_pairable,
+            get_recent_bluetooth_devices,
+            connect_bluetooth_device,
+            get_airplane_mode,
+            set_airplane_mode,
             // Display commands
             get_night_light_status,
+            get_night_light_temperature,
             set_night_light_enabled,
+            set_night_light_for_output,
+            get_night_light_backend,
+            set_night_light_times,
+            resolve_night_light_conflict,
+            get_color_scheme,
+            set_color_scheme,
+            get_window_opacity,
+            set_window_opacity,
+            detect_compositor,
+            get_display_modes,
+            set_display_mode,
+            set_output_enabled,
+            set_display_arrangement,
+            // Input commands
+            get_keyboard_repeat,
+            set_keyboard_repeat,
+            get_pointer_speed,
+            set_pointer_speed,
+            // Wallpaper commands
+            get_wallpaper,
+            set_wallpaper,
+            // Notification commands
+            get_notification_history,
+            dismiss_all_notifications,
+            get_notification_mode,
+            set_notification_mode,
+            // System commands
+            get_system_overview,
+            get_time_sync_status,
+            set_ntp,
+            get_service_status,
+            set_service,
             // Power commands
             suspend_system,
+            request_power_action,
+            confirm_power_action,
+            get_inhibitors,
             // State commands
             get_all_states,
+            invalidate_cache,
+            get_fast_states,
+            reset_defaults,
+            get_enabled_cards,
+            get_capabilities,
+            export_diagnostics,
+            get_command_log,
+            get_backend_timings,
+            check_for_update,
+            start_polling,
+            stop_polling,
             // Window commands
             toggle_window,
             close_window,
             position_window,
+            ensure_floating,
+            set_panel_size,
+            restart_app,
+            get_app_id,
+            get_socket_listener_status,
+            frontend_ready,
+            child_popup_opened,
+            child_popup_closed,
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            command_log::attach(app.handle().clone());
             let window = app
                 .get_webview_window("main")
                 .expect("Failed to get main window");
 
             // Configure window for Niri Wayland
             setup_window_for_niri(&window);
+            if let Err(e) = resize_panel(&window) {
+                warn!("Failed to apply the saved panel size: {}", e);
+            }
 
             // Start socket listener for toggle signals from other instances
-            let window_for_socket = window.clone();
-            std::thread::spawn(move || {
-                start_socket_listener(window_for_socket);
+            spawn_socket_listener(window.clone());
+
+            let state = app.state::<AppState>();
+            info!("Compositor: {:?}", state.compositor());
+            let radios = state.radio_backends();
+            info!("Radio backends: WiFi {:?}, Bluetooth {:?}", radios.wifi, radios.bluetooth);
+
+            audio_monitor::start(app.handle().clone());
+            battery_monitor::start(app.handle().clone());
+            config_watcher::start(app.handle().clone());
+
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                restore_interrupted_flashlight(&app_handle.state::<AppState>()).await;
             });
+            spawn_arrangement_keeper(window.clone());
+
+            if warm_up {
+                warm_up_caches(app.handle().clone());
+            }
 
-            // Default: show window on startup
-            show_window(&window);
+            if auto_brightness_enabled {
+                if let Err(e) = auto_brightness::start(app.handle().clone()) {
+                    warn!("Auto brightness enabled but unavailable: {}", e);
+                }
+            }
+
+            // Warm start: keep the webview loaded but hidden so the first
+            // toggle is a plain show instead of a cold render
+            if start_hidden {
+                info!("Started hidden, waiting for toggle signal");
+            } else {
+                // Show only once the webview has built the panel to avoid an empty flash
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    let state = app_handle.state::<AppState>();
+                    let ready = state.frontend_ready().notified();
+                    let timed_out = tokio::time::timeout(FRONTEND_READY_TIMEOUT, ready)
+                        .await
+                        .is_err();
+                    if timed_out {
+                        debug!("Frontend not ready in time, showing anyway");
+                    }
+                    if let Err(e) = show_window(&window) {
+                        warn!("Failed to show window: {}", e);
+                    }
+                });
+            }
 
             Ok(())
         })
-        .build(tauri::generate_context!())
+        .build(context)
         .expect("Error building Tauri application");
 
     // Run the application
     app.run(|app_handle, event| {
         match event {
-            RunEvent::WindowEvent { label, event, .. } => {
-                if label == "main" {
-                    match event {
-                        WindowEvent::CloseRequested { api, .. } => {
-                            // Prevent actual close, just hide
-                            api.prevent_close();
-                            if let Some(window) = app_handle.get_webview_window("main") {
-                                let _ = window.hide();
-                                WINDOW_VISIBLE.store(false, Ordering::SeqCst);
+            RunEvent::WindowEvent { label, event, .. } if label == "main" => match event {
+                // A focused window is on screen, whoever showed it
+                WindowEvent::Focused(true) => {
+                    WINDOW_VISIBLE.store(true, Ordering::SeqCst);
+                }
+                // On Niri, losing focus typically means user clicked outside;
+                // hiding then is opt-in
+                WindowEvent::Focused(false)
+                    if app_handle.state::<ConfigStore>().get().hide_on_focus_loss =>
+                {
+                    if let Some(window) = app_handle
+                        .get_webview_window("main")
+                        .filter(is_window_visible)
+                    {
+                        // Small delay so transient focus changes don't hide the window
+                        std::thread::spawn(move || {
+                            std::thread::sleep(FOCUS_LOSS_HIDE_DELAY);
+                            if focus_left_app(&window) {
+                                let _ = hide_window(&window);
                             }
-                        }
-                        _ => {}
+                        });
                     }
                 }
-            }
+                WindowEvent::CloseRequested { api, .. } => {
+                    // Prevent actual close, just hide
+                    api.prevent_close();
+                    if let Some(window) = app_handle.get_webview_window("main") {
+                        let _ = hide_window(&window);
+                    }
+                }
+                _ => {}
+            },
+            // Child windows (dialogs, popups) keep the panel up while open
+            RunEvent::WindowEvent { label, event, .. } => match event {
+                WindowEvent::Focused(true) => {
+                    app_handle.state::<AppState>().add_child_window(&label);
+                }
+                WindowEvent::Destroyed => {
+                    app_handle.state::<AppState>().remove_child_window(&label);
+                }
+                _ => {}
+            },
             RunEvent::ExitRequested { .. } => {
-                // Clean up socket on exit
-                let _ = std::fs::remove_file(SOCKET_PATH);
+                pause_polling(&app_handle.state::<AppState>());
+                stop_mic_monitor_internal(&app_handle.state::<AppState>());
+                release_socket();
                 info!("Application exiting, socket cleaned up");
             }
             _ => {}
@@ -130,14 +414,105 @@ fn main() {
     });
 }
 
+// ============================================================================
+// Command-Line Interface
+// ============================================================================
+
+/// Print the aggregate state to stdout and return the process exit code
+///
+/// Runs the backend queries on a minimal Tokio runtime without creating a
+/// window. Exits nonzero if any backend query failed.
+fn run_get_state(json: bool) -> i32 {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {}", e);
+            return 1;
+        }
+    };
+
+    let config = Config::load();
+    set_binary_paths(&config.binaries);
+    let all = runtime.block_on(get_all_states_internal(&AppState::new(), &config));
+    let errors = all.errors();
+
+    if json {
+        match serde_json::to_string(&all) {
+            Ok(output) => println!("{}", output),
+            Err(e) => {
+                eprintln!("Failed to serialize state: {}", e);
+                return 1;
+            }
+        }
+    } else {
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+        if let Some(volume) = &all.volume.value {
+            let muted = if volume.muted { " (muted)" } else { "" };
+            println!("volume: {}%{}", volume.volume, muted);
+        }
+        if let Some(brightness) = &all.brightness.value {
+            println!("brightness: {}%", brightness.brightness);
+        }
+        if let Some(network) = &all.network.value {
+            match &network.wifi_ssid {
+                Some(ssid) if network.wifi_connected => println!("wifi: on ({})", ssid),
+                _ => println!("wifi: {}", on_off(network.wifi_enabled)),
+            }
+            match network.bluetooth_battery {
+                Some(battery) if network.bluetooth_enabled => {
+                    println!("bluetooth: on (battery {}%)", battery)
+                }
+                _ => println!("bluetooth: {}", on_off(network.bluetooth_enabled)),
+            }
+        }
+        if let Some(display) = &all.display.value {
+            println!("night light: {}", on_off(display.night_light_enabled));
+        }
+    }
+
+    for e in &errors {
+        eprintln!("error: {}", e);
+    }
+
+    if errors.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
 // ============================================================================
 // Instance Management
 // ============================================================================
 
+/// Socket used when `SOCKET_PATH` belongs to another user
+///
+/// Named after our UID so each user gets their own; the PID stands in if
+/// the UID cannot be read, which at least keeps this instance working.
+fn fallback_socket_path() -> PathBuf {
+    let id = current_uid().unwrap_or_else(std::process::id);
+    PathBuf::from(format!("/tmp/control-centre-{}.sock", id))
+}
+
+fn current_uid() -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata("/proc/self").ok().map(|meta| meta.uid())
+}
+
+/// Sockets a running instance may listen on, in the order they are tried
+fn socket_candidates() -> [PathBuf; 2] {
+    [PathBuf::from(SOCKET_PATH), fallback_socket_path()]
+}
+
 /// Check if another instance is already running
 fn is_instance_running() -> bool {
     use std::os::unix::net::UnixStream;
-    UnixStream::connect(SOCKET_PATH).is_ok()
+    socket_candidates()
+        .iter()
+        .any(|path| UnixStream::connect(path).is_ok())
 }
 
 /// Send toggle signal to running instance via Unix socket
@@ -145,56 +520,320 @@ fn send_toggle_signal() {
     use std::io::Write;
     use std::os::unix::net::UnixStream;
 
-    match UnixStream::connect(SOCKET_PATH) {
-        Ok(mut stream) => {
-            let _ = stream.write_all(b"toggle");
+    let stream = socket_candidates()
+        .iter()
+        .find_map(|path| UnixStream::connect(path).ok());
+    match stream {
+        Some(mut stream) => {
+            let _ = stream.write_all(b"toggle\n");
             info!("Sent toggle signal to running instance");
         }
-        Err(e) => {
-            warn!("Failed to connect to socket: {}", e);
+        None => {
+            warn!("Failed to connect to socket {}", SOCKET_PATH);
+        }
+    }
+}
+
+/// Remove the socket file if no process is listening on it
+fn remove_stale_socket(path: &Path) {
+    use std::os::unix::net::UnixStream;
+
+    if std::fs::symlink_metadata(path).is_err() {
+        return;
+    }
+
+    if UnixStream::connect(path).is_ok() {
+        warn!("Socket {} is in use by another process", path.display());
+        return;
+    }
+
+    match std::fs::remove_file(path) {
+        Ok(()) => debug!("Removed stale socket {}", path.display()),
+        Err(e) => warn!("Failed to remove stale socket {}: {}", path.display(), e),
+    }
+}
+
+/// Bind the IPC socket, falling back to the per-user socket if needed
+fn bind_socket_listener() -> Option<(std::os::unix::net::UnixListener, PathBuf)> {
+    use std::os::unix::net::UnixListener;
+
+    bind_with_fallback(Path::new(SOCKET_PATH), &fallback_socket_path(), |path| {
+        remove_stale_socket(path);
+        UnixListener::bind(path)
+    })
+}
+
+/// Bind `primary`, retrying with exponential backoff
+///
+/// A `primary` owned by another user (in sticky `/tmp` it can be neither
+/// removed nor replaced) will not go away by retrying, so `fallback` is
+/// bound instead. Returns the listener and the path it is bound to.
+fn bind_with_fallback<L>(
+    primary: &Path,
+    fallback: &Path,
+    mut bind: impl FnMut(&Path) -> std::io::Result<L>,
+) -> Option<(L, PathBuf)> {
+    let mut path = primary;
+    let mut delay = SOCKET_BIND_RETRY_DELAY;
+
+    for attempt in 1..=SOCKET_BIND_ATTEMPTS {
+        match bind(path) {
+            Ok(listener) => return Some((listener, path.to_path_buf())),
+            Err(e) if path == primary && is_ownership_conflict(path, &e) => {
+                error!(
+                    "Socket {} belongs to another user ({}); listening on the per-user socket {} \
+                     instead. Toggle scripts must use that path until the other user's \
+                     instance exits",
+                    primary.display(),
+                    e,
+                    fallback.display()
+                );
+                path = fallback;
+                continue;
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to bind socket {} (attempt {}/{}): {}",
+                    path.display(),
+                    attempt,
+                    SOCKET_BIND_ATTEMPTS,
+                    e
+                );
+            }
         }
+
+        if attempt < SOCKET_BIND_ATTEMPTS {
+            std::thread::sleep(delay);
+            delay *= 2;
+        }
+    }
+
+    None
+}
+
+/// Whether a bind error comes from `path` being another user's socket
+fn is_ownership_conflict(path: &Path, error: &std::io::Error) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match error.kind() {
+        std::io::ErrorKind::PermissionDenied => true,
+        std::io::ErrorKind::AddrInUse => std::fs::symlink_metadata(path)
+            .is_ok_and(|meta| current_uid().is_some_and(|uid| meta.uid() != uid)),
+        _ => false,
+    }
+}
+
+/// Run the socket listener on its own thread, kept for `release_socket`
+fn spawn_socket_listener(window: WebviewWindow) {
+    SOCKET_SHUTDOWN.store(false, Ordering::SeqCst);
+    let handle = std::thread::spawn(move || {
+        start_socket_listener(window);
+    });
+    if let Ok(mut thread) = SOCKET_THREAD.lock() {
+        *thread = Some(handle);
+    }
+}
+
+/// Stop the socket listener and remove its socket if it was ours
+///
+/// Safe to call more than once; later calls find nothing to clean up.
+fn release_socket() {
+    let was_listening = SOCKET_LISTENING.load(Ordering::SeqCst);
+    let bound = SOCKET_BOUND.lock().ok().and_then(|mut path| path.take());
+    let socket = bound.unwrap_or_else(|| PathBuf::from(SOCKET_PATH));
+
+    let handle = SOCKET_THREAD.lock().ok().and_then(|mut thread| thread.take());
+    if let Some(handle) = handle {
+        stop_socket_listener(&socket, &SOCKET_SHUTDOWN, handle);
+    }
+
+    if was_listening {
+        let _ = std::fs::remove_file(&socket);
     }
 }
 
 /// Start Unix socket listener for IPC
+///
+/// Runs until `SOCKET_SHUTDOWN` is set by `stop_socket_listener`.
 fn start_socket_listener(window: WebviewWindow) {
-    use std::io::Read;
-    use std::os::unix::net::UnixListener;
+    let Some((listener, path)) = bind_socket_listener() else {
+        error!(
+            "Cannot listen on {}: launching control-centre again will not toggle this window",
+            SOCKET_PATH
+        );
+        SOCKET_LISTENING.store(false, Ordering::SeqCst);
+        let _ = window.emit("socket-listener-failed", SOCKET_PATH);
+        return;
+    };
 
-    // Remove old socket if exists
-    let _ = std::fs::remove_file(SOCKET_PATH);
-
-    match UnixListener::bind(SOCKET_PATH) {
-        Ok(listener) => {
-            info!("Socket listener started at {}", SOCKET_PATH);
-
-            for stream in listener.incoming() {
-                match stream {
-                    Ok(mut stream) => {
-                        let mut buf = [0u8; 32];
-                        if let Ok(n) = stream.read(&mut buf) {
-                            let msg = String::from_utf8_lossy(&buf[..n]);
-                            if msg.trim() == "toggle" {
-                                toggle_window_visibility(&window);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Socket accept error: {}", e);
+    SOCKET_LISTENING.store(true, Ordering::SeqCst);
+    info!("Socket listener started at {}", path.display());
+    if let Ok(mut bound) = SOCKET_BOUND.lock() {
+        *bound = Some(path);
+    }
+
+    serve_socket(&listener, &SOCKET_SHUTDOWN, |msg| match msg {
+        "toggle" => {
+            if let Err(e) = toggle_window_visibility(&window) {
+                warn!("Failed to toggle window: {}", e);
+            }
+        }
+        "panic-mute" => {
+            let app = window.app_handle().clone();
+            tauri::async_runtime::spawn(async move {
+                match toggle_panic_mute(&app.state::<AppState>()).await {
+                    Ok(result) => info!(
+                        "Panic mute toggled (sink: {}, source: {}, streams: {})",
+                        result.sink, result.source, result.streams
+                    ),
+                    Err(e) => warn!("Failed to toggle panic mute: {}", e),
+                }
+            });
+        }
+        _ => {}
+    });
+
+    SOCKET_LISTENING.store(false, Ordering::SeqCst);
+    info!("Socket listener stopped");
+}
+
+/// Accept connections until `shutdown` is set, passing each known command on
+///
+/// Unknown commands are logged and dropped, as are the malformed lines
+/// skipped by `read_socket_messages`.
+fn serve_socket(
+    listener: &std::os::unix::net::UnixListener,
+    shutdown: &AtomicBool,
+    mut on_message: impl FnMut(&str),
+) {
+    for stream in listener.incoming() {
+        if shutdown.load(Ordering::SeqCst) {
+            break;
+        }
+
+        match stream {
+            Ok(stream) => {
+                // A client that never finishes must not stall the listener
+                if let Err(e) = stream.set_read_timeout(Some(SOCKET_READ_TIMEOUT)) {
+                    warn!("Failed to set socket read timeout: {}", e);
+                    continue;
+                }
+                for msg in read_socket_messages(stream) {
+                    if SOCKET_COMMANDS.contains(&msg.as_str()) {
+                        on_message(&msg);
+                    } else {
+                        warn!("Ignoring unknown socket command {:?}", msg);
                     }
                 }
             }
+            Err(e) => {
+                error!("Socket accept error: {}", e);
+            }
         }
-        Err(e) => {
-            error!("Failed to bind socket: {}", e);
+    }
+}
+
+/// Split a connection's input into trimmed, non-empty lines
+///
+/// At most `SOCKET_MAX_READ` bytes are read. Lines longer than
+/// `SOCKET_MAX_LINE`, cut off by that limit or not valid UTF-8 are
+/// skipped; a read error or timeout ends the connection with the lines
+/// read so far.
+fn read_socket_messages(stream: impl std::io::Read) -> Vec<String> {
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = BufReader::new(stream.take(SOCKET_MAX_READ));
+    let mut messages = Vec::new();
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                debug!("Socket read ended: {}", e);
+                break;
+            }
+        }
+
+        let terminated = line.last() == Some(&b'\n');
+        if !terminated && reader.get_ref().limit() == 0 {
+            warn!(
+                "Ignoring socket message cut off at {} bytes",
+                SOCKET_MAX_READ
+            );
+            break;
+        }
+        if line.len() > SOCKET_MAX_LINE + usize::from(terminated) {
+            warn!("Ignoring oversized socket message ({} bytes)", line.len());
+            continue;
+        }
+
+        match std::str::from_utf8(&line) {
+            Ok(msg) if !msg.trim().is_empty() => messages.push(msg.trim().to_string()),
+            Ok(_) => {}
+            Err(_) => warn!("Ignoring socket message that is not valid UTF-8"),
         }
     }
+
+    messages
+}
+
+/// Signal the listener thread to stop and wait for it to exit
+fn stop_socket_listener(
+    path: impl AsRef<Path>,
+    shutdown: &AtomicBool,
+    handle: std::thread::JoinHandle<()>,
+) {
+    use std::os::unix::net::UnixStream;
+
+    shutdown.store(true, Ordering::SeqCst);
+
+    // accept() only returns on a new connection, so make a throwaway one
+    let _ = UnixStream::connect(path);
+
+    if handle.join().is_err() {
+        warn!("Socket listener thread panicked");
+    }
 }
 
 // ============================================================================
 // Window Management
 // ============================================================================
 
+/// Determine the app-id (Wayland) / WM_CLASS (X11) the window will carry
+///
+/// A valid `app_id` from the config is applied by switching GTK to use the
+/// bundle identifier as its application id. Without it, GTK falls back to
+/// the program name, which is what most installs end up matching on.
+fn resolve_app_id(context: &mut tauri::Context, config: &Config) -> String {
+    if let Some(id) = &config.app_id {
+        if config::is_valid_app_id(id) {
+            context.config_mut().identifier = id.clone();
+            context.config_mut().app.enable_gtk_app_id = true;
+        } else {
+            warn!(
+                "Ignoring app_id override \"{}\": not a valid application id (e.g. com.example.ControlCentre)",
+                id
+            );
+        }
+    }
+
+    if context.config().app.enable_gtk_app_id {
+        return context.config().identifier.clone();
+    }
+
+    std::env::args()
+        .next()
+        .and_then(|arg0| {
+            std::path::Path::new(&arg0)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "control-centre".to_string())
+}
+
 /// Configure window appearance for Niri Wayland overlay behavior
 fn setup_window_for_niri(window: &WebviewWindow) {
     // Set window to be transparent and frameless (configured in tauri.conf.json)
@@ -214,48 +853,256 @@ fn setup_window_for_niri(window: &WebviewWindow) {
 }
 
 /// Show window and update visibility state
-fn show_window(window: &WebviewWindow) {
-    // Position window in top-right corner (with offset for waybar and margins)
-    // Get the primary monitor's size
-    if let Some(monitor) = window.primary_monitor().ok().flatten() {
-        let screen_size = monitor.size();
-        let scale = monitor.scale_factor();
-        
-        // Window dimensions (from tauri.conf.json)
-        let window_width = 420.0;
-        let window_height = 400.0;
-        
-        // Calculate top-right position with margins
-        // Account for waybar height (~48px) and some padding (~10px)
-        let margin_top = 58.0;  // waybar height + padding
-        let margin_right = 10.0;
-        
-        let x = (screen_size.width as f64 / scale) - window_width - margin_right;
-        let y = margin_top;
-        
-        let _ = window.set_position(tauri::Position::Logical(
-            tauri::LogicalPosition::new(x, y)
-        ));
-        debug!("Window positioned at ({}, {})", x, y);
-    }
-    
-    let _ = window.show();
+///
+/// Under Niri the window is also moved to the floating layout if it was
+/// opened tiled, for setups without the window rule.
+fn show_window(window: &WebviewWindow) -> tauri::Result<()> {
+    anchor_window(window);
+
+    window.show()?;
     let _ = window.set_focus();
     WINDOW_VISIBLE.store(true, Ordering::SeqCst);
+    resume_polling(window.app_handle());
+
+    if window.state::<AppState>().capabilities().niri_ipc {
+        let app = window.app_handle().clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = ensure_floating_internal(&app.state::<AppState>()).await {
+                warn!("Failed to make the panel float: {}", e);
+            }
+        });
+    }
 
     // Emit event to frontend to refresh state
     let _ = window.emit("window-shown", ());
     debug!("Window shown");
+    Ok(())
+}
+
+/// Position the window where it was saved for the current monitor layout,
+/// or else in the top-right corner (with offset for waybar and margins)
+///
+/// The x position depends on the current panel width, so this runs again
+/// after every resize to keep the right edge in place.
+///
+/// Monitor info is often missing right after startup on Wayland. The
+/// window is then placed for the fallback width straight away and the
+/// lookup retried on the async runtime, so no caller is blocked waiting.
+fn anchor_window(window: &WebviewWindow) {
+    if !place_window(window) {
+        retry_anchor(window.clone());
+    }
 }
 
-/// Toggle window visibility
-fn toggle_window_visibility(window: &WebviewWindow) {
-    if WINDOW_VISIBLE.load(Ordering::SeqCst) {
-        let _ = window.hide();
-        WINDOW_VISIBLE.store(false, Ordering::SeqCst);
-        debug!("Window hidden via toggle");
+/// Retry `place_window` until monitor info shows up or the attempts run out
+fn retry_anchor(window: WebviewWindow) {
+    tauri::async_runtime::spawn(async move {
+        for attempt in 2..=MONITOR_LOOKUP_ATTEMPTS {
+            tokio::time::sleep(MONITOR_LOOKUP_DELAY).await;
+            if place_window(&window) {
+                debug!("Monitor info available on attempt {}", attempt);
+                return;
+            }
+        }
+        warn!(
+            "No monitor info after {} attempts, keeping the fallback position",
+            MONITOR_LOOKUP_ATTEMPTS
+        );
+    });
+}
+
+/// Move the window into place, returning `false` if the monitor size was
+/// unknown and the fallback width was used
+fn place_window(window: &WebviewWindow) -> bool {
+    let config = window.state::<ConfigStore>().get();
+    let (width, _) = config.panel_dimensions.get(config.panel_size);
+
+    if let Some(layout) = current_layout(window) {
+        if let Some(&(right, y)) = config.window_positions.get(&layout) {
+            // Saved by the right edge so a resize keeps the top-right corner
+            let x = right - width;
+            let _ =
+                window.set_position(tauri::Position::Logical(tauri::LogicalPosition::new(x, y)));
+            debug!(
+                "Window restored to ({}, {}) for monitor layout {}",
+                x, y, layout
+            );
+            return true;
+        }
+    }
+
+    let measured = logical_screen_width(window);
+    let screen_width = match measured {
+        Some(width) => {
+            debug!("Positioning from monitor info ({} logical px wide)", width);
+            width
+        }
+        None => {
+            let width = config
+                .fallback_screen_width
+                .unwrap_or(DEFAULT_FALLBACK_SCREEN_WIDTH);
+            debug!(
+                "Monitor info unavailable, positioning for a {}px wide screen",
+                width
+            );
+            width
+        }
+    };
+
+    let x = screen_width - width - WINDOW_MARGIN_RIGHT;
+    let y = WINDOW_MARGIN_TOP;
+    let _ = window.set_position(tauri::Position::Logical(tauri::LogicalPosition::new(x, y)));
+    debug!("Window positioned at ({}, {})", x, y);
+    measured.is_some()
+}
+
+/// Resize the window to the configured dimensions of the current panel mode
+fn resize_panel(window: &WebviewWindow) -> tauri::Result<PanelSizeChanged> {
+    let config = window.state::<ConfigStore>().get();
+    let (width, height) = config.panel_dimensions.get(config.panel_size);
+
+    window.set_size(tauri::Size::Logical(tauri::LogicalSize::new(width, height)))?;
+    debug!("Window resized to {}x{}", width, height);
+    Ok(PanelSizeChanged {
+        mode: config.panel_size,
+        width,
+        height,
+    })
+}
+
+/// Logical width of the window's monitor, `None` while it is not reported
+fn logical_screen_width(window: &WebviewWindow) -> Option<f64> {
+    let monitor = window
+        .primary_monitor()
+        .ok()
+        .flatten()
+        .or_else(|| window.current_monitor().ok().flatten())?;
+    Some(monitor.size().width as f64 / monitor.scale_factor())
+}
+
+/// Fingerprint of the connected monitors, `None` when none are reported
+fn current_layout(window: &WebviewWindow) -> Option<String> {
+    let monitors: Vec<(String, u32, u32)> = window
+        .available_monitors()
+        .ok()?
+        .iter()
+        .map(|monitor| {
+            let size = monitor.size();
+            let name = monitor.name().cloned().unwrap_or_default();
+            (name, size.width, size.height)
+        })
+        .collect();
+
+    if monitors.is_empty() {
+        None
+    } else {
+        Some(layout_fingerprint(&monitors))
+    }
+}
+
+/// Re-apply the saved display arrangement at startup and on monitor hot-plug
+///
+/// The layout fingerprint only covers names and resolutions, so moving the
+/// outputs does not trigger another pass.
+fn spawn_arrangement_keeper(window: WebviewWindow) {
+    tauri::async_runtime::spawn(async move {
+        let mut arranged_for = None;
+        loop {
+            let layout = Some(current_layout(&window));
+            if layout != arranged_for {
+                let app = window.app_handle();
+                reapply_display_arrangement(&app.state::<AppState>(), &app.state::<ConfigStore>())
+                    .await;
+                arranged_for = layout;
+            }
+            tokio::time::sleep(ARRANGEMENT_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Hash of monitor names and resolutions, independent of their order
+///
+/// FNV-1a rather than std's `DefaultHasher`, whose output may change
+/// between Rust releases and would orphan the saved positions.
+fn layout_fingerprint(monitors: &[(String, u32, u32)]) -> String {
+    let mut entries: Vec<String> = monitors
+        .iter()
+        .map(|(name, width, height)| format!("{}:{}x{}", name, width, height))
+        .collect();
+    entries.sort();
+
+    let hash = entries
+        .join(",")
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+/// Hide window and update visibility state
+///
+/// Every hide path goes through here so the frontend always receives
+/// `window-hidden` and can pause its timers.
+fn hide_window(window: &WebviewWindow) -> tauri::Result<()> {
+    window.hide()?;
+    WINDOW_VISIBLE.store(false, Ordering::SeqCst);
+    pause_polling(&window.state::<AppState>());
+    stop_mic_monitor_internal(&window.state::<AppState>());
+
+    let _ = window.emit("window-hidden", ());
+    debug!("Window hidden");
+    Ok(())
+}
+
+/// Whether focus has moved from the panel to another application
+///
+/// Focus going to one of our own child windows, or to a popup the frontend
+/// registered (e.g. a native file picker), does not count.
+fn focus_left_app(window: &WebviewWindow) -> bool {
+    if window.is_focused().unwrap_or(false) {
+        return false;
+    }
+    if window.state::<AppState>().has_child_windows() {
+        debug!("Focus moved to a child window, keeping the panel visible");
+        return false;
+    }
+
+    !window
+        .app_handle()
+        .webview_windows()
+        .values()
+        .any(|w| w.is_focused().unwrap_or(false))
+}
+
+/// Query whether the window is actually visible
+///
+/// The compositor can hide or unmap the window behind our back, so the
+/// window itself is the source of truth. `WINDOW_VISIBLE` is resynced from
+/// it and only used as a fallback when the query fails.
+fn is_window_visible(window: &WebviewWindow) -> bool {
+    match window.is_visible() {
+        Ok(visible) => {
+            if WINDOW_VISIBLE.swap(visible, Ordering::SeqCst) != visible {
+                debug!("Visibility flag was out of sync, now {}", visible);
+            }
+            visible
+        }
+        Err(e) => {
+            warn!("Failed to query window visibility: {}", e);
+            WINDOW_VISIBLE.load(Ordering::SeqCst)
+        }
+    }
+}
+
+/// Toggle window visibility, returning whether the window is now visible
+fn toggle_window_visibility(window: &WebviewWindow) -> tauri::Result<bool> {
+    if is_window_visible(window) {
+        hide_window(window)?;
+        Ok(false)
     } else {
-        show_window(window);
+        show_window(window)?;
+        Ok(true)
     }
 }
 
@@ -266,33 +1113,32 @@ fn toggle_window_visibility(window: &WebviewWindow) {
 /// Toggle window visibility from frontend
 #[tauri::command]
 async fn toggle_window(window: WebviewWindow) -> Result<bool, String> {
-    let visible = WINDOW_VISIBLE.load(Ordering::SeqCst);
-    if visible {
-        window.hide().map_err(|e| e.to_string())?;
-        WINDOW_VISIBLE.store(false, Ordering::SeqCst);
-    } else {
-        window.show().map_err(|e| e.to_string())?;
-        window.set_focus().map_err(|e| e.to_string())?;
-        WINDOW_VISIBLE.store(true, Ordering::SeqCst);
-    }
-    Ok(!visible)
+    toggle_window_visibility(&window).map_err(|e| e.to_string())
 }
 
 /// Close/hide window from frontend (ESC key handler)
 #[tauri::command]
 async fn close_window(window: WebviewWindow) -> Result<(), String> {
-    window.hide().map_err(|e| e.to_string())?;
-    WINDOW_VISIBLE.store(false, Ordering::SeqCst);
-    Ok(())
+    hide_window(&window).map_err(|e| e.to_string())
 }
 
 /// Position window on screen
 ///
+/// The position is saved for the current monitor layout and used whenever
+/// the window is shown on that layout again, so docking and undocking each
+/// get their own spot. It is stored as the top-right corner, so switching
+/// the panel size keeps the right edge where it was put.
+///
 /// Note: On Niri Wayland, window positioning is handled by the compositor.
 /// This command calculates desired position but actual placement depends
 /// on Niri window rules.
 #[tauri::command]
-async fn position_window(window: WebviewWindow, x: i32, y: i32) -> Result<(), String> {
+async fn position_window(
+    window: WebviewWindow,
+    config: tauri::State<'_, ConfigStore>,
+    x: i32,
+    y: i32,
+) -> Result<(), String> {
     // On Wayland/Niri, direct positioning may not work
     // The compositor handles window placement based on rules
     // This is a best-effort attempt
@@ -301,5 +1147,271 @@ async fn position_window(window: WebviewWindow, x: i32, y: i32) -> Result<(), St
             x as f64, y as f64,
         )))
         .map_err(|e| e.to_string())?;
+
+    if let Some(layout) = current_layout(&window) {
+        config
+            .update(|c| {
+                let (width, _) = c.panel_dimensions.get(c.panel_size);
+                let right = x as f64 + width;
+                c.window_positions.insert(layout, (right, y as f64));
+            })
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Payload of `panel-size-changed`, also returned by `set_panel_size`
+#[derive(Debug, Clone, serde::Serialize)]
+struct PanelSizeChanged {
+    mode: PanelSize,
+    width: f64,
+    height: f64,
+}
+
+/// Switch between the compact and expanded panel
+///
+/// The window keeps its top-right corner in place and the mode is saved,
+/// so it is restored on the next start. Emits `panel-size-changed` for the
+/// frontend to adjust its layout.
+#[tauri::command]
+async fn set_panel_size(
+    window: WebviewWindow,
+    config: tauri::State<'_, ConfigStore>,
+    mode: PanelSize,
+) -> Result<PanelSizeChanged, String> {
+    config
+        .update(|c| c.panel_size = mode)
+        .map_err(|e| e.to_string())?;
+
+    let changed = resize_panel(&window).map_err(|e| e.to_string())?;
+    anchor_window(&window);
+
+    let _ = window.emit("panel-size-changed", &changed);
+    info!("Panel size set to {:?}", mode);
+    Ok(changed)
+}
+
+/// Restart Control Centre, e.g. to pick up an edited config file
+///
+/// The socket is released before the new instance starts, otherwise it
+/// would find this one still listening and just toggle it. If the new
+/// instance cannot be started, the listener is brought back and this
+/// instance keeps running.
+#[tauri::command]
+async fn restart_app(app: tauri::AppHandle, window: WebviewWindow) -> Result<(), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Cannot restart: executable path unavailable: {}", e))?;
+    if !exe.is_file() {
+        // Replaced or removed since startup, e.g. by an upgrade
+        return Err(format!(
+            "Cannot restart: {} no longer exists",
+            exe.display()
+        ));
+    }
+
+    release_socket();
+
+    match std::process::Command::new(&exe)
+        .args(std::env::args_os().skip(1))
+        .spawn()
+    {
+        Ok(child) => {
+            info!("Restarting as {} (pid {})", exe.display(), child.id());
+            app.exit(0);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to start {}: {}", exe.display(), e);
+            spawn_socket_listener(window);
+            Err(format!("Cannot restart: {}", e))
+        }
+    }
+}
+
+/// Get the app-id the compositor sees for this window
+///
+/// Use this value in Niri window rules: `match app-id="<value>"`.
+#[tauri::command]
+async fn get_app_id() -> Result<String, String> {
+    APP_ID
+        .get()
+        .cloned()
+        .ok_or_else(|| "App-id not resolved yet".to_string())
+}
+
+/// Whether the IPC socket is listening for toggle signals
+///
+/// Returns `false` if binding failed, in which case relaunching the binary
+/// cannot toggle this window.
+#[tauri::command]
+async fn get_socket_listener_status() -> Result<bool, String> {
+    Ok(SOCKET_LISTENING.load(Ordering::SeqCst))
+}
+
+/// Signal that the frontend has built the panel and the window may be shown
+#[tauri::command]
+async fn frontend_ready(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    debug!("Frontend ready");
+    state.frontend_ready().notify_one();
+    Ok(())
+}
+
+/// Register a popup the webview owns (e.g. a native file picker) so the
+/// focus it takes does not hide the panel
+#[tauri::command]
+async fn child_popup_opened(
+    state: tauri::State<'_, AppState>,
+    label: String,
+) -> Result<(), String> {
+    debug!("Child popup {} opened", label);
+    state.add_child_window(&label);
     Ok(())
 }
+
+/// Unregister a popup registered with `child_popup_opened`
+#[tauri::command]
+async fn child_popup_closed(
+    state: tauri::State<'_, AppState>,
+    label: String,
+) -> Result<(), String> {
+    debug!("Child popup {} closed", label);
+    state.remove_child_window(&label);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::{mpsc, Arc};
+
+    #[test]
+    fn test_socket_listener_starts_and_stops() {
+        let path = "/tmp/test-control-centre-listener.sock";
+        let _ = std::fs::remove_file(path);
+
+        let listener = UnixListener::bind(path).expect("Failed to bind test socket");
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+
+        let flag = Arc::clone(&shutdown);
+        let handle = std::thread::spawn(move || {
+            serve_socket(&listener, &flag, |msg| {
+                let _ = tx.send(msg.to_string());
+            });
+        });
+
+        UnixStream::connect(path)
+            .and_then(|mut stream| stream.write_all(b"toggle"))
+            .expect("Failed to send message");
+        let msg = rx.recv_timeout(Duration::from_secs(2)).expect("No message received");
+        assert_eq!(msg, "toggle");
+
+        // Joins the thread; hangs the test if shutdown doesn't unblock accept()
+        stop_socket_listener(path, &shutdown, handle);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_read_socket_messages() {
+        assert_eq!(read_socket_messages(&b"toggle"[..]), vec!["toggle"]);
+        assert_eq!(
+            read_socket_messages(&b"toggle\n\n  toggle  \n"[..]),
+            vec!["toggle", "toggle"]
+        );
+
+        let mut oversized = vec![b'a'; SOCKET_MAX_LINE + 1];
+        oversized.extend_from_slice(b"\ntoggle\n");
+        assert_eq!(read_socket_messages(&oversized[..]), vec!["toggle"]);
+
+        assert_eq!(
+            read_socket_messages(&b"\xff\xfe\x00\ntoggle\n"[..]),
+            vec!["toggle"]
+        );
+
+        // Whatever lies beyond the read limit is never looked at
+        let mut huge = b"toggle\n".to_vec();
+        huge.extend(vec![b'b'; SOCKET_MAX_READ as usize * 4]);
+        assert_eq!(read_socket_messages(&huge[..]), vec!["toggle"]);
+    }
+
+    #[test]
+    fn test_socket_listener_survives_malformed_payloads() {
+        let path = "/tmp/test-control-centre-malformed.sock";
+        let _ = std::fs::remove_file(path);
+
+        let listener = UnixListener::bind(path).expect("Failed to bind test socket");
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let (tx, rx) = mpsc::channel();
+
+        let flag = Arc::clone(&shutdown);
+        let handle = std::thread::spawn(move || {
+            serve_socket(&listener, &flag, |msg| {
+                let _ = tx.send(msg.to_string());
+            });
+        });
+
+        let payloads: [Vec<u8>; 3] = [
+            vec![b'x'; 1 << 20],
+            vec![0xff, 0xfe, 0xfd, b'\n'],
+            b"reboot\n".to_vec(),
+        ];
+        for payload in payloads {
+            // The listener hangs up once its read limit is reached
+            let _ = UnixStream::connect(path).and_then(|mut stream| stream.write_all(&payload));
+        }
+
+        UnixStream::connect(path)
+            .and_then(|mut stream| stream.write_all(b"toggle\n"))
+            .expect("Listener stopped accepting connections");
+        let msg = rx.recv_timeout(Duration::from_secs(2)).expect("No message received");
+        assert_eq!(msg, "toggle");
+        assert!(rx.try_recv().is_err());
+
+        stop_socket_listener(path, &shutdown, handle);
+
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_bind_falls_back_on_permission_denied() {
+        let primary = Path::new("/tmp/test-control-centre-foreign.sock");
+        let fallback = Path::new("/tmp/test-control-centre-fallback.sock");
+        let _ = std::fs::remove_file(fallback);
+
+        let mut tried = Vec::new();
+        let (listener, path) = bind_with_fallback(primary, fallback, |path| {
+            tried.push(path.to_path_buf());
+            if path == primary {
+                Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+            } else {
+                UnixListener::bind(path)
+            }
+        })
+        .expect("Fallback socket was not bound");
+
+        assert_eq!(path, fallback);
+        assert_eq!(tried, vec![primary.to_path_buf(), fallback.to_path_buf()]);
+        assert!(UnixStream::connect(fallback).is_ok());
+
+        drop(listener);
+        let _ = std::fs::remove_file(fallback);
+    }
+
+    #[test]
+    fn test_layout_fingerprint() {
+        let laptop = ("eDP-1".to_string(), 2880, 1800);
+        let dock = ("DP-3".to_string(), 2560, 1440);
+
+        let docked = layout_fingerprint(&[laptop.clone(), dock.clone()]);
+        assert_eq!(docked, layout_fingerprint(&[dock.clone(), laptop.clone()]));
+        assert_ne!(docked, layout_fingerprint(std::slice::from_ref(&laptop)));
+        assert_ne!(
+            docked,
+            layout_fingerprint(&[laptop, ("DP-3".to_string(), 1920, 1080)])
+        );
+        assert_eq!(docked.len(), 16);
+    }
+}
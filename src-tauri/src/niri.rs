@@ -0,0 +1,402 @@
+//! Niri IPC helpers
+//!
+//! Parses the JSON emitted by `niri msg --json`. Deserialization is
+//! tolerant: every field has a default, and outputs are accepted either as
+//! an object keyed by name (current Niri) or as a plain array.
+//!
+//! Input settings have no IPC, so they are read from and written to the
+//! `input` section of the KDL config, which Niri reloads on change.
+
+use crate::config::{Config, DisplayArrangement};
+use crate::error::{CCResult, ControlCentreError};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// A mode advertised by an output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputMode {
+    #[serde(default)]
+    pub width: u32,
+    #[serde(default)]
+    pub height: u32,
+    /// Refresh rate in millihertz
+    #[serde(default, alias = "refresh")]
+    pub refresh_rate: f64,
+    #[serde(default, alias = "preferred")]
+    pub is_preferred: bool,
+}
+
+impl OutputMode {
+    /// Refresh rate in hertz
+    pub fn refresh_hz(&self) -> f64 {
+        self.refresh_rate / 1000.0
+    }
+
+    /// Mode in the `<width>x<height>@<refresh>` form `niri msg output` accepts
+    pub fn to_mode_string(&self) -> String {
+        format!("{}x{}@{:.3}", self.width, self.height, self.refresh_hz())
+    }
+}
+
+/// Logical placement of an enabled output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogicalOutput {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale: f64,
+}
+
+/// An output as reported by `niri msg --json outputs`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NiriOutput {
+    pub name: String,
+    pub make: String,
+    pub model: String,
+    pub modes: Vec<OutputMode>,
+    /// Index into `modes`; `None` when the output is disabled
+    pub current_mode: Option<usize>,
+    /// `None` when the output is disabled
+    pub logical: Option<LogicalOutput>,
+}
+
+impl NiriOutput {
+    /// Currently active mode, if the output is enabled
+    pub fn current(&self) -> Option<&OutputMode> {
+        self.current_mode.and_then(|index| self.modes.get(index))
+    }
+
+    /// Whether the output is turned on
+    pub fn is_enabled(&self) -> bool {
+        self.current_mode.is_some() || self.logical.is_some()
+    }
+}
+
+/// Parse `niri msg --json outputs`
+pub fn parse_outputs(json: &str) -> CCResult<Vec<NiriOutput>> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| ControlCentreError::ParseError(e.to_string()))?;
+
+    let entries: Vec<(Option<String>, serde_json::Value)> = match value {
+        serde_json::Value::Object(map) => map.into_iter().map(|(k, v)| (Some(k), v)).collect(),
+        serde_json::Value::Array(items) => items.into_iter().map(|v| (None, v)).collect(),
+        other => {
+            return Err(ControlCentreError::ParseError(format!(
+                "Unexpected niri outputs JSON: {}",
+                other
+            )))
+        }
+    };
+
+    let mut outputs = entries
+        .into_iter()
+        .map(|(key, value)| {
+            let mut output: NiriOutput = serde_json::from_value(value)
+                .map_err(|e| ControlCentreError::ParseError(e.to_string()))?;
+            if output.name.is_empty() {
+                output.name = key.unwrap_or_default();
+            }
+            Ok(output)
+        })
+        .collect::<CCResult<Vec<_>>>()?;
+
+    outputs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(outputs)
+}
+
+/// A window as reported by `niri msg --json windows`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NiriWindow {
+    pub id: u64,
+    pub app_id: Option<String>,
+    pub pid: Option<u32>,
+    pub is_floating: bool,
+}
+
+/// Parse `niri msg --json windows`
+pub fn parse_windows(json: &str) -> CCResult<Vec<NiriWindow>> {
+    serde_json::from_str(json).map_err(|e| ControlCentreError::ParseError(e.to_string()))
+}
+
+/// Check that a mode string has the `<width>x<height>[@<refresh>]` form
+pub fn is_valid_mode_string(mode: &str) -> bool {
+    let (size, refresh) = match mode.split_once('@') {
+        Some((size, refresh)) => (size, Some(refresh)),
+        None => (mode, None),
+    };
+
+    let size_ok = size
+        .split_once('x')
+        .is_some_and(|(w, h)| w.parse::<u32>().is_ok() && h.parse::<u32>().is_ok());
+    let refresh_ok = refresh.is_none_or(|r| r.parse::<f64>().is_ok_and(|hz| hz > 0.0));
+
+    size_ok && refresh_ok
+}
+
+/// Compute logical positions of the enabled outputs for an arrangement
+///
+/// Extend keeps the current left-to-right order and places each output
+/// directly right of the previous one, using logical (scaled) widths.
+pub fn arrangement_positions(
+    outputs: &[NiriOutput],
+    arrangement: DisplayArrangement,
+) -> Vec<(String, i32, i32)> {
+    let mut enabled: Vec<(&NiriOutput, &LogicalOutput)> = outputs
+        .iter()
+        .filter_map(|o| o.logical.as_ref().map(|logical| (o, logical)))
+        .collect();
+    enabled.sort_by_key(|(o, logical)| (logical.x, logical.y, o.name.clone()));
+
+    let mut x = 0i32;
+    enabled
+        .into_iter()
+        .map(|(o, logical)| match arrangement {
+            DisplayArrangement::Mirror => (o.name.clone(), 0, 0),
+            DisplayArrangement::Extend => {
+                let position = (o.name.clone(), x, 0);
+                x = x.saturating_add(i32::try_from(logical.width).unwrap_or(i32::MAX));
+                position
+            }
+        })
+        .collect()
+}
+
+/// Path of the Niri config file
+///
+/// `$NIRI_CONFIG` takes precedence over the XDG location, as it does for
+/// Niri itself.
+pub fn config_path() -> PathBuf {
+    match std::env::var_os("NIRI_CONFIG") {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => Config::base_dir().join("niri").join("config.kdl"),
+    }
+}
+
+/// Walk the config line by line, calling `f` with the enclosing block
+/// names and the first token of each line
+///
+/// This is a line-oriented scan, not a KDL parser: it understands the
+/// one-node-per-line layout Niri's default config uses.
+fn scan_blocks(kdl: &str, mut f: impl FnMut(usize, &[&str], &str, &str)) {
+    let mut stack: Vec<&str> = Vec::new();
+
+    for (index, line) in kdl.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or_default().trim();
+        let mut tokens = code.split_whitespace();
+        let Some(first) = tokens.next() else {
+            continue;
+        };
+        let rest = code[first.len()..].trim();
+
+        f(index, &stack, first, rest);
+
+        let opens = code.matches('{').count();
+        let closes = code.matches('}').count();
+        if opens > closes {
+            stack.push(first);
+        }
+        for _ in opens..closes {
+            stack.pop();
+        }
+    }
+}
+
+/// Value of `key` in `input { <device> { ... } }`, if set
+pub fn input_setting(kdl: &str, device: &str, key: &str) -> Option<String> {
+    let mut value = None;
+    scan_blocks(kdl, |_, stack, first, rest| {
+        if stack == ["input", device] && first == key {
+            value = Some(rest.to_string());
+        }
+    });
+    value
+}
+
+/// Set `key` in `input { <device> { ... } }`, adding it if absent
+///
+/// A trailing `//` comment on the replaced line is kept. Returns the
+/// updated config, or `None` if the device block is missing or opens and
+/// closes on one line, where there is nowhere to insert a new line.
+pub fn set_input_setting(kdl: &str, device: &str, key: &str, value: &str) -> Option<String> {
+    let mut existing = None;
+    let mut block = None;
+    scan_blocks(kdl, |index, stack, first, rest| {
+        if stack == ["input", device] && first == key {
+            existing = Some(index);
+        } else if stack == ["input"] && first == device {
+            let open = rest.matches('{').count() > rest.matches('}').count();
+            block = Some((index, open));
+        }
+    });
+
+    let mut lines: Vec<String> = kdl.lines().map(str::to_string).collect();
+    match (existing, block) {
+        (Some(index), _) => {
+            let line = &lines[index];
+            let indent = leading_whitespace(line);
+            lines[index] = match line.find("//") {
+                Some(start) => format!("{}{} {} {}", indent, key, value, &line[start..]),
+                None => format!("{}{} {}", indent, key, value),
+            };
+        }
+        (None, Some((index, true))) => {
+            let indent = leading_whitespace(&lines[index]);
+            lines.insert(index + 1, format!("{}    {} {}", indent, key, value));
+        }
+        (None, _) => return None,
+    }
+
+    let mut updated = lines.join("\n");
+    if kdl.ends_with('\n') {
+        updated.push('\n');
+    }
+    Some(updated)
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUTS_JSON: &str = r#"{
+        "eDP-1": {
+            "name": "eDP-1",
+            "make": "BOE",
+            "model": "0x0BCA",
+            "modes": [
+                {"width": 2560, "height": 1600, "refresh_rate": 165000, "is_preferred": true},
+                {"width": 1920, "height": 1200, "refresh_rate": 60000, "is_preferred": false}
+            ],
+            "current_mode": 0,
+            "vrr_supported": true,
+            "logical": {"x": 0, "y": 0, "width": 1707, "height": 1067, "scale": 1.5, "transform": "Normal"}
+        },
+        "HDMI-A-1": {
+            "name": "HDMI-A-1",
+            "modes": [{"width": 1920, "height": 1080, "refresh_rate": 60000}],
+            "current_mode": null,
+            "logical": null
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_outputs_object() {
+        let outputs = parse_outputs(OUTPUTS_JSON).unwrap();
+        assert_eq!(outputs.len(), 2);
+
+        let edp = outputs.iter().find(|o| o.name == "eDP-1").unwrap();
+        assert!(edp.is_enabled());
+        assert_eq!(edp.current().unwrap().to_mode_string(), "2560x1600@165.000");
+
+        let hdmi = outputs.iter().find(|o| o.name == "HDMI-A-1").unwrap();
+        assert!(!hdmi.is_enabled());
+        assert!(hdmi.current().is_none());
+    }
+
+    #[test]
+    fn test_parse_outputs_array_without_names() {
+        let outputs = parse_outputs(r#"[{"modes": [], "current_mode": null}]"#).unwrap();
+        assert_eq!(outputs.len(), 1);
+        assert!(outputs[0].name.is_empty());
+        assert!(parse_outputs("42").is_err());
+    }
+
+    #[test]
+    fn test_arrangement_positions() {
+        let outputs = parse_outputs(OUTPUTS_JSON).unwrap();
+        let mut external = outputs[0].clone();
+        external.name = "DP-2".to_string();
+        external.logical = Some(LogicalOutput {
+            x: -1920,
+            width: 1920,
+            height: 1080,
+            scale: 1.0,
+            ..Default::default()
+        });
+        let outputs = vec![outputs[1].clone(), external, outputs[0].clone()];
+
+        assert_eq!(
+            arrangement_positions(&outputs, DisplayArrangement::Extend),
+            vec![("DP-2".to_string(), 0, 0), ("eDP-1".to_string(), 1920, 0)]
+        );
+        assert_eq!(
+            arrangement_positions(&outputs, DisplayArrangement::Mirror),
+            vec![("DP-2".to_string(), 0, 0), ("eDP-1".to_string(), 0, 0)]
+        );
+    }
+
+    #[test]
+    fn test_parse_windows() {
+        let json = r#"[
+            {"id": 12, "title": "Control Centre", "app_id": "com.niri.ControlCentre",
+             "pid": 4242, "workspace_id": 1, "is_focused": true, "is_floating": false},
+            {"id": 3, "title": "Terminal", "app_id": null, "pid": null, "workspace_id": 1}
+        ]"#;
+        let windows = parse_windows(json).unwrap();
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].pid, Some(4242));
+        assert!(!windows[0].is_floating);
+        assert_eq!(windows[1].app_id, None);
+        assert!(parse_windows("{}").is_err());
+    }
+
+    #[test]
+    fn test_is_valid_mode_string() {
+        assert!(is_valid_mode_string("1920x1080"));
+        assert!(is_valid_mode_string("1920x1080@60"));
+        assert!(is_valid_mode_string("2560x1600@165.000"));
+        assert!(!is_valid_mode_string("1920"));
+        assert!(!is_valid_mode_string("1920x1080@"));
+        assert!(!is_valid_mode_string("big x small"));
+    }
+
+    const INPUT_KDL: &str = "input {
+    keyboard {
+        xkb {
+            layout \"us\"
+        }
+        repeat-rate 30 // chars per second
+    }
+
+    touchpad {
+        tap
+    }
+}
+
+output \"eDP-1\" {
+    scale 1.5
+}
+";
+
+    #[test]
+    fn test_input_setting() {
+        assert_eq!(input_setting(INPUT_KDL, "keyboard", "repeat-rate").as_deref(), Some("30"));
+        assert_eq!(input_setting(INPUT_KDL, "keyboard", "repeat-delay"), None);
+        assert_eq!(input_setting(INPUT_KDL, "touchpad", "repeat-rate"), None);
+        assert_eq!(input_setting(INPUT_KDL, "xkb", "layout"), None);
+    }
+
+    #[test]
+    fn test_set_input_setting() {
+        let updated = set_input_setting(INPUT_KDL, "keyboard", "repeat-rate", "40").unwrap();
+        assert!(updated.contains("        repeat-rate 40 // chars per second\n"));
+        assert!(!updated.contains("repeat-rate 30"));
+
+        let updated = set_input_setting(&updated, "keyboard", "repeat-delay", "300").unwrap();
+        assert!(updated.contains("    keyboard {\n        repeat-delay 300\n"));
+        assert_eq!(input_setting(&updated, "keyboard", "repeat-delay").as_deref(), Some("300"));
+        assert!(updated.ends_with("}\n"));
+
+        assert!(set_input_setting(INPUT_KDL, "mouse", "accel-speed", "0.5").is_none());
+
+        let inline = "input {\n    keyboard { repeat-rate 30; }\n}\n";
+        assert!(set_input_setting(inline, "keyboard", "repeat-delay", "300").is_none());
+    }
+}
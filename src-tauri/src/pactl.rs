@@ -0,0 +1,379 @@
+//! pactl JSON helpers
+//!
+//! Parses the output of `pactl -f json list sinks|sources|sink-inputs`
+//! (PulseAudio 16+ and pipewire-pulse). Like the Niri parser,
+//! deserialization is tolerant: every field has a default so older or
+//! newer servers with extra or missing keys still parse.
+
+use crate::error::{CCResult, ControlCentreError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A sink port, e.g. speakers or the headphone jack
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Port {
+    pub name: String,
+    pub description: String,
+    /// `false` when the port is reported as not available (unplugged)
+    pub available: bool,
+}
+
+/// Detailed state of a sink
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SinkInfo {
+    /// Server-side index, referenced by sink inputs
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    /// Loudest channel, in percent
+    pub volume: u8,
+    pub muted: bool,
+    /// Hardware reference volume, in percent
+    pub base_volume: u8,
+    pub channels: Vec<String>,
+    pub ports: Vec<Port>,
+    pub active_port: Option<String>,
+    /// Format the sink runs at, `None` if the server did not report it
+    pub sample_spec: Option<SampleSpec>,
+}
+
+/// Sample format, rate and channel count of a sink
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampleSpec {
+    /// PulseAudio format name, e.g. `s16le` or `float32le`
+    pub sample_format: String,
+    /// In Hz
+    pub sample_rate: u32,
+    pub channels: u8,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawVolume {
+    value_percent: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawPort {
+    name: String,
+    description: String,
+    availability: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawSink {
+    index: u32,
+    name: String,
+    description: String,
+    mute: bool,
+    channel_map: String,
+    volume: BTreeMap<String, RawVolume>,
+    base_volume: RawVolume,
+    ports: Vec<RawPort>,
+    active_port: Option<String>,
+    sample_specification: String,
+}
+
+/// An application stream playing to a sink
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SinkInput {
+    pub index: u32,
+    /// Index of the sink the stream plays to
+    pub sink: u32,
+    /// `application.name` property, empty if the client sets none
+    pub app_name: String,
+    /// Loudest channel, in percent
+    pub volume: u8,
+    pub muted: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawSinkInput {
+    index: u32,
+    sink: u32,
+    mute: bool,
+    volume: BTreeMap<String, RawVolume>,
+    properties: BTreeMap<String, serde_json::Value>,
+}
+
+/// A program connected to the sound server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Client {
+    pub index: u32,
+    /// `application.name` property, empty if the client sets none
+    pub app_name: String,
+    /// `application.process.id` property, if reported
+    pub pid: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawClient {
+    index: u32,
+    properties: BTreeMap<String, serde_json::Value>,
+}
+
+/// A string property of a pactl object, empty if missing
+fn property<'a>(properties: &'a BTreeMap<String, serde_json::Value>, key: &str) -> &'a str {
+    properties
+        .get(key)
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+}
+
+/// Parse a percentage such as `"50%"`, saturating at 255
+fn parse_percent(value: &str) -> u8 {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .parse::<u32>()
+        .map(|p| p.min(u8::MAX as u32) as u8)
+        .unwrap_or(0)
+}
+
+/// Loudest channel of a per-channel volume map
+fn max_percent(volume: &BTreeMap<String, RawVolume>) -> u8 {
+    volume
+        .values()
+        .map(|v| parse_percent(&v.value_percent))
+        .max()
+        .unwrap_or(0)
+}
+
+impl From<RawSink> for SinkInfo {
+    fn from(raw: RawSink) -> Self {
+        let volume = max_percent(&raw.volume);
+
+        Self {
+            index: raw.index,
+            name: raw.name,
+            description: raw.description,
+            volume,
+            muted: raw.mute,
+            base_volume: parse_percent(&raw.base_volume.value_percent),
+            channels: raw
+                .channel_map
+                .split(',')
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::to_string)
+                .collect(),
+            ports: raw
+                .ports
+                .into_iter()
+                .map(|p| Port {
+                    available: p.availability != "not available",
+                    name: p.name,
+                    description: p.description,
+                })
+                .collect(),
+            active_port: raw.active_port.filter(|p| !p.is_empty()),
+            sample_spec: parse_sample_spec(&raw.sample_specification),
+        }
+    }
+}
+
+/// Parse a sample specification such as `s16le 2ch 48000Hz`
+///
+/// This is also the value of the `Sample Specification:` line in the
+/// plain `pactl list sinks` output.
+pub fn parse_sample_spec(spec: &str) -> Option<SampleSpec> {
+    let mut fields = spec.split_whitespace();
+    let sample_format = fields.next()?.to_string();
+    let channels = fields.next()?.strip_suffix("ch")?.parse().ok()?;
+    let sample_rate = fields.next()?.strip_suffix("Hz")?.parse().ok()?;
+    if fields.next().is_some() {
+        return None;
+    }
+
+    Some(SampleSpec {
+        sample_format,
+        sample_rate,
+        channels,
+    })
+}
+
+/// Parse `pactl -f json list sinks`
+pub fn parse_sinks(json: &str) -> CCResult<Vec<SinkInfo>> {
+    let raw: Vec<RawSink> = serde_json::from_str(json)
+        .map_err(|e| ControlCentreError::ParseError(format!("Invalid pactl JSON: {}", e)))?;
+    Ok(raw.into_iter().map(SinkInfo::from).collect())
+}
+
+/// Parse `pactl -f json list sources`, which has the same shape as sinks
+pub fn parse_sources(json: &str) -> CCResult<Vec<SinkInfo>> {
+    parse_sinks(json)
+}
+
+/// Parse `pactl -f json list sink-inputs`
+pub fn parse_sink_inputs(json: &str) -> CCResult<Vec<SinkInput>> {
+    let raw: Vec<RawSinkInput> = serde_json::from_str(json)
+        .map_err(|e| ControlCentreError::ParseError(format!("Invalid pactl JSON: {}", e)))?;
+
+    Ok(raw
+        .into_iter()
+        .map(|input| SinkInput {
+            index: input.index,
+            sink: input.sink,
+            app_name: property(&input.properties, "application.name").to_string(),
+            volume: max_percent(&input.volume),
+            muted: input.mute,
+        })
+        .collect())
+}
+
+/// Parse `pactl -f json list clients`
+pub fn parse_clients(json: &str) -> CCResult<Vec<Client>> {
+    let raw: Vec<RawClient> = serde_json::from_str(json)
+        .map_err(|e| ControlCentreError::ParseError(format!("Invalid pactl JSON: {}", e)))?;
+
+    Ok(raw
+        .into_iter()
+        .map(|client| Client {
+            index: client.index,
+            app_name: property(&client.properties, "application.name").to_string(),
+            pid: property(&client.properties, "application.process.id")
+                .parse()
+                .ok(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SINKS_JSON: &str = r#"[{
+        "index": 56,
+        "state": "SUSPENDED",
+        "name": "alsa_output.pci-0000_00_1f.3.analog-stereo",
+        "description": "Built-in Audio Analog Stereo",
+        "sample_specification": "s32le 2ch 48000Hz",
+        "channel_map": "front-left,front-right",
+        "mute": false,
+        "volume": {
+            "front-left": {"value": 32768, "value_percent": "50%", "db": "-18.06 dB"},
+            "front-right": {"value": 39322, "value_percent": "60%", "db": "-13.31 dB"}
+        },
+        "balance": 0.17,
+        "base_volume": {"value": 65536, "value_percent": "100%", "db": "0.00 dB"},
+        "ports": [
+            {"name": "analog-output-speaker", "description": "Speakers", "type": "Speaker",
+             "priority": 10000, "availability": "availability unknown"},
+            {"name": "analog-output-headphones", "description": "Headphones", "type": "Headphones",
+             "priority": 9900, "availability": "not available"}
+        ],
+        "active_port": "analog-output-speaker",
+        "formats": ["pcm"]
+    }, {
+        "name": "bluez_output.AA_BB_CC_DD_EE_FF.1",
+        "mute": true,
+        "channel_map": "mono",
+        "volume": {"mono": {"value_percent": "35%"}},
+        "ports": [],
+        "active_port": null
+    }]"#;
+
+    #[test]
+    fn test_parse_sinks() {
+        let sinks = parse_sinks(SINKS_JSON).unwrap();
+        assert_eq!(sinks.len(), 2);
+
+        let builtin = &sinks[0];
+        assert_eq!(builtin.index, 56);
+        assert_eq!(builtin.description, "Built-in Audio Analog Stereo");
+        assert_eq!(builtin.volume, 60);
+        assert_eq!(builtin.base_volume, 100);
+        assert_eq!(builtin.channels, vec!["front-left", "front-right"]);
+        assert_eq!(builtin.ports.len(), 2);
+        assert_eq!(
+            builtin.sample_spec.as_ref().map(|spec| spec.sample_rate),
+            Some(48000)
+        );
+        assert!(builtin.ports[0].available);
+        assert!(!builtin.ports[1].available);
+        assert_eq!(builtin.active_port.as_deref(), Some("analog-output-speaker"));
+
+        let bluez = &sinks[1];
+        assert!(bluez.muted);
+        assert_eq!(bluez.volume, 35);
+        assert_eq!(bluez.channels, vec!["mono"]);
+        assert!(bluez.active_port.is_none());
+        assert!(bluez.sample_spec.is_none());
+    }
+
+    #[test]
+    fn test_parse_sinks_invalid() {
+        assert!(parse_sinks("Connection failure").is_err());
+        assert!(parse_sinks("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_sink_inputs() {
+        let json = r#"[{
+            "index": 112,
+            "sink": 56,
+            "mute": false,
+            "volume": {"front-left": {"value_percent": "80%"}, "front-right": {"value_percent": "80%"}},
+            "properties": {"application.name": "Firefox", "media.name": "Playback"}
+        }, {
+            "index": 113,
+            "mute": true,
+            "volume": {},
+            "properties": {}
+        }]"#;
+
+        let inputs = parse_sink_inputs(json).unwrap();
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0].index, 112);
+        assert_eq!(inputs[0].sink, 56);
+        assert_eq!(inputs[0].app_name, "Firefox");
+        assert_eq!(inputs[0].volume, 80);
+        assert!(inputs[1].muted);
+        assert!(inputs[1].app_name.is_empty());
+    }
+
+    #[test]
+    fn test_parse_clients() {
+        let json = r#"[{
+            "index": 42,
+            "driver": "protocol-native.c",
+            "owner_module": 9,
+            "properties": {"application.name": "Firefox", "application.process.id": "2345"}
+        }, {
+            "index": 43,
+            "properties": {"application.name": "pipewire"}
+        }]"#;
+
+        let clients = parse_clients(json).unwrap();
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].index, 42);
+        assert_eq!(clients[0].app_name, "Firefox");
+        assert_eq!(clients[0].pid, Some(2345));
+        assert!(clients[1].pid.is_none());
+        assert!(parse_clients("Connection failure").is_err());
+    }
+
+    #[test]
+    fn test_parse_sample_spec() {
+        assert_eq!(
+            parse_sample_spec("s16le 2ch 44100Hz"),
+            Some(SampleSpec {
+                sample_format: "s16le".to_string(),
+                sample_rate: 44100,
+                channels: 2,
+            })
+        );
+
+        let spec = parse_sample_spec("  float32le 6ch 96000Hz\n").unwrap();
+        assert_eq!(spec.sample_format, "float32le");
+        assert_eq!(spec.channels, 6);
+
+        assert_eq!(parse_sample_spec(""), None);
+        assert_eq!(parse_sample_spec("s16le 2 48000"), None);
+        assert_eq!(parse_sample_spec("s16le 2ch 48000Hz extra"), None);
+    }
+}
@@ -2,12 +2,75 @@
 //! 
 //! Manages cached state for system settings to reduce redundant CLI calls.
 
+use crate::command_log::CommandLog;
+use crate::commands::{
+    load_bluetooth_history, AirplaneRestore, Capabilities, Compositor, Escalation,
+    ExternalBrightness, NightLightBackend, PanicMuteRestore, RadioBackends,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tokio::sync::Notify;
 
 /// Cache duration for system state (prevents excessive CLI calls)
 const CACHE_DURATION: Duration = Duration::from_millis(500);
 
+/// Cache duration for DDC monitor brightness, which takes seconds to read
+///
+/// Only the monitor's own buttons change it behind the app's back, so the
+/// startup pre-read is still good when the panel is first opened.
+const DDC_CACHE_DURATION: Duration = Duration::from_secs(60);
+
+/// Minimum time between two invocations of the same power action
+const POWER_ACTION_COOLDOWN: Duration = Duration::from_secs(2);
+
+/// How long a power action confirmation token stays valid
+const POWER_TOKEN_TTL: Duration = Duration::from_secs(10);
+
+/// Largest difference between a set and a read brightness put down to rounding
+const BRIGHTNESS_ROUNDING_TOLERANCE: u8 = 1;
+
+/// Outcome of checking a power action confirmation token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerTokenCheck {
+    Valid,
+    /// No confirmation was requested for the action
+    Missing,
+    Expired,
+    Mismatch,
+}
+
+/// Group of cached values, named like the domains of `AllStates`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheDomain {
+    /// Volume and mute
+    Volume,
+    Brightness,
+    /// WiFi and Bluetooth
+    Network,
+    /// Night Light
+    Display,
+}
+
+impl CacheDomain {
+    pub const ALL: [CacheDomain; 4] = [
+        CacheDomain::Volume,
+        CacheDomain::Brightness,
+        CacheDomain::Network,
+        CacheDomain::Display,
+    ];
+    
+    /// Look up a domain by its serialized name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|domain| serde_json::to_value(domain).is_ok_and(|value| value == name))
+    }
+}
+
 /// State entry with timestamp for cache invalidation
 #[derive(Debug)]
 struct CacheEntry<T> {
@@ -23,12 +86,12 @@ impl<T: Clone> CacheEntry<T> {
         }
     }
     
-    fn is_valid(&self) -> bool {
-        self.timestamp.elapsed() < CACHE_DURATION
+    fn get(&self) -> Option<T> {
+        self.get_within(CACHE_DURATION)
     }
     
-    fn get(&self) -> Option<T> {
-        if self.is_valid() {
+    fn get_within(&self, max_age: Duration) -> Option<T> {
+        if self.timestamp.elapsed() < max_age {
             Some(self.value.clone())
         } else {
             None
@@ -41,20 +104,105 @@ pub struct AppState {
     volume: Mutex<Option<CacheEntry<u8>>>,
     muted: Mutex<Option<CacheEntry<bool>>>,
     brightness: Mutex<Option<CacheEntry<u8>>>,
+    /// Brightness last set through a command, for `settle_brightness`
+    brightness_request: Mutex<Option<(u8, Instant)>>,
+    /// DDC monitor brightness keyed by `ddcutil` display number
+    external_brightness: Mutex<HashMap<u32, CacheEntry<ExternalBrightness>>>,
+    /// Held around `ddcutil` calls, which fail when they share an I2C bus
+    ddc_bus: tokio::sync::Mutex<()>,
+    /// How brightnessctl is run as root, probed on the first privileged write
+    brightness_escalation: tokio::sync::OnceCell<Option<Escalation>>,
     wifi_enabled: Mutex<Option<CacheEntry<bool>>>,
     bluetooth_enabled: Mutex<Option<CacheEntry<bool>>>,
     night_light_enabled: Mutex<Option<CacheEntry<bool>>>,
+    /// Whether several night light daemons are running, cached with Night Light
+    night_light_conflict: Mutex<Option<CacheEntry<bool>>>,
+    /// Daemons found by the last conflict check, so only changes are logged
+    night_light_daemons: Mutex<Vec<&'static str>>,
+    /// Authoritative Night Light backend; sticky once detected (never expires)
+    night_light_backend: Mutex<Option<NightLightBackend>>,
+    /// Compositor detected at startup, selects the IPC used for display features
+    compositor: Compositor,
+    /// WiFi/Bluetooth backends detected at startup
+    radio_backends: RadioBackends,
+    /// Features the frontend can offer, detected at startup
+    capabilities: Capabilities,
+    /// Last start time of each power action, keyed by action name
+    power_actions: Mutex<HashMap<&'static str, Instant>>,
+    /// Outstanding confirmation tokens and their issue time, keyed by action name
+    power_tokens: Mutex<HashMap<&'static str, (String, Instant)>>,
+    /// Interval requested via `start_polling`; `None` when polling is off
+    polling_interval: Mutex<Option<u64>>,
+    /// Running polling task, only present while the window is visible
+    polling_task: Mutex<Option<JoinHandle<()>>>,
+    /// Task reading the microphone for `mic-level` events; owns the recorder
+    mic_monitor: Mutex<Option<JoinHandle<()>>>,
+    /// Notified by the `frontend_ready` command once the webview has built the panel
+    frontend_ready: Notify,
+    /// Open child windows and popups; focus moving to them keeps the panel up
+    child_windows: Mutex<HashSet<String>>,
+    /// Last keyboard color applied through OpenRGB, which cannot read it back
+    kbd_rgb: Mutex<Option<String>>,
+    /// Last non-speaker sink `toggle_audio_output` switched away from
+    other_sink: Mutex<Option<String>>,
+    /// Brightness to restore when the flashlight is turned off; `Some` while on
+    flashlight: Mutex<Option<u8>>,
+    /// Mute states from before `panic_mute`; `Some` while panic muted
+    panic_mute: Mutex<Option<PanicMuteRestore>>,
+    /// Volume from before `duck_volume`; `Some` while ducked
+    duck_origin: Mutex<Option<u8>>,
+    /// Radio states from before airplane mode; `Some` while it is on
+    airplane_mode: Mutex<Option<AirplaneRestore>>,
+    /// Last connection time of each Bluetooth device (Unix seconds) by address
+    bluetooth_history: Mutex<BTreeMap<String, u64>>,
+    /// Bluetooth devices connected at the last observation
+    bluetooth_connected: Mutex<HashSet<String>>,
+    /// Outputs with their own Night Light and its temperature (Kelvin)
+    night_light_outputs: Mutex<BTreeMap<String, u32>>,
+    /// Last screen temperature the app applied globally (Kelvin)
+    night_light_temperature: Mutex<Option<u32>>,
+    /// Recent backend command invocations and per-tool timings
+    command_log: CommandLog,
 }
 
 impl AppState {
     pub fn new() -> Self {
+        let compositor = Compositor::detect();
         Self {
             volume: Mutex::new(None),
             muted: Mutex::new(None),
             brightness: Mutex::new(None),
+            brightness_request: Mutex::new(None),
+            external_brightness: Mutex::new(HashMap::new()),
+            ddc_bus: tokio::sync::Mutex::new(()),
+            brightness_escalation: tokio::sync::OnceCell::new(),
             wifi_enabled: Mutex::new(None),
             bluetooth_enabled: Mutex::new(None),
             night_light_enabled: Mutex::new(None),
+            night_light_conflict: Mutex::new(None),
+            night_light_daemons: Mutex::new(Vec::new()),
+            night_light_backend: Mutex::new(None),
+            compositor,
+            radio_backends: RadioBackends::detect(),
+            capabilities: Capabilities::detect(compositor),
+            power_actions: Mutex::new(HashMap::new()),
+            power_tokens: Mutex::new(HashMap::new()),
+            polling_interval: Mutex::new(None),
+            polling_task: Mutex::new(None),
+            mic_monitor: Mutex::new(None),
+            frontend_ready: Notify::new(),
+            child_windows: Mutex::new(HashSet::new()),
+            kbd_rgb: Mutex::new(None),
+            other_sink: Mutex::new(None),
+            flashlight: Mutex::new(None),
+            panic_mute: Mutex::new(None),
+            duck_origin: Mutex::new(None),
+            airplane_mode: Mutex::new(None),
+            bluetooth_history: Mutex::new(load_bluetooth_history()),
+            bluetooth_connected: Mutex::new(HashSet::new()),
+            night_light_outputs: Mutex::new(BTreeMap::new()),
+            night_light_temperature: Mutex::new(None),
+            command_log: CommandLog::default(),
         }
     }
     
@@ -88,6 +236,211 @@ impl AppState {
         }
     }
     
+    /// Remember a brightness the user asked for
+    pub fn note_brightness_request(&self, value: u8) {
+        if let Ok(mut request) = self.brightness_request.lock() {
+            *request = Some((value, Instant::now()));
+        }
+    }
+    
+    /// Report a freshly read brightness as the value requested within
+    /// `window`, if the two only differ by rounding
+    ///
+    /// brightnessctl works in raw backlight steps, so setting 30% can read
+    /// back as 29% and make the slider jump. Larger differences are real
+    /// changes (e.g. brightness keys) and pass through.
+    pub fn settle_brightness(&self, read: u8, window: Duration) -> u8 {
+        let request = self.brightness_request.lock().ok().and_then(|r| *r);
+        match request {
+            Some((requested, at))
+                if at.elapsed() < window
+                    && read.abs_diff(requested) <= BRIGHTNESS_ROUNDING_TOLERANCE =>
+            {
+                requested
+            }
+            _ => read,
+        }
+    }
+    
+    pub fn get_cached_external_brightness(&self, display: u32) -> Option<ExternalBrightness> {
+        self.external_brightness
+            .lock()
+            .ok()?
+            .get(&display)?
+            .get_within(DDC_CACHE_DURATION)
+    }
+    
+    pub fn set_cached_external_brightness(&self, display: u32, value: ExternalBrightness) {
+        if let Ok(mut cache) = self.external_brightness.lock() {
+            cache.insert(display, CacheEntry::new(value));
+        }
+    }
+    
+    pub fn ddc_bus(&self) -> &tokio::sync::Mutex<()> {
+        &self.ddc_bus
+    }
+    
+    pub(crate) fn brightness_escalation(&self) -> &tokio::sync::OnceCell<Option<Escalation>> {
+        &self.brightness_escalation
+    }
+    
+    pub fn get_kbd_rgb(&self) -> Option<String> {
+        self.kbd_rgb.lock().ok()?.clone()
+    }
+    
+    pub fn set_kbd_rgb(&self, color: String) {
+        if let Ok(mut current) = self.kbd_rgb.lock() {
+            *current = Some(color);
+        }
+    }
+    
+    pub fn get_other_sink(&self) -> Option<String> {
+        self.other_sink.lock().ok()?.clone()
+    }
+    
+    pub fn set_other_sink(&self, sink: String) {
+        if let Ok(mut other) = self.other_sink.lock() {
+            *other = Some(sink);
+        }
+    }
+    
+    pub fn set_flashlight(&self, prior_brightness: u8) {
+        if let Ok(mut flashlight) = self.flashlight.lock() {
+            *flashlight = Some(prior_brightness);
+        }
+    }
+    
+    /// Mark the flashlight off, returning the brightness it replaced
+    pub fn take_flashlight(&self) -> Option<u8> {
+        self.flashlight.lock().ok()?.take()
+    }
+    
+    /// Save the mute states from before a panic mute
+    ///
+    /// A second panic mute keeps the first states, which are the ones
+    /// worth restoring, and adds the streams that started since.
+    pub fn store_panic_mute(&self, restore: PanicMuteRestore) {
+        if let Ok(mut saved) = self.panic_mute.lock() {
+            let Some(first) = saved.as_mut() else {
+                *saved = Some(restore);
+                return;
+            };
+            first.sink = first.sink.take().or(restore.sink);
+            first.source = first.source.take().or(restore.source);
+            for (index, muted) in restore.inputs {
+                if !first.inputs.iter().any(|(saved, _)| *saved == index) {
+                    first.inputs.push((index, muted));
+                }
+            }
+        }
+    }
+    
+    /// End the panic mute, returning the states to restore
+    pub fn take_panic_mute(&self) -> Option<PanicMuteRestore> {
+        self.panic_mute.lock().ok()?.take()
+    }
+    
+    pub fn is_panic_muted(&self) -> bool {
+        self.panic_mute.lock().is_ok_and(|saved| saved.is_some())
+    }
+    
+    pub fn duck_origin(&self) -> Option<u8> {
+        *self.duck_origin.lock().ok()?
+    }
+    
+    /// Save the volume from before a duck
+    ///
+    /// A duck while already ducked keeps the first volume, which is the
+    /// one worth restoring.
+    pub fn store_duck_origin(&self, volume: u8) {
+        if let Ok(mut origin) = self.duck_origin.lock() {
+            origin.get_or_insert(volume);
+        }
+    }
+    
+    /// End the duck, returning the volume to restore
+    pub fn take_duck_origin(&self) -> Option<u8> {
+        self.duck_origin.lock().ok()?.take()
+    }
+    
+    /// Save the radio states from before airplane mode
+    pub fn store_airplane_mode(&self, restore: AirplaneRestore) {
+        if let Ok(mut saved) = self.airplane_mode.lock() {
+            *saved = Some(restore);
+        }
+    }
+    
+    /// End airplane mode, returning the radio states to restore
+    pub fn take_airplane_mode(&self) -> Option<AirplaneRestore> {
+        self.airplane_mode.lock().ok()?.take()
+    }
+    
+    pub fn is_airplane_mode(&self) -> bool {
+        self.airplane_mode.lock().is_ok_and(|saved| saved.is_some())
+    }
+    
+    pub fn bluetooth_history(&self) -> BTreeMap<String, u64> {
+        self.bluetooth_history
+            .lock()
+            .map(|history| history.clone())
+            .unwrap_or_default()
+    }
+    
+    /// Note the Bluetooth devices connected right now
+    ///
+    /// Devices that were not connected at the previous observation get
+    /// `now` as their last connection time. Returns whether the history
+    /// changed.
+    pub fn note_bluetooth_connected(&self, connected: &[String], now: u64) -> bool {
+        let (Ok(mut seen), Ok(mut history)) =
+            (self.bluetooth_connected.lock(), self.bluetooth_history.lock())
+        else {
+            return false;
+        };
+        
+        let mut changed = false;
+        for address in connected.iter().filter(|address| !seen.contains(*address)) {
+            history.insert(address.clone(), now);
+            changed = true;
+        }
+        *seen = connected.iter().cloned().collect();
+        changed
+    }
+    
+    pub fn night_light_outputs(&self) -> BTreeMap<String, u32> {
+        self.night_light_outputs
+            .lock()
+            .map(|outputs| outputs.clone())
+            .unwrap_or_default()
+    }
+    
+    /// Record an output's Night Light temperature, or `None` to turn it off
+    pub fn set_output_night_light(&self, output: &str, temperature: Option<u32>) {
+        if let Ok(mut outputs) = self.night_light_outputs.lock() {
+            match temperature {
+                Some(temperature) => outputs.insert(output.to_string(), temperature),
+                None => outputs.remove(output),
+            };
+        }
+    }
+    
+    pub fn clear_output_night_lights(&self) {
+        if let Ok(mut outputs) = self.night_light_outputs.lock() {
+            outputs.clear();
+        }
+    }
+    
+    pub fn night_light_temperature(&self) -> Option<u32> {
+        *self.night_light_temperature.lock().ok()?
+    }
+    
+    /// Record the temperature just applied, or `None` when it varies (schedules)
+    pub fn set_night_light_temperature(&self, temperature: Option<u32>) {
+        if let Ok(mut current) = self.night_light_temperature.lock() {
+            *current = temperature;
+        }
+    }
+    
     pub fn get_cached_wifi(&self) -> Option<bool> {
         self.wifi_enabled.lock().ok()?.as_ref()?.get()
     }
@@ -118,13 +471,177 @@ impl AppState {
         }
     }
     
+    pub fn get_cached_night_light_conflict(&self) -> Option<bool> {
+        self.night_light_conflict.lock().ok()?.as_ref()?.get()
+    }
+    
+    pub fn set_cached_night_light_conflict(&self, value: bool) {
+        if let Ok(mut cache) = self.night_light_conflict.lock() {
+            *cache = Some(CacheEntry::new(value));
+        }
+    }
+    
+    /// Record the running night light daemons, returning the previous ones
+    pub fn replace_night_light_daemons(&self, running: Vec<&'static str>) -> Vec<&'static str> {
+        self.night_light_daemons
+            .lock()
+            .map(|mut daemons| std::mem::replace(&mut *daemons, running))
+            .unwrap_or_default()
+    }
+    
+    pub fn get_night_light_backend(&self) -> Option<NightLightBackend> {
+        *self.night_light_backend.lock().ok()?
+    }
+    
+    pub fn set_night_light_backend(&self, backend: NightLightBackend) {
+        if let Ok(mut current) = self.night_light_backend.lock() {
+            *current = Some(backend);
+        }
+    }
+    
+    pub fn compositor(&self) -> Compositor {
+        self.compositor
+    }
+    
+    pub fn command_log(&self) -> &CommandLog {
+        &self.command_log
+    }
+    
+    pub fn radio_backends(&self) -> RadioBackends {
+        self.radio_backends
+    }
+    
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+    
+    /// Record the start of a power action
+    ///
+    /// Returns `false` if the same action already started within the
+    /// cooldown window, in which case the caller must not run it again.
+    pub fn try_begin_power_action(&self, action: &'static str) -> bool {
+        let Ok(mut actions) = self.power_actions.lock() else {
+            return false;
+        };
+        
+        let now = Instant::now();
+        if actions
+            .get(action)
+            .is_some_and(|started| now.duration_since(*started) < POWER_ACTION_COOLDOWN)
+        {
+            return false;
+        }
+        
+        actions.insert(action, now);
+        true
+    }
+    
+    /// Store the confirmation token for an action, replacing any earlier one
+    pub fn issue_power_token(&self, action: &'static str, token: String) {
+        if let Ok(mut tokens) = self.power_tokens.lock() {
+            tokens.insert(action, (token, Instant::now()));
+        }
+    }
+    
+    /// Check and consume the confirmation token for an action
+    ///
+    /// Any attempt uses the token up, so a wrong guess cannot be retried.
+    pub fn take_power_token(&self, action: &'static str, token: &str) -> PowerTokenCheck {
+        let pending = self.power_tokens.lock().ok().and_then(|mut t| t.remove(action));
+        let Some((expected, issued)) = pending else {
+            return PowerTokenCheck::Missing;
+        };
+        
+        if issued.elapsed() >= POWER_TOKEN_TTL {
+            PowerTokenCheck::Expired
+        } else if expected != token {
+            PowerTokenCheck::Mismatch
+        } else {
+            PowerTokenCheck::Valid
+        }
+    }
+    
+    pub fn frontend_ready(&self) -> &Notify {
+        &self.frontend_ready
+    }
+    
+    pub fn add_child_window(&self, label: &str) {
+        if let Ok(mut children) = self.child_windows.lock() {
+            children.insert(label.to_string());
+        }
+    }
+    
+    pub fn remove_child_window(&self, label: &str) {
+        if let Ok(mut children) = self.child_windows.lock() {
+            children.remove(label);
+        }
+    }
+    
+    pub fn has_child_windows(&self) -> bool {
+        self.child_windows.lock().is_ok_and(|children| !children.is_empty())
+    }
+    
+    pub fn polling_interval(&self) -> Option<u64> {
+        *self.polling_interval.lock().ok()?
+    }
+    
+    pub fn set_polling_interval(&self, interval_ms: Option<u64>) {
+        if let Ok(mut current) = self.polling_interval.lock() {
+            *current = interval_ms;
+        }
+    }
+    
+    /// Swap the polling task, aborting the previous one
+    pub fn replace_polling_task(&self, task: Option<JoinHandle<()>>) {
+        if let Ok(mut current) = self.polling_task.lock() {
+            if let Some(old) = std::mem::replace(&mut *current, task) {
+                old.abort();
+            }
+        }
+    }
+    
+    /// Swap the microphone monitor, aborting the previous one
+    ///
+    /// Returns whether a monitor was running.
+    pub fn replace_mic_monitor(&self, task: Option<JoinHandle<()>>) -> bool {
+        let Ok(mut current) = self.mic_monitor.lock() else {
+            return false;
+        };
+        match std::mem::replace(&mut *current, task) {
+            Some(old) => {
+                old.abort();
+                true
+            }
+            None => false,
+        }
+    }
+    
+    pub fn invalidate(&self, domain: CacheDomain) {
+        match domain {
+            CacheDomain::Volume => {
+                if let Ok(mut v) = self.volume.lock() { *v = None; }
+                if let Ok(mut v) = self.muted.lock() { *v = None; }
+            }
+            CacheDomain::Brightness => {
+                // DDC values keep their own longer expiry; a poll must not
+                // turn every read into a multi-second `ddcutil getvcp`
+                if let Ok(mut v) = self.brightness.lock() { *v = None; }
+            }
+            CacheDomain::Network => {
+                if let Ok(mut v) = self.wifi_enabled.lock() { *v = None; }
+                if let Ok(mut v) = self.bluetooth_enabled.lock() { *v = None; }
+            }
+            CacheDomain::Display => {
+                if let Ok(mut v) = self.night_light_enabled.lock() { *v = None; }
+                if let Ok(mut v) = self.night_light_conflict.lock() { *v = None; }
+            }
+        }
+    }
+    
     pub fn invalidate_all(&self) {
-        if let Ok(mut v) = self.volume.lock() { *v = None; }
-        if let Ok(mut v) = self.muted.lock() { *v = None; }
-        if let Ok(mut v) = self.brightness.lock() { *v = None; }
-        if let Ok(mut v) = self.wifi_enabled.lock() { *v = None; }
-        if let Ok(mut v) = self.bluetooth_enabled.lock() { *v = None; }
-        if let Ok(mut v) = self.night_light_enabled.lock() { *v = None; }
+        for domain in CacheDomain::ALL {
+            self.invalidate(domain);
+        }
     }
 }
 
@@ -133,3 +650,150 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_power_action_cooldown() {
+        let state = AppState::new();
+        assert!(state.try_begin_power_action("suspend"));
+        assert!(!state.try_begin_power_action("suspend"));
+        assert!(state.try_begin_power_action("reboot"));
+    }
+    
+    #[test]
+    fn test_child_windows() {
+        let state = AppState::new();
+        assert!(!state.has_child_windows());
+        state.add_child_window("file-picker");
+        state.add_child_window("file-picker");
+        assert!(state.has_child_windows());
+        state.remove_child_window("file-picker");
+        assert!(!state.has_child_windows());
+    }
+    
+    #[test]
+    fn test_power_tokens() {
+        let state = AppState::new();
+        assert_eq!(state.take_power_token("reboot", "abc"), PowerTokenCheck::Missing);
+        
+        state.issue_power_token("reboot", "abc".to_string());
+        assert_eq!(state.take_power_token("shutdown", "abc"), PowerTokenCheck::Missing);
+        assert_eq!(state.take_power_token("reboot", "abc"), PowerTokenCheck::Valid);
+        assert_eq!(state.take_power_token("reboot", "abc"), PowerTokenCheck::Missing);
+        
+        state.issue_power_token("reboot", "abc".to_string());
+        assert_eq!(state.take_power_token("reboot", "xyz"), PowerTokenCheck::Mismatch);
+        assert_eq!(state.take_power_token("reboot", "abc"), PowerTokenCheck::Missing);
+    }
+    
+    #[test]
+    fn test_flashlight_restore() {
+        let state = AppState::new();
+        assert_eq!(state.take_flashlight(), None);
+        
+        state.set_flashlight(40);
+        assert_eq!(state.take_flashlight(), Some(40));
+        assert_eq!(state.take_flashlight(), None);
+    }
+    
+    #[test]
+    fn test_invalidate_domain() {
+        let state = AppState::new();
+        state.set_cached_volume(40);
+        state.set_cached_brightness(60);
+        
+        state.invalidate(CacheDomain::Volume);
+        assert!(state.get_cached_volume().is_none());
+        assert_eq!(state.get_cached_brightness(), Some(60));
+        
+        assert_eq!(CacheDomain::from_name("network"), Some(CacheDomain::Network));
+        assert_eq!(CacheDomain::from_name("Network"), None);
+    }
+    
+    #[test]
+    fn test_external_brightness_cache() {
+        let state = AppState::new();
+        let brightness = ExternalBrightness { current: 40, max: 100 };
+        state.set_cached_external_brightness(1, brightness);
+        assert_eq!(state.get_cached_external_brightness(1), Some(brightness));
+        assert_eq!(state.get_cached_external_brightness(2), None);
+        
+        state.invalidate_all();
+        assert_eq!(state.get_cached_external_brightness(1), Some(brightness));
+    }
+    
+    #[test]
+    fn test_settle_brightness() {
+        let state = AppState::new();
+        let window = Duration::from_secs(1);
+        assert_eq!(state.settle_brightness(29, window), 29);
+        
+        state.note_brightness_request(30);
+        assert_eq!(state.settle_brightness(29, window), 30);
+        assert_eq!(state.settle_brightness(31, window), 30);
+        // Changed by something else
+        assert_eq!(state.settle_brightness(50, window), 50);
+        // Hysteresis off
+        assert_eq!(state.settle_brightness(29, Duration::ZERO), 29);
+    }
+    
+    #[test]
+    fn test_panic_mute_keeps_first_states() {
+        let state = AppState::new();
+        assert!(!state.is_panic_muted());
+        
+        let before = PanicMuteRestore {
+            sink: Some(("speakers".to_string(), false)),
+            source: None,
+            inputs: vec![(12, false), (15, true)],
+        };
+        state.store_panic_mute(before.clone());
+        state.store_panic_mute(PanicMuteRestore {
+            sink: Some(("speakers".to_string(), true)),
+            source: Some(("mic".to_string(), false)),
+            inputs: vec![(12, true), (15, true), (20, false)],
+        });
+        assert!(state.is_panic_muted());
+        
+        // Streams and devices that were new to the second panic mute are added
+        let merged = PanicMuteRestore {
+            source: Some(("mic".to_string(), false)),
+            inputs: vec![(12, false), (15, true), (20, false)],
+            ..before
+        };
+        assert_eq!(state.take_panic_mute(), Some(merged));
+        assert!(!state.is_panic_muted());
+        assert_eq!(state.take_panic_mute(), None);
+    }
+    
+    #[test]
+    fn test_note_bluetooth_connected_on_connect() {
+        let state = AppState::new();
+        let headset = "AA:BB:CC:DD:EE:FF".to_string();
+        
+        assert!(state.note_bluetooth_connected(std::slice::from_ref(&headset), 100));
+        assert!(!state.note_bluetooth_connected(std::slice::from_ref(&headset), 200));
+        assert_eq!(state.bluetooth_history().get(&headset), Some(&100));
+        
+        // Reconnecting after a disconnect counts as a new connection
+        assert!(!state.note_bluetooth_connected(&[], 300));
+        assert!(state.note_bluetooth_connected(std::slice::from_ref(&headset), 400));
+        assert_eq!(state.bluetooth_history().get(&headset), Some(&400));
+    }
+    
+    #[test]
+    fn test_duck_origin_keeps_first_volume() {
+        let state = AppState::new();
+        assert_eq!(state.duck_origin(), None);
+        
+        state.store_duck_origin(70);
+        state.store_duck_origin(20);
+        assert_eq!(state.duck_origin(), Some(70));
+        
+        assert_eq!(state.take_duck_origin(), Some(70));
+        assert_eq!(state.take_duck_origin(), None);
+    }
+}
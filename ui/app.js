@@ -62,36 +62,69 @@ function mockInvoke(command, args) {
     };
     
     switch (command) {
-        case 'get_all_states':
+        case 'get_all_states': {
+            const available = (value) => ({ available: true, value, error: null });
             return {
-                volume: { volume: mockState.volume, muted: mockState.muted },
-                brightness: { brightness: mockState.brightness, max_brightness: 100 },
-                network: { 
+                volume: available({ volume: mockState.volume, muted: mockState.muted }),
+                mic: available({ volume: 80, muted: false }),
+                brightness: available({ brightness: mockState.brightness, max_brightness: 100 }),
+                network: available({ 
                     wifi_enabled: mockState.wifi, 
                     wifi_connected: true, 
                     wifi_ssid: 'MockNetwork',
                     bluetooth_enabled: mockState.bluetooth,
-                    bluetooth_connected: false
-                },
-                display: { night_light_enabled: mockState.nightLight }
+                    bluetooth_connected: false,
+                    bluetooth_battery: null,
+                }),
+                display: available({
+                    night_light_enabled: mockState.nightLight,
+                    night_light_from: null,
+                    night_light_to: null,
+                    conflict: false,
+                    night_light_outputs: {},
+                    night_light_temperature: mockState.nightLight ? 4500 : null
+                })
             };
+        }
+        case 'get_fast_states': {
+            const available = (value) => ({ available: true, value, error: null });
+            return {
+                volume: available({ volume: mockState.volume, muted: mockState.muted }),
+                brightness: available({ brightness: mockState.brightness, max_brightness: 100 })
+            };
+        }
         case 'get_volume':
             return mockState.volume;
         case 'set_volume':
-            mockState.volume = args.value;
-            return args.value;
+            mockState.volume = Math.max(0, Math.min(100, args.value));
+            return mockState.volume;
+        case 'adjust_volume':
+            mockState.volume = Math.max(0, Math.min(100, mockState.volume + args.delta));
+            return mockState.volume;
+        case 'nudge_volume':
+            mockState.volume = Math.max(0, Math.min(100, mockState.volume + (args.up ? 2 : -2)));
+            return mockState.volume;
         case 'toggle_mute':
             mockState.muted = !mockState.muted;
             return mockState.muted;
+        case 'set_mute':
+            mockState.muted = args.muted;
+            return mockState.muted;
         case 'get_mute_status':
             return mockState.muted;
         case 'get_brightness':
             return mockState.brightness;
         case 'set_brightness':
-            mockState.brightness = args.value;
-            return args.value;
+            mockState.brightness = Math.max(1, Math.min(100, args.value));
+            return mockState.brightness;
+        case 'adjust_brightness':
+            mockState.brightness = Math.max(1, Math.min(100, mockState.brightness + args.delta));
+            return mockState.brightness;
+        case 'nudge_brightness':
+            mockState.brightness = Math.max(1, Math.min(100, mockState.brightness + (args.up ? 2 : -2)));
+            return mockState.brightness;
         case 'get_wifi_status':
-            return { wifi_enabled: mockState.wifi, wifi_connected: true, wifi_ssid: 'MockNetwork', bluetooth_enabled: mockState.bluetooth, bluetooth_connected: false };
+            return { wifi_enabled: mockState.wifi, wifi_connected: true, wifi_ssid: 'MockNetwork', bluetooth_enabled: mockState.bluetooth, bluetooth_connected: false, bluetooth_battery: null };
         case 'set_wifi_enabled':
             mockState.wifi = args.enabled;
             return args.enabled;
@@ -100,17 +133,159 @@ function mockInvoke(command, args) {
         case 'set_bluetooth_enabled':
             mockState.bluetooth = args.enabled;
             return args.enabled;
+        case 'get_recent_bluetooth_devices':
+            return [
+                {
+                    address: 'AA:BB:CC:DD:EE:01',
+                    name: 'AirPods',
+                    connected: false,
+                    last_connected: 1760000000,
+                },
+                { address: 'AA:BB:CC:DD:EE:02', name: 'Keyboard', connected: false, last_connected: null },
+            ];
+        case 'connect_bluetooth_device':
+            return null;
+        case 'get_airplane_mode':
+            return false;
+        case 'set_airplane_mode':
+            return {
+                enabled: args.enabled,
+                wifi_enabled: !args.enabled,
+                wifi_reconnected: !args.enabled,
+                bluetooth_enabled: !args.enabled,
+                bluetooth_reconnected: args.enabled ? [] : ['WH-1000XM4'],
+            };
         case 'get_night_light_status':
             return mockState.nightLight;
         case 'set_night_light_enabled':
             mockState.nightLight = args.enabled;
-            return args.enabled;
+            return {
+                night_light_enabled: args.enabled,
+                night_light_from: null,
+                night_light_to: null,
+                conflict: false,
+                night_light_outputs: {},
+                night_light_temperature: args.enabled ? 4500 : null
+            };
         case 'suspend_system':
             console.log('[MOCK] System would suspend');
             return null;
+        case 'request_power_action':
+            return 'mock-' + args.action;
+        case 'confirm_power_action':
+            console.log(`[MOCK] System would ${args.action}`);
+            return null;
         case 'close_window':
             console.log('[MOCK] Window would close');
             return null;
+        case 'get_app_id':
+            return 'control-centre';
+        case 'get_socket_listener_status':
+            return true;
+        case 'frontend_ready':
+        case 'start_polling':
+        case 'stop_polling':
+            return null;
+        case 'get_window_opacity':
+            return 0.85;
+        case 'set_window_opacity':
+            return args.level;
+        case 'get_color_scheme':
+            return 'dark';
+        case 'set_color_scheme':
+            return args.scheme;
+        case 'get_service_status':
+            return 'active';
+        case 'set_service':
+            return args.enabled ? 'active' : 'inactive';
+        case 'get_capabilities': {
+            const backends = {
+                audio: true, brightness: true, network: true, bluetooth: true,
+                night_light: true, power: true, media: true
+            };
+            return {
+                compositor: 'niri', backends, niri_ipc: true,
+                battery: true, kbd_backlight: false, has_touchpad: true, timedatectl: true
+            };
+        }
+        case 'restart_app':
+            console.log('[MOCK] App would restart');
+            return null;
+        case 'set_panel_size':
+            return { mode: args.mode, width: 420, height: args.mode === 'expanded' ? 720 : 400 };
+        case 'get_sink_limits':
+            return [];
+        case 'get_sink_format':
+            return { sample_format: 's32le', sample_rate: 48000, channels: 2 };
+        case 'list_audio_clients':
+            return [{ index: 42, app_name: 'Firefox', pid: 2345 }];
+        case 'kill_audio_client':
+            return { index: args.index, app_name: 'Firefox', pid: 2345 };
+        case 'get_night_light_temperature':
+            return { temperature: mockState.nightLight ? 4500 : 6500, source: 'backend' };
+        case 'invalidate_cache':
+            return args.domain ? [args.domain] : ['volume', 'brightness', 'network', 'display'];
+        case 'reset_defaults': {
+            const available = (value) => ({ available: true, value, error: null });
+            mockState.volume = 50;
+            mockState.muted = false;
+            mockState.brightness = 70;
+            mockState.nightLight = false;
+            return {
+                volume: available(50),
+                muted: available(false),
+                brightness: available(70),
+                night_light_enabled: available(false)
+            };
+        }
+        case 'get_primary_connection':
+            return mockState.wifi
+                ? { kind: 'wifi', name: 'MockNetwork', strength: 72 }
+                : { kind: 'none', name: null, strength: null };
+        case 'get_inhibitors':
+            return [];
+        case 'restart_audio_service':
+            console.log('[MOCK] Audio services would restart');
+            return null;
+        case 'duck_volume':
+            return Math.min(args.to, mockState.volume);
+        case 'unduck_volume':
+            return mockState.volume;
+        case 'get_mic_level':
+            return 0.42;
+        case 'start_mic_monitor':
+        case 'stop_mic_monitor':
+            return null;
+        case 'panic_mute':
+        case 'panic_unmute':
+            return { sink: true, source: true, streams: 2 };
+        case 'check_for_update':
+            return { current: '1.0.0', latest: '1.0.0', update_available: false };
+        case 'ensure_floating':
+            return false;
+        case 'get_command_log':
+            return [{
+                command: 'pactl', args: ['get-sink-volume', '@DEFAULT_SINK@'],
+                exit_code: 0, output: 'Volume: front-left: 32768 /  50%', duration_ms: 4
+            }];
+        case 'get_backend_timings':
+            return [{
+                command: 'bluetoothctl', runs: 3, last_ms: 1200, average_ms: 950, max_ms: 1400
+            }];
+        case 'get_notification_mode':
+            return { mode: 'default', available: ['default', 'do-not-disturb'] };
+        case 'set_notification_mode':
+            return { mode: args.mode, available: ['default', 'do-not-disturb'] };
+        case 'get_external_brightness':
+            return { current: 60, max: 100 };
+        case 'get_time_sync_status':
+            return { ntp_enabled: true, synchronized: true };
+        case 'set_ntp':
+            return { ntp_enabled: args.enabled, synchronized: args.enabled };
+        case 'get_system_overview':
+            return { uptime_secs: 35127, load_average: [0.52, 0.61, 0.7] };
+        case 'get_enabled_cards':
+            return ['audio', 'brightness', 'network', 'bluetooth', 'night_light', 'power', 'media'];
         default:
             return null;
     }
@@ -120,6 +295,9 @@ function mockInvoke(command, args) {
 // State Management
 // ============================================================================
 
+/** Interval of backend `states-updated` pushes while the window is visible */
+const POLLING_INTERVAL_MS = 2000;
+
 const state = {
     volume: 50,
     muted: false,
@@ -128,7 +306,19 @@ const state = {
     wifiConnected: false,
     wifiSsid: null,
     bluetoothEnabled: false,
+    bluetoothBattery: null,
     nightLightEnabled: false,
+    // Kelvin applied by Night Light, null while it is off
+    nightLightTemperature: null,
+    // Reason the volume controls do nothing (dummy output), null otherwise
+    audioUnavailable: null,
+    // Whether each backend domain responded (see DomainState in commands.rs)
+    available: {
+        volume: true,
+        brightness: true,
+        network: true,
+        display: true,
+    },
     isLoading: true,
     pendingOperations: new Set(),
 };
@@ -178,12 +368,30 @@ document.addEventListener('DOMContentLoaded', async () => {
     // Set up event listeners
     setupEventListeners();
     
+    // Tell the backend the panel is built so it can show the window. Not
+    // requestAnimationFrame: it never fires while the webview is hidden
+    setTimeout(() => {
+        invoke('frontend_ready').catch(() => {});
+    }, 0);
+    
+    // Hide cards turned off in the config
+    await loadEnabledCards();
+    
+    // Follow the system light/dark preference
+    invoke('get_color_scheme').then(applyColorScheme).catch(() => {});
+    
+    // Background opacity chosen with set_window_opacity
+    invoke('get_window_opacity').then(applyWindowOpacity).catch(() => {});
+    
     // Load initial state
     await loadInitialState();
     
     // Listen for Tauri events
     setupTauriEvents();
     
+    // Keep the panel live; the backend pauses polling while hidden
+    invoke('start_polling', { intervalMs: POLLING_INTERVAL_MS }).catch(() => {});
+    
     // Hide loading overlay
     hideLoading();
 });
@@ -247,10 +455,12 @@ function setupEventListeners() {
     elements.volumeSlider.addEventListener('input', handleVolumeInput);
     elements.volumeSlider.addEventListener('change', handleVolumeChange);
     elements.volumeIconBtn.addEventListener('click', toggleMute);
+    elements.volumeSlider.closest('.slider-row').addEventListener('wheel', handleVolumeWheel, { passive: false });
     
     // Brightness slider
     elements.brightnessSlider.addEventListener('input', handleBrightnessInput);
     elements.brightnessSlider.addEventListener('change', handleBrightnessChange);
+    elements.brightnessSlider.closest('.slider-row').addEventListener('wheel', handleBrightnessWheel, { passive: false });
     
     // Keyboard shortcuts
     document.addEventListener('keydown', handleKeyDown);
@@ -267,6 +477,67 @@ function setupTauriEvents() {
         console.log('Window shown, refreshing state');
         await loadInitialState();
     });
+    
+    // Window hidden: drop transient UI so nothing runs in the background
+    window.__TAURI__.event.listen('window-hidden', () => {
+        console.log('Window hidden, pausing UI timers');
+        pauseTimers();
+    });
+    
+    // Periodic state pushed by the backend while the window is visible
+    window.__TAURI__.event.listen('states-updated', (event) => {
+        // Don't fight an in-flight toggle with a stale snapshot
+        if (state.pendingOperations.size === 0) {
+            applyAllStates(event.payload);
+        }
+    });
+    
+    // Background opacity changed with set_window_opacity
+    window.__TAURI__.event.listen('window-opacity-changed', (event) => {
+        applyWindowOpacity(event.payload);
+    });
+    
+    // System color scheme changed from the panel
+    window.__TAURI__.event.listen('color-scheme-changed', (event) => {
+        applyColorScheme(event.payload);
+    });
+    
+    // Output port switched (e.g. headphones plugged in); volume may differ
+    window.__TAURI__.event.listen('port-changed', async (event) => {
+        console.log('Audio port changed', event.payload);
+        await loadInitialState();
+    });
+    
+    // Paired devices reconnected after Bluetooth was turned on
+    window.__TAURI__.event.listen('bluetooth-reconnected', (event) => {
+        showToast(`Reconnected ${event.payload.join(', ')}`, 'success');
+    });
+    
+    // Battery fell to a warning threshold while discharging
+    window.__TAURI__.event.listen('battery-low', (event) => {
+        showToast(`Battery low: ${event.payload.percent}%`, 'error');
+    });
+    
+    // A backend tool took longer than slow_command_ms
+    window.__TAURI__.event.listen('slow-command', (event) => {
+        showToast(`${event.payload.command} is slow (${event.payload.duration_ms}ms)`, 'error');
+    });
+    
+    // Config file edited; enabled cards may have changed
+    window.__TAURI__.event.listen('config-reloaded', async () => {
+        await loadEnabledCards();
+        await loadInitialState();
+    });
+    
+    // Panel switched between compact and expanded
+    window.__TAURI__.event.listen('panel-size-changed', (event) => {
+        document.body.classList.toggle('panel-expanded', event.payload.mode === 'expanded');
+    });
+    
+    // Backend could not bind its IPC socket; relaunching won't toggle
+    window.__TAURI__.event.listen('socket-listener-failed', () => {
+        showToast('Toggle socket unavailable, relaunch will not toggle', 'error');
+    });
 }
 
 // ============================================================================
@@ -277,21 +548,10 @@ async function loadInitialState() {
     try {
         showLoading();
         
-        const allStates = await invoke('get_all_states');
-        
-        // Update state
-        state.volume = allStates.volume.volume;
-        state.muted = allStates.volume.muted;
-        state.brightness = allStates.brightness.brightness;
-        state.wifiEnabled = allStates.network.wifi_enabled;
-        state.wifiConnected = allStates.network.wifi_connected;
-        state.wifiSsid = allStates.network.wifi_ssid;
-        state.bluetoothEnabled = allStates.network.bluetooth_enabled;
-        state.nightLightEnabled = allStates.display.night_light_enabled;
-        
-        // Update UI
-        updateAllUI();
-        
+        // Paint the cheap domains first; network and Night Light follow
+        applyFastStates(await invoke('get_fast_states'));
+        hideLoading();
+        applyAllStates(await invoke('get_all_states'));
     } catch (error) {
         console.error('Failed to load initial state:', error);
         showToast('Failed to load system state', 'error');
@@ -300,6 +560,61 @@ async function loadInitialState() {
     }
 }
 
+/**
+ * Apply the volume and brightness states from `get_fast_states`
+ */
+function applyFastStates({ volume, brightness }) {
+    state.available.volume = volume.available;
+    state.audioUnavailable = volume.audio_unavailable ? volume.error : null;
+    state.available.brightness = brightness.available;
+    
+    if (volume.available) {
+        state.volume = volume.value.volume;
+        state.muted = volume.value.muted;
+    }
+    if (brightness.available) {
+        state.brightness = brightness.value.brightness;
+    }
+    
+    updateVolumeUI();
+    updateBrightnessUI();
+    updateAvailabilityUI();
+}
+
+/**
+ * Apply an aggregate state from `get_all_states` or a `states-updated` event
+ */
+function applyAllStates({ volume, brightness, network, display }) {
+    // Update state, keeping previous values for unavailable domains
+    state.available.volume = volume.available;
+    state.audioUnavailable = volume.audio_unavailable ? volume.error : null;
+    state.available.brightness = brightness.available;
+    state.available.network = network.available;
+    state.available.display = display.available;
+    
+    if (volume.available) {
+        state.volume = volume.value.volume;
+        state.muted = volume.value.muted;
+    }
+    if (brightness.available) {
+        state.brightness = brightness.value.brightness;
+    }
+    if (network.available) {
+        state.wifiEnabled = network.value.wifi_enabled;
+        state.wifiConnected = network.value.wifi_connected;
+        state.wifiSsid = network.value.wifi_ssid;
+        state.bluetoothEnabled = network.value.bluetooth_enabled;
+        state.bluetoothBattery = network.value.bluetooth_battery;
+    }
+    if (display.available) {
+        state.nightLightEnabled = display.value.night_light_enabled;
+        state.nightLightTemperature = display.value.night_light_temperature;
+    }
+    
+    // Update UI
+    updateAllUI();
+}
+
 // ============================================================================
 // UI Updates
 // ============================================================================
@@ -310,6 +625,78 @@ function updateAllUI() {
     updateWifiUI();
     updateBluetoothUI();
     updateNightLightUI();
+    updateAvailabilityUI();
+}
+
+/**
+ * Switch the panel between its dark and light theme
+ * @param {'light'|'dark'} scheme
+ */
+function applyColorScheme(scheme) {
+    document.documentElement.dataset.theme = scheme;
+}
+
+/**
+ * Apply the panel background opacity (0.0-1.0)
+ */
+function applyWindowOpacity(level) {
+    document.documentElement.style.setProperty('--panel-alpha', level);
+}
+
+/**
+ * Show only the cards enabled in the config (see `enabled_cards`)
+ */
+async function loadEnabledCards() {
+    let cards;
+    try {
+        cards = await invoke('get_enabled_cards');
+    } catch (error) {
+        console.error('Failed to load enabled cards:', error);
+        return;
+    }
+    
+    const cardElements = {
+        audio: elements.volumeSlider.closest('.slider-row'),
+        brightness: elements.brightnessSlider.closest('.slider-row'),
+        network: elements.wifiTile,
+        bluetooth: elements.bluetoothTile,
+        night_light: elements.nightLightTile,
+        power: elements.suspendTile,
+    };
+    
+    for (const [card, element] of Object.entries(cardElements)) {
+        element.hidden = !cards.includes(card);
+    }
+}
+
+/**
+ * Disable controls whose backend is unavailable instead of showing fake data
+ */
+function updateAvailabilityUI() {
+    const setAvailable = (element, available) => {
+        element.dataset.unavailable = !available;
+        element.disabled = !available;
+    };
+    
+    setAvailable(elements.volumeSlider, state.available.volume);
+    setAvailable(elements.volumeIconBtn, state.available.volume);
+    elements.volumeSlider.closest('.slider-row').dataset.unavailable = !state.available.volume;
+    elements.volumeSlider.closest('.slider-row').title = state.audioUnavailable ?? '';
+    
+    setAvailable(elements.brightnessSlider, state.available.brightness);
+    elements.brightnessSlider.closest('.slider-row').dataset.unavailable = !state.available.brightness;
+    
+    setAvailable(elements.wifiTile, state.available.network);
+    setAvailable(elements.bluetoothTile, state.available.network);
+    setAvailable(elements.nightLightTile, state.available.display);
+    
+    if (!state.available.network) {
+        elements.wifiStatus.textContent = 'Unavailable';
+        elements.bluetoothStatus.textContent = 'Unavailable';
+    }
+    if (!state.available.display) {
+        elements.nightLightStatus.textContent = 'Unavailable';
+    }
 }
 
 function updateVolumeUI() {
@@ -349,7 +736,11 @@ function updateWifiUI() {
 
 function updateBluetoothUI() {
     elements.bluetoothTile.dataset.enabled = state.bluetoothEnabled;
-    elements.bluetoothStatus.textContent = state.bluetoothEnabled ? 'On' : 'Off';
+    if (state.bluetoothEnabled && state.bluetoothBattery != null) {
+        elements.bluetoothStatus.textContent = `On · ${state.bluetoothBattery}%`;
+    } else {
+        elements.bluetoothStatus.textContent = state.bluetoothEnabled ? 'On' : 'Off';
+    }
 }
 
 function updateNightLightUI() {
@@ -382,7 +773,9 @@ function handleVolumeChange(e) {
     // Debounce the actual command
     volumeDebounceTimer = setTimeout(async () => {
         try {
-            await invoke('set_volume', { value });
+            // Slider values are clamped rather than rejected
+            state.volume = await invoke('set_volume', { value, mode: 'clamp' });
+            updateVolumeUI();
         } catch (error) {
             // Revert UI on error
             await refreshVolume();
@@ -390,6 +783,17 @@ function handleVolumeChange(e) {
     }, 50);
 }
 
+// Scrolling nudges in fine steps; the backend coalesces bursts
+async function handleVolumeWheel(e) {
+    e.preventDefault();
+    try {
+        state.volume = await invoke('nudge_volume', { up: e.deltaY < 0 });
+        updateVolumeUI();
+    } catch (error) {
+        console.error('Failed to nudge volume:', error);
+    }
+}
+
 async function toggleMute() {
     try {
         state.muted = await invoke('toggle_mute');
@@ -431,13 +835,24 @@ function handleBrightnessChange(e) {
     
     brightnessDebounceTimer = setTimeout(async () => {
         try {
-            await invoke('set_brightness', { value });
+            state.brightness = await invoke('set_brightness', { value, mode: 'clamp' });
+            updateBrightnessUI();
         } catch (error) {
             await refreshBrightness();
         }
     }, 50);
 }
 
+async function handleBrightnessWheel(e) {
+    e.preventDefault();
+    try {
+        state.brightness = await invoke('nudge_brightness', { up: e.deltaY < 0 });
+        updateBrightnessUI();
+    } catch (error) {
+        console.error('Failed to nudge brightness:', error);
+    }
+}
+
 async function refreshBrightness() {
     try {
         state.brightness = await invoke('get_brightness');
@@ -459,8 +874,12 @@ async function toggleWifi() {
         const newState = !state.wifiEnabled;
         elements.wifiTile.dataset.enabled = newState; // Optimistic update
         
-        await invoke('set_wifi_enabled', { enabled: newState });
-        state.wifiEnabled = newState;
+        // The backend returns the state read back from the radio
+        state.wifiEnabled = await invoke('set_wifi_enabled', { enabled: newState });
+        if (state.wifiEnabled !== newState) {
+            updateWifiUI();
+            showToast('WiFi is blocked by a hardware switch', 'error');
+        }
         
         // Refresh to get connection status
         setTimeout(async () => {
@@ -488,9 +907,11 @@ async function toggleBluetooth() {
         const newState = !state.bluetoothEnabled;
         elements.bluetoothTile.dataset.enabled = newState; // Optimistic update
         
-        await invoke('set_bluetooth_enabled', { enabled: newState });
-        state.bluetoothEnabled = newState;
+        state.bluetoothEnabled = await invoke('set_bluetooth_enabled', { enabled: newState });
         updateBluetoothUI();
+        if (state.bluetoothEnabled !== newState) {
+            showToast('Bluetooth is blocked by a hardware switch', 'error');
+        }
         
     } catch (error) {
         // Revert on error
@@ -509,8 +930,9 @@ async function toggleNightLight() {
         const newState = !state.nightLightEnabled;
         elements.nightLightTile.dataset.enabled = newState; // Optimistic update
         
-        await invoke('set_night_light_enabled', { enabled: newState });
-        state.nightLightEnabled = newState;
+        const display = await invoke('set_night_light_enabled', { enabled: newState });
+        state.nightLightEnabled = display.night_light_enabled;
+        state.nightLightTemperature = display.night_light_temperature;
         updateNightLightUI();
         
     } catch (error) {
@@ -606,6 +1028,19 @@ function hideLoading() {
 
 let toastTimer = null;
 
+/**
+ * Cancel pending timers while the window is hidden
+ */
+function pauseTimers() {
+    if (toastTimer) {
+        clearTimeout(toastTimer);
+        toastTimer = null;
+    }
+    if (elements.toast) {
+        elements.toast.classList.remove('show');
+    }
+}
+
 function showToast(message, type = 'info') {
     if (!elements.toast) return;
     
@@ -643,6 +1078,7 @@ if (typeof module !== 'undefined' && module.exports) {
         state,
         invoke,
         loadInitialState,
+        loadEnabledCards,
         toggleWifi,
         toggleBluetooth,
         toggleNightLight,
@@ -103,27 +103,55 @@ describe('IPC Communication', () => {
     });
     
     test('get_all_states returns complete state object', async () => {
+        const available = (value) => ({ available: true, value, error: null });
         const mockState = {
-            volume: { volume: 50, muted: false },
-            brightness: { brightness: 75, max_brightness: 100 },
-            network: {
+            volume: available({ volume: 50, muted: false }),
+            brightness: available({ brightness: 75, max_brightness: 100 }),
+            network: available({
                 wifi_enabled: true,
                 wifi_connected: true,
                 wifi_ssid: 'TestNetwork',
                 bluetooth_enabled: false,
                 bluetooth_connected: false,
-            },
-            display: { night_light_enabled: false },
+            }),
+            display: available({ night_light_enabled: false }),
         };
         
         mockTauri.invoke.mockResolvedValue(mockState);
         
         const result = await mockTauri.invoke('get_all_states');
         
-        expect(result.volume.volume).toBe(50);
-        expect(result.brightness.brightness).toBe(75);
-        expect(result.network.wifi_enabled).toBe(true);
-        expect(result.display.night_light_enabled).toBe(false);
+        expect(result.volume.value.volume).toBe(50);
+        expect(result.brightness.value.brightness).toBe(75);
+        expect(result.network.value.wifi_enabled).toBe(true);
+        expect(result.display.value.night_light_enabled).toBe(false);
+    });
+    
+    test('get_all_states marks missing backends unavailable', async () => {
+        const mockState = {
+            volume: { available: false, value: null, error: 'Command not found: pactl' },
+        };
+        
+        mockTauri.invoke.mockResolvedValue(mockState);
+        
+        const result = await mockTauri.invoke('get_all_states');
+        
+        expect(result.volume.available).toBe(false);
+        expect(result.volume.value).toBeNull();
+        expect(result.volume.error).toContain('pactl');
+    });
+    
+    test('get_all_states reports disabled cards without an error', async () => {
+        const mockState = {
+            volume: { available: false, value: null, error: null },
+        };
+        
+        mockTauri.invoke.mockResolvedValue(mockState);
+        
+        const result = await mockTauri.invoke('get_all_states');
+        
+        expect(result.volume.available).toBe(false);
+        expect(result.volume.error).toBeNull();
     });
 });
 